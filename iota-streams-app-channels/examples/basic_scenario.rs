@@ -1,12 +1,16 @@
 #![allow(non_snake_case)]
 use failure::{
+    bail,
     ensure,
     Fallible,
 };
 use iota_lib_rs::prelude::iota_client;
 use iota_streams_app::{
     message::HasLink,
-    transport::tangle::client::SendTrytesOptions,
+    transport::tangle::{
+        client::SendTrytesOptions,
+        mock::MockNode,
+    },
 };
 use iota_streams_app_channels::{
     api::tangle::{
@@ -14,6 +18,7 @@ use iota_streams_app_channels::{
         Author,
         Subscriber,
         Transport,
+        UnwrappedChannelMessage,
     },
     message,
 };
@@ -73,11 +78,16 @@ where
 
     {
         let msg = transport.recv_message_with_options(&signed_packet_link, recv_opt)?;
-        let preparsed = msg.parse_header()?;
-        ensure!(preparsed.check_content_type(message::signed_packet::TYPE));
-        let (unwrapped_public, unwrapped_masked) = subscriberA.unwrap_signed_packet(preparsed)?;
-        ensure!(public_payload == unwrapped_public);
-        ensure!(masked_payload == unwrapped_masked);
+        match subscriberA.handle_msg(&msg)? {
+            UnwrappedChannelMessage::SignedPacket {
+                public_payload: unwrapped_public,
+                masked_payload: unwrapped_masked,
+            } => {
+                ensure!(public_payload == unwrapped_public);
+                ensure!(masked_payload == unwrapped_masked);
+            }
+            _ => bail!("Expected signed packet."),
+        }
     }
 
     println!("subscribe");
@@ -171,10 +181,25 @@ where
     Ok(())
 }
 
+/// Run the example against IRI node with URI given as the first argument, eg. "https://nodes.devnet.iota.org:443",
+/// or against a local mock node if no URI is given.
 fn main() {
-    let mut client = iota_client::Client::new("https://nodes.devnet.iota.org:443");
     let mut send_opt = SendTrytesOptions::default();
     send_opt.min_weight_magnitude = 10;
     let recv_opt = ();
-    let _result = dbg!(example(&mut client, send_opt, recv_opt));
+
+    let result = match std::env::args().nth(1) {
+        Some(uri) => {
+            let mut client = iota_client::Client::new(&uri);
+            dbg!(example(&mut client, send_opt, recv_opt))
+        }
+        None => {
+            let node = MockNode::start(send_opt.min_weight_magnitude).unwrap();
+            let mut client = iota_client::Client::new(node.uri());
+            dbg!(example(&mut client, send_opt, recv_opt))
+        }
+    };
+    if result.is_err() {
+        std::process::exit(1);
+    }
 }
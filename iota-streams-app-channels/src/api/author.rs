@@ -10,6 +10,7 @@ use std::{
         HashSet,
     },
     fmt::Debug,
+    hash,
     str::FromStr,
 };
 
@@ -35,11 +36,74 @@ use iota_streams_app::message::{
     header::Header,
     *,
 };
-use iota_streams_protobuf3::types::*;
+use iota_streams_protobuf3::{
+    command::*,
+    types::*,
+};
 
-use super::*;
+use super::{
+    branches::Branches,
+    key_history::KeyHistory,
+    *,
+};
 use crate::message::*;
 
+/// Callback deciding whether to trust subscriber's NTRU public key.
+pub type SubscribeApproval<TW, F> = Box<dyn Fn(&ntru::PublicKey<TW, F>) -> bool>;
+
+/// Policy for trusting NTRU public keys received from subscribers in Subscribe messages.
+pub enum SubscribePolicy<TW, F> {
+    /// Trust all subscribers.
+    AcceptAll,
+
+    /// Keep subscribers pending until explicitly approved with `approve_subscriber`.
+    Manual,
+
+    /// Trust subscribers approved by the callback, keep the others pending.
+    Callback(SubscribeApproval<TW, F>),
+}
+
+impl<TW, F> Default for SubscribePolicy<TW, F> {
+    fn default() -> Self {
+        SubscribePolicy::AcceptAll
+    }
+}
+
+/// Policy for changing Author's MSS key before its WOTS one-time keys are exhausted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyRotationPolicy {
+    /// Keys are changed explicitly with `change_key`.
+    Manual,
+
+    /// Change key once at most `threshold` WOTS keys are left on the branch. At least one
+    /// key is always reserved for signing the ChangeKey message itself.
+    Threshold(usize),
+}
+
+impl Default for KeyRotationPolicy {
+    fn default() -> Self {
+        KeyRotationPolicy::Manual
+    }
+}
+
+/// MSS private key valid on the branch of `link_to` which still has WOTS keys left.
+fn signing_mss_sk<'a, TW, P, Rel>(
+    mss_sks: &'a KeyHistory<Rel, mss::PrivateKey<TW, P>>,
+    link_to: &Rel,
+) -> Fallible<&'a mss::PrivateKey<TW, P>>
+where
+    TW: IntTbitWord + SpongosTbitWord,
+    P: mss::Parameters<TW>,
+    Rel: Eq + hash::Hash + Clone,
+{
+    let mss_sk = mss_sks.try_get(link_to)?;
+    ensure!(
+        mss_sk.private_keys_left() > 0,
+        "MSS private key is exhausted, WOTS keys can't be reused."
+    );
+    Ok(mss_sk)
+}
+
 /// Generic Channel Author type parametrised by the type of links, link store and
 /// link generator.
 ///
@@ -58,6 +122,7 @@ use crate::message::*;
 pub struct AuthorT<TW, F, P, Link, Store, LinkGen>
 where
     P: mss::Parameters<TW>,
+    Link: HasLink,
 {
     /// PRNG object used for MSS, NTRU, Spongos key generation, etc.
     prng: prng::Prng<TW, P::PrngG>,
@@ -66,8 +131,9 @@ where
     /// It can be modified before changing keys.
     pub default_mss_height: usize,
 
-    /// Own MSS private key.
-    pub(crate) mss_sk: mss::PrivateKey<TW, P>,
+    /// Own MSS private keys indexed by the link of Announce or ChangeKey message.
+    /// Messages are signed with the key valid on the branch they are linked to.
+    pub(crate) mss_sks: KeyHistory<<Link as HasLink>::Rel, mss::PrivateKey<TW, P>>,
 
     /// Own optional NTRU key pair.
     pub(crate) opt_ntru: Option<(ntru::PrivateKey<TW, F>, ntru::PublicKey<TW, F>)>,
@@ -78,6 +144,22 @@ where
     /// Subscribers' trusted NTRU public keys.
     pub ntru_pks: ntru::NtruPks<TW, F>,
 
+    /// Subscribers registry: NTRU public keys of subscribers indexed by the link
+    /// of their Subscribe message. Only approved keys are added to `ntru_pks`.
+    pub(crate) subscribers: HashMap<<Link as HasLink>::Rel, ntru::PublicKey<TW, F>>,
+
+    /// Policy for approving new subscribers.
+    pub subscribe_policy: SubscribePolicy<TW, F>,
+
+    /// Branches of messages started with keyloads.
+    pub(crate) branches: Branches<<Link as HasLink>::Rel>,
+
+    /// Sign keyloads with own MSS private key.
+    pub sign_keyloads: bool,
+
+    /// Policy for changing MSS key in `sign_packet_with_key_rotation`.
+    pub key_rotation: KeyRotationPolicy,
+
     /// Link store.
     store: RefCell<Store>,
 
@@ -95,7 +177,7 @@ where
     P: mss::Parameters<TW>,
     Link: HasLink + AbsorbExternalFallback<TW, F> + Default + Clone + Eq,
     <Link as HasLink>::Base: Eq + Debug,
-    <Link as HasLink>::Rel: Eq + Debug + Default + SkipFallback<TW, F>,
+    <Link as HasLink>::Rel: Eq + hash::Hash + Clone + Debug + Default + SkipFallback<TW, F>,
     Store: LinkStore<TW, F, <Link as HasLink>::Rel>,
     LinkGen: ChannelLinkGenerator<TW, P, Link>,
 {
@@ -112,11 +194,11 @@ where
         let mss_sk = mss::PrivateKey::<TW, P>::gen(&prng, mss_nonce.slice(), mss_height);
 
         let appinst = link_gen.link_from(mss_sk.public_key());
+        let mut mss_sks = KeyHistory::default();
+        mss_sks.insert(appinst.rel().clone(), mss_sk);
 
         let opt_ntru = if with_ntru {
-            let ntru_nonce = Tbits::<TW>::from_str("NTRUNONCE").unwrap();
-            let key_pair = ntru::gen_keypair::<TW, F, P::PrngG>(&prng, ntru_nonce.slice());
-            Some(key_pair)
+            Some(Self::gen_ntru(&prng))
         } else {
             None
         };
@@ -124,11 +206,16 @@ where
         Self {
             prng: prng,
             default_mss_height: mss_height,
-            mss_sk: mss_sk,
+            mss_sks,
             opt_ntru: opt_ntru,
 
             psks: HashMap::new(),
             ntru_pks: HashSet::new(),
+            subscribers: HashMap::new(),
+            subscribe_policy: SubscribePolicy::default(),
+            branches: Branches::default(),
+            sign_keyloads: false,
+            key_rotation: KeyRotationPolicy::default(),
 
             store: RefCell::new(store),
             link_gen: link_gen,
@@ -136,14 +223,21 @@ where
         }
     }
 
+    /// Generate NTRU key pair, it's completely determined by `prng`.
+    fn gen_ntru(prng: &prng::Prng<TW, P::PrngG>) -> (ntru::PrivateKey<TW, F>, ntru::PublicKey<TW, F>) {
+        let ntru_nonce = Tbits::<TW>::from_str("NTRUNONCE").unwrap();
+        ntru::gen_keypair::<TW, F, P::PrngG>(prng, ntru_nonce.slice())
+    }
+
     /// Prepare Announcement message.
     pub fn prepare_announcement<'a>(
         &'a mut self,
     ) -> Fallible<PreparedMessage<'a, TW, F, Link, Store, announce::ContentWrap<TW, F, P>>> {
         // Create Header for the first message in the channel.
-        let header = self.link_gen.header_from(self.mss_sk.public_key(), announce::TYPE);
+        let mss_sk = signing_mss_sk(&self.mss_sks, self.appinst.rel())?;
+        let header = self.link_gen.header_from(mss_sk.public_key(), announce::TYPE);
         let content = announce::ContentWrap {
-            mss_sk: &self.mss_sk,
+            mss_sk,
             ntru_pk: self.opt_ntru.as_ref().map(|key_pair| &key_pair.1),
         };
         Ok(PreparedMessage::new(self.store.borrow(), header, content))
@@ -155,7 +249,67 @@ where
         info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
     ) -> Fallible<TbinaryMessage<TW, F, Link>> {
         let wrapped = self.prepare_announcement()?.wrap()?;
-        wrapped.commit(self.store.borrow_mut(), info)
+        let msg = wrapped.commit(self.store.borrow_mut(), info)?;
+        // Announce starts the branch of the initial MSS key.
+        self.mss_sks.link_msg(msg.link.rel().clone(), self.appinst.rel());
+        let appinst = self.appinst.rel().clone();
+        self.next_mss_sk(&appinst);
+        Ok(msg)
+    }
+
+    /// Message `msg` linked to `link_to` inherits its MSS key and branch.
+    fn link_msg(&mut self, msg: <Link as HasLink>::Rel, link_to: &<Link as HasLink>::Rel) {
+        self.mss_sks.link_msg(msg.clone(), link_to);
+        self.branches.link_msg(msg, link_to);
+    }
+
+    /// Keyload `msg` linked to `link_to` inherits its MSS key and starts a new branch.
+    fn link_keyload(&mut self, msg: <Link as HasLink>::Rel, link_to: &<Link as HasLink>::Rel) {
+        self.mss_sks.link_msg(msg.clone(), link_to);
+        self.branches.start(msg);
+    }
+
+    /// Tip of the branch started with keyload `root`.
+    pub fn branch_tip(&self, root: &<Link as HasLink>::Rel) -> Option<&<Link as HasLink>::Rel> {
+        self.branches.tip(root)
+    }
+
+    /// Name the branch started with keyload `root`, names are persisted with `export`.
+    /// Branch `name` must be a tryte string.
+    pub fn name_branch(&mut self, name: &str, root: &<Link as HasLink>::Rel) -> Fallible<()> {
+        ensure!(Tbits::<TW>::from_str(name).is_ok(), "Branch name '{}' is not a tryte string.", name);
+        self.branches.name(name.to_string(), root.clone())
+    }
+
+    /// Root of the branch `name`.
+    pub fn branch_root(&self, name: &str) -> Option<&<Link as HasLink>::Rel> {
+        self.branches.named(name)
+    }
+
+    /// Names of the known branches.
+    pub fn branch_names(&self) -> Vec<&str> {
+        self.branches.names().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Switch MSS private key valid on the branch of `link_to` to the next WOTS key
+    /// once it has been used for signing.
+    fn next_mss_sk(&mut self, link_to: &<Link as HasLink>::Rel) {
+        if let Some(mss_sk) = self.mss_sks.get_mut(link_to) {
+            mss_sk.next();
+        }
+    }
+
+    /// Number of WOTS keys left in MSS private key valid on the branch of `link_to`.
+    pub fn mss_keys_left(&self, link_to: &<Link as HasLink>::Rel) -> Fallible<usize> {
+        Ok(self.mss_sks.try_get(link_to)?.private_keys_left())
+    }
+
+    /// Does `key_rotation` policy require changing key before signing a message linked to `link_to`?
+    pub fn needs_key_change(&self, link_to: &<Link as HasLink>::Rel) -> Fallible<bool> {
+        match self.key_rotation {
+            KeyRotationPolicy::Manual => Ok(false),
+            KeyRotationPolicy::Threshold(threshold) => Ok(self.mss_keys_left(link_to)? <= threshold.max(1)),
+        }
     }
 
     /// Prepare ChangeKey message: generate new MSS key pair.
@@ -163,32 +317,37 @@ where
         &'a mut self,
         link_to: &'a <Link as HasLink>::Rel,
     ) -> Fallible<PreparedMessage<'a, TW, F, Link, Store, change_key::ContentWrap<'a, TW, P, Link>>> {
-        let mss_nonce = self.mss_sk.nonce().clone();
+        // MSS key is determined by PRNG and nonce, so the nonce must be unique.
+        let mss_nonce = prng::random_nonce(spongos::Spongos::<TW, F>::NONCE_SIZE);
         let mss_sk = mss::PrivateKey::gen(&self.prng, mss_nonce.slice(), self.default_mss_height);
 
         let header = self.link_gen.header_from(link_to, change_key::TYPE);
 
-        let content = change_key::ContentWrap::new(link_to, mss_sk, &self.mss_sk);
+        let mss_linked_sk = signing_mss_sk(&self.mss_sks, link_to)?;
+        let content = change_key::ContentWrap::new(link_to, mss_sk, mss_linked_sk);
         Ok(PreparedMessage::new(self.store.borrow(), header, content))
     }
 
-    /// Generate a new MSS key pair, create change key message linked to the `link_to`
-    /// and replace the current MSS key pair with the newly generated one.
+    /// Generate a new MSS key pair and create change key message linked to the `link_to`.
+    /// The new key is used for messages linked to the ChangeKey message, old keys are
+    /// retained and used for messages linked to the old branches.
     pub fn change_key(
         &mut self,
         link_to: &<Link as HasLink>::Rel,
         info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
     ) -> Fallible<TbinaryMessage<TW, F, Link>> {
-        let (wrapped, mss_sk) = {
+        let (wrapped, mut mss_sk) = {
             let prepared = self.prepare_change_key(link_to)?;
             let wrapped = prepared.wrap()?;
-
-            // Update MSS private key, drop the old one.
-            //TODO: Return the old MSS key or add a container of MSS private keys?
             (wrapped, prepared.content.mss_sk)
         };
-        self.mss_sk = mss_sk;
-        wrapped.commit(self.store.borrow_mut(), info)
+        let msg = wrapped.commit(self.store.borrow_mut(), info)?;
+        // Both the new and the linked keys have been used for signing.
+        mss_sk.next();
+        self.next_mss_sk(link_to);
+        self.branches.link_msg(msg.link.rel().clone(), link_to);
+        self.mss_sks.insert(msg.link.rel().clone(), mss_sk);
+        Ok(msg)
     }
 
     fn do_prepare_keyload<'a, Psks, NtruPks>(
@@ -197,7 +356,7 @@ where
         link_to: &'a <Link as HasLink>::Rel,
         psks: Psks,
         ntru_pks: NtruPks,
-    ) -> Fallible<PreparedMessage<'a, TW, F, Link, Store, keyload::ContentWrap<'a, TW, F, P::PrngG, Link, Psks, NtruPks>>>
+    ) -> Fallible<PreparedMessage<'a, TW, F, Link, Store, keyload::ContentWrap<'a, TW, F, P::PrngG, P, Link, Psks, NtruPks>>>
     where
         Psks: Clone + ExactSizeIterator<Item = psk::IPsk<'a, TW>>,
         NtruPks: Clone + ExactSizeIterator<Item = ntru::INtruPk<'a, TW, F>>,
@@ -211,6 +370,11 @@ where
             psks: psks,
             prng: &self.prng,
             ntru_pks: ntru_pks,
+            mss_sk: if self.sign_keyloads {
+                Some(signing_mss_sk(&self.mss_sks, link_to)?)
+            } else {
+                None
+            },
             _phantom: std::marker::PhantomData,
         };
         Ok(PreparedMessage::new(self.store.borrow(), header, content))
@@ -233,6 +397,7 @@ where
                 TW,
                 F,
                 P::PrngG,
+                P,
                 Link,
                 std::vec::IntoIter<psk::IPsk<'a, TW>>,
                 std::vec::IntoIter<ntru::INtruPk<'a, TW, F>>,
@@ -260,6 +425,7 @@ where
                 TW,
                 F,
                 P::PrngG,
+                P,
                 Link,
                 std::collections::hash_map::Iter<psk::PskId<TW>, psk::Psk<TW>>,
                 std::collections::hash_set::Iter<ntru::PublicKey<TW, F>>,
@@ -282,7 +448,12 @@ where
         info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
     ) -> Fallible<TbinaryMessage<TW, F, Link>> {
         let wrapped = self.prepare_keyload(link_to, psk_ids, ntru_pkids)?.wrap()?;
-        wrapped.commit(self.store.borrow_mut(), info)
+        let msg = wrapped.commit(self.store.borrow_mut(), info)?;
+        self.link_keyload(msg.link.rel().clone(), link_to);
+        if self.sign_keyloads {
+            self.next_mss_sk(link_to);
+        }
+        Ok(msg)
     }
 
     /// Create keyload message with a new session key shared with all Subscribers
@@ -293,7 +464,137 @@ where
         info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
     ) -> Fallible<TbinaryMessage<TW, F, Link>> {
         let wrapped = self.prepare_keyload_for_everyone(link_to)?.wrap()?;
-        wrapped.commit(self.store.borrow_mut(), info)
+        let msg = wrapped.commit(self.store.borrow_mut(), info)?;
+        self.link_keyload(msg.link.rel().clone(), link_to);
+        if self.sign_keyloads {
+            self.next_mss_sk(link_to);
+        }
+        Ok(msg)
+    }
+
+    fn do_prepare_keyload_private<'a, Psks, NtruPks>(
+        &'a self,
+        header: Header<TW, Link>,
+        link_to: &'a <Link as HasLink>::Rel,
+        psks: Psks,
+        ntru_pks: NtruPks,
+    ) -> Fallible<PreparedMessage<'a, TW, F, Link, Store, private_keyload::ContentWrap<'a, TW, F, P::PrngG, P, Link, Psks, NtruPks>>>
+    where
+        Psks: Clone + ExactSizeIterator<Item = psk::IPsk<'a, TW>>,
+        NtruPks: Clone + ExactSizeIterator<Item = ntru::INtruPk<'a, TW, F>>,
+    {
+        let nonce = NTrytes(prng::random_nonce(spongos::Spongos::<TW, F>::NONCE_SIZE));
+        let key = NTrytes(prng::random_key(spongos::Spongos::<TW, F>::KEY_SIZE));
+        let content = private_keyload::ContentWrap {
+            link: link_to,
+            nonce,
+            key,
+            psks,
+            prng: &self.prng,
+            ntru_pks,
+            mss_sk: if self.sign_keyloads {
+                Some(signing_mss_sk(&self.mss_sks, link_to)?)
+            } else {
+                None
+            },
+            _phantom: std::marker::PhantomData,
+        };
+        Ok(PreparedMessage::new(self.store.borrow(), header, content))
+    }
+
+    /// Prepare PrivateKeyload message, see `private_keyload` module.
+    pub fn prepare_keyload_private<'a>(
+        &'a mut self,
+        link_to: &'a <Link as HasLink>::Rel,
+        psk_ids: &psk::PskIds<TW>,
+        ntru_pkids: &ntru::NtruPkids<TW>,
+    ) -> Fallible<
+        PreparedMessage<
+            'a,
+            TW,
+            F,
+            Link,
+            Store,
+            private_keyload::ContentWrap<
+                'a,
+                TW,
+                F,
+                P::PrngG,
+                P,
+                Link,
+                std::vec::IntoIter<psk::IPsk<'a, TW>>,
+                std::vec::IntoIter<ntru::INtruPk<'a, TW, F>>,
+            >,
+        >,
+    > {
+        let header = self.link_gen.header_from(link_to, private_keyload::TYPE);
+        let psks = psk::filter_psks(&self.psks, psk_ids);
+        let ntru_pks = ntru::filter_ntru_pks(&self.ntru_pks, ntru_pkids);
+        self.do_prepare_keyload_private(header, link_to, psks.into_iter(), ntru_pks.into_iter())
+    }
+
+    /// Prepare PrivateKeyload message for all Subscribers known to Author.
+    pub fn prepare_keyload_private_for_everyone<'a>(
+        &'a mut self,
+        link_to: &'a <Link as HasLink>::Rel,
+    ) -> Fallible<
+        PreparedMessage<
+            'a,
+            TW,
+            F,
+            Link,
+            Store,
+            private_keyload::ContentWrap<
+                'a,
+                TW,
+                F,
+                P::PrngG,
+                P,
+                Link,
+                std::collections::hash_map::Iter<'a, psk::PskId<TW>, psk::Psk<TW>>,
+                std::collections::hash_set::Iter<'a, ntru::PublicKey<TW, F>>,
+            >,
+        >,
+    > {
+        let header = self.link_gen.header_from(link_to, private_keyload::TYPE);
+        let ipsks = self.psks.iter();
+        let intru_pks = self.ntru_pks.iter();
+        self.do_prepare_keyload_private(header, link_to, ipsks, intru_pks)
+    }
+
+    /// Create keyload message with a new session key shared with recipients
+    /// identified by pre-shared key IDs and by NTRU public key IDs. Unlike `share_keyload`
+    /// the identities of the recipients are not revealed in the message.
+    pub fn share_keyload_private(
+        &mut self,
+        link_to: &<Link as HasLink>::Rel,
+        psk_ids: &psk::PskIds<TW>,
+        ntru_pkids: &ntru::NtruPkids<TW>,
+        info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
+    ) -> Fallible<TbinaryMessage<TW, F, Link>> {
+        let wrapped = self.prepare_keyload_private(link_to, psk_ids, ntru_pkids)?.wrap()?;
+        let msg = wrapped.commit(self.store.borrow_mut(), info)?;
+        self.link_keyload(msg.link.rel().clone(), link_to);
+        if self.sign_keyloads {
+            self.next_mss_sk(link_to);
+        }
+        Ok(msg)
+    }
+
+    /// Create keyload message with a new session key shared with all Subscribers
+    /// known to Author without revealing their identities.
+    pub fn share_keyload_private_for_everyone(
+        &mut self,
+        link_to: &<Link as HasLink>::Rel,
+        info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
+    ) -> Fallible<TbinaryMessage<TW, F, Link>> {
+        let wrapped = self.prepare_keyload_private_for_everyone(link_to)?.wrap()?;
+        let msg = wrapped.commit(self.store.borrow_mut(), info)?;
+        self.link_keyload(msg.link.rel().clone(), link_to);
+        if self.sign_keyloads {
+            self.next_mss_sk(link_to);
+        }
+        Ok(msg)
     }
 
     /// Prepare SignedPacket message.
@@ -308,13 +609,14 @@ where
             link: link_to,
             public_payload: public_payload,
             masked_payload: masked_payload,
-            mss_sk: &self.mss_sk,
+            mss_sk: signing_mss_sk(&self.mss_sks, link_to)?,
             _phantom: std::marker::PhantomData,
         };
         Ok(PreparedMessage::new(self.store.borrow(), header, content))
     }
 
     /// Create a signed message with public and masked payload.
+    /// Fails if MSS private key valid on the branch of `link_to` is exhausted.
     pub fn sign_packet(
         &mut self,
         link_to: &<Link as HasLink>::Rel,
@@ -325,7 +627,33 @@ where
         let wrapped = self
             .prepare_signed_packet(link_to, public_payload, masked_payload)?
             .wrap()?;
-        wrapped.commit(self.store.borrow_mut(), info)
+        let msg = wrapped.commit(self.store.borrow_mut(), info)?;
+        self.link_msg(msg.link.rel().clone(), link_to);
+        self.next_mss_sk(link_to);
+        Ok(msg)
+    }
+
+    /// Create a signed message, change key first if required by `key_rotation` policy.
+    ///
+    /// Returns the ChangeKey message, if any, and the signed message. The signed message
+    /// is linked to the ChangeKey message if the key has been changed, and to `link_to` otherwise.
+    pub fn sign_packet_with_key_rotation(
+        &mut self,
+        link_to: &<Link as HasLink>::Rel,
+        public_payload: &Trytes<TW>,
+        masked_payload: &Trytes<TW>,
+        change_key_info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
+        info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
+    ) -> Fallible<(Option<TbinaryMessage<TW, F, Link>>, TbinaryMessage<TW, F, Link>)> {
+        if self.needs_key_change(link_to)? {
+            let change_key = self.change_key(link_to, change_key_info)?;
+            let change_key_link = change_key.link.rel().clone();
+            let msg = self.sign_packet(&change_key_link, public_payload, masked_payload, info)?;
+            Ok((Some(change_key), msg))
+        } else {
+            let msg = self.sign_packet(link_to, public_payload, masked_payload, info)?;
+            Ok((None, msg))
+        }
     }
 
     /// Prepare TaggedPacket message.
@@ -337,7 +665,7 @@ where
     ) -> Fallible<PreparedMessage<'a, TW, F, Link, Store, tagged_packet::ContentWrap<'a, TW, F, Link>>> {
         let header = self.link_gen.header_from(link_to, tagged_packet::TYPE);
         let content = tagged_packet::ContentWrap {
-            link: link_to,
+            msgid: link_to,
             public_payload: public_payload,
             masked_payload: masked_payload,
             _phantom: std::marker::PhantomData,
@@ -357,7 +685,9 @@ where
         let wrapped = self
             .prepare_tagged_packet(link_to, public_payload, masked_payload)?
             .wrap()?;
-        wrapped.commit(self.store.borrow_mut(), info)
+        let msg = wrapped.commit(self.store.borrow_mut(), info)?;
+        self.link_msg(msg.link.rel().clone(), link_to);
+        Ok(msg)
     }
 
     fn ensure_appinst<'a>(&self, preparsed: &PreparsedMessage<'a, TW, F, Link>) -> Fallible<()> {
@@ -394,6 +724,7 @@ where
                 'b,
                 TW,
                 F,
+                P,
                 Link,
                 Self,
                 for<'c> fn(&'c Self, &psk::PskId<TW>) -> Option<&'c psk::Psk<TW>>,
@@ -406,6 +737,7 @@ where
             'b,
             TW,
             F,
+            P,
             Link,
             Self,
             for<'c> fn(&'c Self, &psk::PskId<TW>) -> Option<&'c psk::Psk<TW>>,
@@ -420,12 +752,39 @@ where
         preparsed: PreparsedMessage<'a, TW, F, Link>,
         info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
     ) -> Fallible<()> {
-        let _content = self.unwrap_keyload(preparsed)?.commit(self.store.borrow_mut(), info)?;
+        let unwrapped = self.unwrap_keyload(preparsed)?;
+        let link = unwrapped.link.rel().clone();
+        let content = unwrapped.commit(self.store.borrow_mut(), info)?;
+        self.link_keyload(link, &content.link);
         // Unwrapped nonce and key in content are not used explicitly.
         // The resulting spongos state is joined into a protected message state.
         Ok(())
     }
 
+    pub fn unwrap_keyload_private<'a, 'b>(
+        &'b self,
+        preparsed: PreparsedMessage<'a, TW, F, Link>,
+    ) -> Fallible<UnwrappedMessage<TW, F, Link, private_keyload::ContentUnwrap<'b, TW, F, P, Link>>> {
+        self.ensure_appinst(&preparsed)?;
+        let psks = self.psks.iter().collect();
+        let ntru_sk = self.opt_ntru.as_ref().map(|(ntru_sk, _)| ntru_sk);
+        let content = private_keyload::ContentUnwrap::new(psks, ntru_sk);
+        preparsed.unwrap(&*self.store.borrow(), content)
+    }
+
+    /// Try unwrapping session key from PrivateKeyload using known pre-shared keys or NTRU private key (if any).
+    pub fn handle_keyload_private<'a>(
+        &mut self,
+        preparsed: PreparsedMessage<'a, TW, F, Link>,
+        info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
+    ) -> Fallible<()> {
+        let unwrapped = self.unwrap_keyload_private(preparsed)?;
+        let link = unwrapped.link.rel().clone();
+        let content = unwrapped.commit(self.store.borrow_mut(), info)?;
+        self.link_keyload(link, &content.link);
+        Ok(())
+    }
+
     pub fn unwrap_tagged_packet<'a>(
         &self,
         preparsed: PreparsedMessage<'a, TW, F, Link>,
@@ -441,9 +800,10 @@ where
         preparsed: PreparsedMessage<'a, TW, F, Link>,
         info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
     ) -> Fallible<(Trytes<TW>, Trytes<TW>)> {
-        let content = self
-            .unwrap_tagged_packet(preparsed)?
-            .commit(self.store.borrow_mut(), info)?;
+        let unwrapped = self.unwrap_tagged_packet(preparsed)?;
+        let link = unwrapped.link.rel().clone();
+        let content = unwrapped.commit(self.store.borrow_mut(), info)?;
+        self.link_msg(link, &content.msgid);
         Ok((content.public_payload, content.masked_payload))
     }
 
@@ -460,22 +820,63 @@ where
         }
     }
 
-    /// Get public payload, decrypt masked payload and verify MAC.
+    /// Decrypt subscriber's NTRU public key and register the subscriber.
+    /// The key is trusted, ie. added to `ntru_pks`, only if approved by `subscribe_policy`.
     pub fn handle_subscribe<'a>(
         &mut self,
         preparsed: PreparsedMessage<'a, TW, F, Link>,
         info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
     ) -> Fallible<()> {
-        let content = self
-            .unwrap_subscribe(preparsed)?
-            .commit(self.store.borrow_mut(), info)?;
-        //TODO: trust content.subscriber_ntru_pk and add to the list of subscribers only if trusted.
+        let unwrapped = self.unwrap_subscribe(preparsed)?;
+        let link = unwrapped.link.rel().clone();
+        let content = unwrapped.commit(self.store.borrow_mut(), info)?;
+        let link_to = content.link.clone();
         let subscriber_ntru_pk = content.subscriber_ntru_pk;
-        self.ntru_pks.insert(subscriber_ntru_pk);
+        self.link_msg(link.clone(), &link_to);
+        let approved = match &self.subscribe_policy {
+            SubscribePolicy::AcceptAll => true,
+            SubscribePolicy::Manual => false,
+            SubscribePolicy::Callback(approve) => approve(&subscriber_ntru_pk),
+        };
+        if approved {
+            self.ntru_pks.insert(subscriber_ntru_pk.clone());
+        }
+        self.subscribers.insert(link, subscriber_ntru_pk);
         // Unwrapped unsubscribe_key is not used explicitly.
+        // The resulting spongos state is joined by Unsubscribe message.
         Ok(())
     }
 
+    /// Trust NTRU public key of the subscriber registered with Subscribe message `link`.
+    pub fn approve_subscriber(&mut self, link: &<Link as HasLink>::Rel) -> Fallible<()> {
+        if let Some(subscriber_ntru_pk) = self.subscribers.get(link) {
+            self.ntru_pks.insert(subscriber_ntru_pk.clone());
+            Ok(())
+        } else {
+            bail!("Subscriber {:?} is not registered.", link)
+        }
+    }
+
+    /// Remove the subscriber registered with Subscribe message `link` and stop trusting its key.
+    pub fn remove_subscriber(&mut self, link: &<Link as HasLink>::Rel) -> Fallible<()> {
+        if let Some(subscriber_ntru_pk) = self.subscribers.remove(link) {
+            // The same key may be registered with another Subscribe message.
+            if !self.subscribers.values().any(|ntru_pk| *ntru_pk == subscriber_ntru_pk) {
+                self.ntru_pks.remove(&subscriber_ntru_pk);
+            }
+            Ok(())
+        } else {
+            bail!("Subscriber {:?} is not registered.", link)
+        }
+    }
+
+    /// Is the subscriber registered with Subscribe message `link` trusted?
+    pub fn is_subscriber_approved(&self, link: &<Link as HasLink>::Rel) -> bool {
+        self.subscribers
+            .get(link)
+            .map_or(false, |subscriber_ntru_pk| self.ntru_pks.contains(subscriber_ntru_pk))
+    }
+
     pub fn unwrap_unsubscribe<'a>(
         &self,
         preparsed: PreparsedMessage<'a, TW, F, Link>,
@@ -485,30 +886,49 @@ where
         preparsed.unwrap(&*self.store.borrow(), content)
     }
 
-    /// Get public payload, decrypt masked payload and verify MAC.
+    /// Verify MAC and remove the subscriber, so that it's not included into keyloads for everyone.
     pub fn handle_unsubscribe<'a>(
         &mut self,
         preparsed: PreparsedMessage<'a, TW, F, Link>,
         info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
     ) -> Fallible<()> {
-        let _content = self
-            .unwrap_unsubscribe(preparsed)?
-            .commit(self.store.borrow_mut(), info)?;
-        Ok(())
+        let unwrapped = self.unwrap_unsubscribe(preparsed)?;
+        let link = unwrapped.link.rel().clone();
+        let content = unwrapped.commit(self.store.borrow_mut(), info)?;
+        self.link_msg(link, &content.link);
+        // MAC is verified, the sender knows `unsubscribe_key` from the Subscribe message.
+        self.remove_subscriber(&content.link)
     }
 
     /// Unwrap message with default logic.
+    ///
+    /// Author can handle keyload, tagged packet, subscribe and unsubscribe messages.
     pub fn handle_msg(
         &mut self,
         msg: &TbinaryMessage<TW, F, Link>,
         info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
-    ) -> Fallible<()> {
+    ) -> Fallible<UnwrappedChannelMessage<TW>> {
         let preparsed = msg.parse_header()?;
         self.ensure_appinst(&preparsed)?;
 
         if preparsed.check_content_type(tagged_packet::TYPE) {
-            self.handle_tagged_packet(preparsed, info)?;
-            Ok(())
+            let (public_payload, masked_payload) = self.handle_tagged_packet(preparsed, info)?;
+            Ok(UnwrappedChannelMessage::TaggedPacket {
+                public_payload,
+                masked_payload,
+            })
+        } else if preparsed.check_content_type(keyload::TYPE) {
+            self.handle_keyload(preparsed, info)?;
+            Ok(UnwrappedChannelMessage::Keyload)
+        } else if preparsed.check_content_type(private_keyload::TYPE) {
+            self.handle_keyload_private(preparsed, info)?;
+            Ok(UnwrappedChannelMessage::Keyload)
+        } else if preparsed.check_content_type(subscribe::TYPE) {
+            self.handle_subscribe(preparsed, info)?;
+            Ok(UnwrappedChannelMessage::Subscribe)
+        } else if preparsed.check_content_type(unsubscribe::TYPE) {
+            self.handle_unsubscribe(preparsed, info)?;
+            Ok(UnwrappedChannelMessage::Unsubscribe)
         } else if preparsed.check_content_type(announce::TYPE) {
             bail!("Can't handle announce message.")
         } else if preparsed.check_content_type(change_key::TYPE) {
@@ -520,3 +940,400 @@ where
         }
     }
 }
+
+/// Type of Author state, see `state` module.
+const STATE_TYPE: &str = "STREAMS9CHANNEL9AUTHOR";
+
+/// Author state is persisted with the following message.
+///
+/// ```pb3
+/// message AuthorState {
+///     skip tryte prng_key[81];
+///     skip size default_mss_height;
+///     skip size h;
+///     skip repeated(h) {
+///         skip link key_link;
+///         skip trytes mss_nonce;
+///         skip size mss_height;
+///         skip size mss_skn;
+///         skip size n;
+///         skip repeated(n) tryte mss_node[81];
+///     }
+///     skip size b;
+///     skip repeated(b) {
+///         skip link msg;
+///         skip link key_link;
+///     }
+///     skip oneof {
+///         null empty = 0;
+///         null ntru = 1;
+///     }
+///     skip size m;
+///     skip repeated(m) {
+///         skip tryte pskid[27];
+///         skip tryte psk[81];
+///     }
+///     skip size k;
+///     skip repeated(k) tryte ntru_pk[3072];
+///     skip size l;
+///     skip repeated(l) {
+///         skip link subscribe;
+///         skip tryte subscriber_ntru_pk[3072];
+///     }
+///     skip size r;
+///     skip repeated(r) {
+///         skip link msg;
+///         skip link root;
+///     }
+///     skip size t;
+///     skip repeated(t) {
+///         skip link root;
+///         skip link tip;
+///     }
+///     skip size s;
+///     skip repeated(s) {
+///         skip trytes branch_name;
+///         skip link root;
+///     }
+///     skip tryte sign_keyloads;
+///     skip oneof {
+///         null manual = 0;
+///         size threshold = 1;
+///     }
+///     skip link appinst;
+///     skip link_gen link_gen;
+///     skip store store;
+/// }
+/// ```
+///
+/// MSS private keys are stored as a PRNG key, nonces and the current Merkle
+/// tree states so that Merkle trees are not regenerated. Used WOTS keys are
+/// never reused after import. Each key is indexed by the link of the message
+/// introducing it and each known message refers to the key valid on its
+/// branch. NTRU key pair is completely determined by the PRNG key and is
+/// regenerated. Subscribe policy is not persisted and must be set again after
+/// import.
+impl<TW, F, P, Link, Store, LinkGen> AuthorT<TW, F, P, Link, Store, LinkGen>
+where
+    TW: RngTbitWord + IntTbitWord + StringTbitWord + SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW> + Clone + Default,
+    P: mss::Parameters<TW>,
+    Link: HasLink + AbsorbExternalFallback<TW, F> + SkipFallback<TW, F> + Default + Clone + Eq,
+    <Link as HasLink>::Base: Eq + Debug,
+    <Link as HasLink>::Rel: Eq + hash::Hash + Clone + Debug + Default + SkipFallback<TW, F>,
+    Store: LinkStore<TW, F, <Link as HasLink>::Rel> + SkipFallback<TW, F> + Default,
+    LinkGen: ChannelLinkGenerator<TW, P, Link> + SkipFallback<TW, F> + Default,
+{
+    /// Export Author state encrypted with `password`.
+    pub fn export(&self, password: &str) -> Fallible<Tbits<TW>> {
+        let prng_key = NTrytes(self.prng.secret_key().clone());
+        let default_mss_height = Size(self.default_mss_height);
+        let mss_sks: Vec<_> = self
+            .mss_sks
+            .keys()
+            .map(|(key_link, mss_sk)| {
+                let (mss_height, mss_skn, mss_nodes) = mss_sk.store();
+                (key_link, Trytes(mss_sk.nonce().clone()), Size(mss_height), Size(mss_skn), mss_nodes)
+            })
+            .collect();
+        let sign_keyloads = if self.sign_keyloads { Trint3(1) } else { Trint3(0) };
+        let key_rotation_threshold = match self.key_rotation {
+            KeyRotationPolicy::Manual => None,
+            KeyRotationPolicy::Threshold(threshold) => Some(Size(threshold)),
+        };
+        let branch_names: Vec<_> = self
+            .branches
+            .names()
+            .filter_map(|(name, root)| Tbits::from_str(name).ok().map(|name| (Trytes(name), root)))
+            .collect();
+        let store = self.store.borrow();
+
+        let buf_size = {
+            let mut ctx = sizeof::Context::<TW, F>::new();
+            ctx.skip(&prng_key)?
+                .skip(&default_mss_height)?
+                .skip(Size(mss_sks.len()))?
+                .repeated(mss_sks.iter(), |ctx, (key_link, mss_nonce, mss_height, mss_skn, mss_nodes)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(*key_link))?
+                        .skip(mss_nonce)?
+                        .skip(mss_height)?
+                        .skip(mss_skn)?
+                        .skip(Size(mss_nodes.len()))?
+                        .repeated(mss_nodes.iter(), |ctx, node| ctx.skip(&NTrytes(node.clone())))
+                })?
+                .skip(Size(self.mss_sks.branches().len()))?
+                .repeated(self.mss_sks.branches(), |ctx, (msg, key_link)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(msg))?
+                        .skip(<&Fallback<<Link as HasLink>::Rel>>::from(key_link))
+                })?
+                .optional(OneofMode::Skip, self.opt_ntru.as_ref(), |ctx, _| Ok(ctx))?
+                .skip(Size(self.psks.len()))?
+                .repeated(self.psks.iter(), |ctx, (pskid, psk)| {
+                    ctx.skip(&NTrytes(pskid.clone()))?.skip(&NTrytes(psk.clone()))
+                })?
+                .skip(Size(self.ntru_pks.len()))?
+                .repeated(self.ntru_pks.iter(), |ctx, ntru_pk| {
+                    ctx.skip(&NTrytes(ntru_pk.tbits().clone()))
+                })?
+                .skip(Size(self.subscribers.len()))?
+                .repeated(self.subscribers.iter(), |ctx, (link, ntru_pk)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(link))?
+                        .skip(&NTrytes(ntru_pk.tbits().clone()))
+                })?
+                .skip(Size(self.branches.roots().len()))?
+                .repeated(self.branches.roots(), |ctx, (msg, root)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(msg))?
+                        .skip(<&Fallback<<Link as HasLink>::Rel>>::from(root))
+                })?
+                .skip(Size(self.branches.tips().len()))?
+                .repeated(self.branches.tips(), |ctx, (root, tip)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(root))?
+                        .skip(<&Fallback<<Link as HasLink>::Rel>>::from(tip))
+                })?
+                .skip(Size(branch_names.len()))?
+                .repeated(branch_names.iter(), |ctx, (name, root)| {
+                    ctx.skip(name)?.skip(<&Fallback<<Link as HasLink>::Rel>>::from(*root))
+                })?
+                .skip(&sign_keyloads)?
+                .optional(OneofMode::Skip, key_rotation_threshold.as_ref(), |ctx, threshold| {
+                    ctx.skip(threshold)
+                })?
+                .skip(<&Fallback<Link>>::from(&self.appinst))?
+                .skip(<&Fallback<LinkGen>>::from(&self.link_gen))?
+                .skip(<&Fallback<Store>>::from(&*store))?;
+            ctx.get_size()
+        };
+
+        let mut buf = Tbits::<TW>::zero(buf_size);
+        {
+            let mut ctx = wrap::Context::<TW, F, _>::new(buf.slice_mut());
+            ctx.skip(&prng_key)?
+                .skip(&default_mss_height)?
+                .skip(Size(mss_sks.len()))?
+                .repeated(mss_sks.iter(), |ctx, (key_link, mss_nonce, mss_height, mss_skn, mss_nodes)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(*key_link))?
+                        .skip(mss_nonce)?
+                        .skip(mss_height)?
+                        .skip(mss_skn)?
+                        .skip(Size(mss_nodes.len()))?
+                        .repeated(mss_nodes.iter(), |ctx, node| ctx.skip(&NTrytes(node.clone())))
+                })?
+                .skip(Size(self.mss_sks.branches().len()))?
+                .repeated(self.mss_sks.branches(), |ctx, (msg, key_link)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(msg))?
+                        .skip(<&Fallback<<Link as HasLink>::Rel>>::from(key_link))
+                })?
+                .optional(OneofMode::Skip, self.opt_ntru.as_ref(), |ctx, _| Ok(ctx))?
+                .skip(Size(self.psks.len()))?
+                .repeated(self.psks.iter(), |ctx, (pskid, psk)| {
+                    ctx.skip(&NTrytes(pskid.clone()))?.skip(&NTrytes(psk.clone()))
+                })?
+                .skip(Size(self.ntru_pks.len()))?
+                .repeated(self.ntru_pks.iter(), |ctx, ntru_pk| {
+                    ctx.skip(&NTrytes(ntru_pk.tbits().clone()))
+                })?
+                .skip(Size(self.subscribers.len()))?
+                .repeated(self.subscribers.iter(), |ctx, (link, ntru_pk)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(link))?
+                        .skip(&NTrytes(ntru_pk.tbits().clone()))
+                })?
+                .skip(Size(self.branches.roots().len()))?
+                .repeated(self.branches.roots(), |ctx, (msg, root)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(msg))?
+                        .skip(<&Fallback<<Link as HasLink>::Rel>>::from(root))
+                })?
+                .skip(Size(self.branches.tips().len()))?
+                .repeated(self.branches.tips(), |ctx, (root, tip)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(root))?
+                        .skip(<&Fallback<<Link as HasLink>::Rel>>::from(tip))
+                })?
+                .skip(Size(branch_names.len()))?
+                .repeated(branch_names.iter(), |ctx, (name, root)| {
+                    ctx.skip(name)?.skip(<&Fallback<<Link as HasLink>::Rel>>::from(*root))
+                })?
+                .skip(&sign_keyloads)?
+                .optional(OneofMode::Skip, key_rotation_threshold.as_ref(), |ctx, threshold| {
+                    ctx.skip(threshold)
+                })?
+                .skip(<&Fallback<Link>>::from(&self.appinst))?
+                .skip(<&Fallback<LinkGen>>::from(&self.link_gen))?
+                .skip(<&Fallback<Store>>::from(&*store))?;
+            ensure!(ctx.stream.is_empty(), "OStream has not been exhausted.");
+        }
+
+        state::wrap_state::<TW, F>(STATE_TYPE, password, buf)
+    }
+
+    /// Import Author state previously exported with `export` and encrypted with `password`.
+    pub fn import(state: &Tbits<TW>, password: &str) -> Fallible<Self> {
+        let state = state::unwrap_state::<TW, F>(STATE_TYPE, password, state)?;
+
+        let mut prng_key = NTrytes::zero(prng::Prng::<TW, P::PrngG>::KEY_SIZE);
+        let mut default_mss_height = Size(0);
+        let mut repeated_mss_sks = Size(0);
+        let mut mss_sks = Vec::new();
+        let mut repeated_mss_branches = Size(0);
+        let mut mss_branches = HashMap::new();
+        let mut has_ntru = None;
+        let mut repeated_psks = Size(0);
+        let mut psks = HashMap::new();
+        let mut repeated_ntru_pks = Size(0);
+        let mut ntru_pks = HashSet::new();
+        let mut repeated_subscribers = Size(0);
+        let mut subscribers = HashMap::new();
+        let mut repeated_branch_roots = Size(0);
+        let mut branch_roots = HashMap::new();
+        let mut repeated_branch_tips = Size(0);
+        let mut branch_tips = HashMap::new();
+        let mut repeated_branch_names = Size(0);
+        let mut branch_names = Vec::new();
+        let mut sign_keyloads = Trint3(-1);
+        let mut key_rotation_threshold: Option<Size> = None;
+        let mut appinst = Link::default();
+        let mut link_gen = LinkGen::default();
+        let mut store = Store::default();
+
+        {
+            let mut ctx = unwrap::Context::<TW, F, _>::new(state.slice());
+            ctx.skip(&mut prng_key)?
+                .skip(&mut default_mss_height)?
+                .skip(&mut repeated_mss_sks)?
+                .repeated(repeated_mss_sks, |ctx| {
+                    let mut key_link = <Link as HasLink>::Rel::default();
+                    let mut mss_nonce = Trytes::<TW>::default();
+                    let mut mss_height = Size(0);
+                    let mut mss_skn = Size(0);
+                    let mut repeated_mss_nodes = Size(0);
+                    let mut mss_nodes = Vec::new();
+                    ctx.skip(<&mut Fallback<<Link as HasLink>::Rel>>::from(&mut key_link))?
+                        .skip(&mut mss_nonce)?
+                        .skip(&mut mss_height)?
+                        .skip(&mut mss_skn)?
+                        .skip(&mut repeated_mss_nodes)?
+                        .repeated(repeated_mss_nodes, |ctx| {
+                            let mut node = NTrytes::zero(P::MT_HASH_SIZE);
+                            ctx.skip(&mut node)?;
+                            mss_nodes.push(node.0);
+                            Ok(ctx)
+                        })?;
+                    mss_sks.push((key_link, mss_nonce, mss_height, mss_skn, mss_nodes));
+                    Ok(ctx)
+                })?
+                .skip(&mut repeated_mss_branches)?
+                .repeated(repeated_mss_branches, |ctx| {
+                    let mut msg = <Link as HasLink>::Rel::default();
+                    let mut key_link = <Link as HasLink>::Rel::default();
+                    ctx.skip(<&mut Fallback<<Link as HasLink>::Rel>>::from(&mut msg))?
+                        .skip(<&mut Fallback<<Link as HasLink>::Rel>>::from(&mut key_link))?;
+                    mss_branches.insert(msg, key_link);
+                    Ok(ctx)
+                })?
+                .optional(OneofMode::Skip, &mut has_ntru, |ctx, ()| Ok(ctx))?
+                .skip(&mut repeated_psks)?
+                .repeated(repeated_psks, |ctx| {
+                    let mut pskid = NTrytes::zero(psk::PSKID_SIZE);
+                    let mut psk = NTrytes::zero(psk::PSK_SIZE);
+                    ctx.skip(&mut pskid)?.skip(&mut psk)?;
+                    psks.insert(pskid.0, psk.0);
+                    Ok(ctx)
+                })?
+                .skip(&mut repeated_ntru_pks)?
+                .repeated(repeated_ntru_pks, |ctx| {
+                    let mut ntru_pk = NTrytes::zero(ntru::PK_SIZE);
+                    ctx.skip(&mut ntru_pk)?;
+                    if let Some(ntru_pk) = ntru::PublicKey::from_trits(ntru_pk.0) {
+                        ntru_pks.insert(ntru_pk);
+                        Ok(ctx)
+                    } else {
+                        bail!("Bad NTRU public key.")
+                    }
+                })?
+                .skip(&mut repeated_subscribers)?
+                .repeated(repeated_subscribers, |ctx| {
+                    let mut link = <Link as HasLink>::Rel::default();
+                    let mut ntru_pk = NTrytes::zero(ntru::PK_SIZE);
+                    ctx.skip(<&mut Fallback<<Link as HasLink>::Rel>>::from(&mut link))?
+                        .skip(&mut ntru_pk)?;
+                    if let Some(ntru_pk) = ntru::PublicKey::from_trits(ntru_pk.0) {
+                        subscribers.insert(link, ntru_pk);
+                        Ok(ctx)
+                    } else {
+                        bail!("Bad NTRU public key.")
+                    }
+                })?
+                .skip(&mut repeated_branch_roots)?
+                .repeated(repeated_branch_roots, |ctx| {
+                    let mut msg = <Link as HasLink>::Rel::default();
+                    let mut root = <Link as HasLink>::Rel::default();
+                    ctx.skip(<&mut Fallback<<Link as HasLink>::Rel>>::from(&mut msg))?
+                        .skip(<&mut Fallback<<Link as HasLink>::Rel>>::from(&mut root))?;
+                    branch_roots.insert(msg, root);
+                    Ok(ctx)
+                })?
+                .skip(&mut repeated_branch_tips)?
+                .repeated(repeated_branch_tips, |ctx| {
+                    let mut root = <Link as HasLink>::Rel::default();
+                    let mut tip = <Link as HasLink>::Rel::default();
+                    ctx.skip(<&mut Fallback<<Link as HasLink>::Rel>>::from(&mut root))?
+                        .skip(<&mut Fallback<<Link as HasLink>::Rel>>::from(&mut tip))?;
+                    branch_tips.insert(root, tip);
+                    Ok(ctx)
+                })?
+                .skip(&mut repeated_branch_names)?
+                .repeated(repeated_branch_names, |ctx| {
+                    let mut name = Trytes::default();
+                    let mut root = <Link as HasLink>::Rel::default();
+                    ctx.skip(&mut name)?
+                        .skip(<&mut Fallback<<Link as HasLink>::Rel>>::from(&mut root))?;
+                    branch_names.push((name.0.to_string(), root));
+                    Ok(ctx)
+                })?
+                .skip(&mut sign_keyloads)?
+                .guard(sign_keyloads == Trint3(0) || sign_keyloads == Trint3(1), "Bad sign keyloads flag")?
+                .optional(OneofMode::Skip, &mut key_rotation_threshold, |ctx, threshold| {
+                    ctx.skip(threshold)
+                })?
+                .skip(<&mut Fallback<Link>>::from(&mut appinst))?
+                .skip(<&mut Fallback<LinkGen>>::from(&mut link_gen))?
+                .skip(<&mut Fallback<Store>>::from(&mut store))?;
+            ensure!(ctx.stream.is_empty(), "Trailing trits after Author state.");
+        }
+
+        let prng = prng::Prng::init(prng_key.0);
+        let mut mss_keys = HashMap::new();
+        for (key_link, mss_nonce, mss_height, mss_skn, mss_nodes) in mss_sks {
+            match mss::PrivateKey::load(&prng, mss_nonce.0.slice(), mss_height.0, mss_skn.0, mss_nodes) {
+                Some(mss_sk) => mss_keys.insert(key_link, mss_sk),
+                None => bail!("Bad MSS private key."),
+            };
+        }
+        let mss_sks = KeyHistory::from_parts(mss_keys, mss_branches)?;
+        let mut branches = Branches::from_parts(branch_roots, branch_tips)?;
+        for (name, root) in branch_names {
+            branches.name(name, root)?;
+        }
+        let opt_ntru = has_ntru.map(|()| Self::gen_ntru(&prng));
+
+        Ok(Self {
+            prng,
+            default_mss_height: default_mss_height.0,
+            mss_sks,
+            opt_ntru,
+
+            psks,
+            ntru_pks,
+            subscribers,
+            subscribe_policy: SubscribePolicy::default(),
+            branches,
+            sign_keyloads: sign_keyloads == Trint3(1),
+            key_rotation: match key_rotation_threshold {
+                Some(threshold) => KeyRotationPolicy::Threshold(threshold.0),
+                None => KeyRotationPolicy::Manual,
+            },
+
+            store: RefCell::new(store),
+            link_gen,
+            appinst,
+        })
+    }
+}
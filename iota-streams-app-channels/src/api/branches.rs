@@ -0,0 +1,109 @@
+//! Branches of channel messages.
+
+use failure::{
+    ensure,
+    Fallible,
+};
+use std::{
+    collections::{
+        hash_map,
+        HashMap,
+    },
+    hash,
+};
+
+/// Branches of messages indexed by the link of the keyload message starting the branch.
+///
+/// Each known message is mapped to the root of its branch: a message inherits the branch
+/// of the message it is linked to, and a keyload starts a new branch. Branch tip is the
+/// last message added to the branch, new messages are usually linked to the tip.
+pub struct Branches<Rel> {
+    /// Link of the keyload starting the branch of a message.
+    roots: HashMap<Rel, Rel>,
+
+    /// Link of the last message in a branch indexed by the branch root.
+    tips: HashMap<Rel, Rel>,
+
+    /// Roots of the named branches indexed by branch names.
+    names: HashMap<String, Rel>,
+}
+
+impl<Rel> Default for Branches<Rel> {
+    fn default() -> Self {
+        Self {
+            roots: HashMap::new(),
+            tips: HashMap::new(),
+            names: HashMap::new(),
+        }
+    }
+}
+
+impl<Rel> Branches<Rel>
+where
+    Rel: Eq + hash::Hash + Clone,
+{
+    /// Restore branches from roots and tips, each tip must belong to its branch.
+    pub fn from_parts(roots: HashMap<Rel, Rel>, tips: HashMap<Rel, Rel>) -> Fallible<Self> {
+        ensure!(
+            tips.iter().all(|(root, tip)| roots.get(tip) == Some(root)),
+            "Branch tip doesn't belong to the branch."
+        );
+        Ok(Self {
+            roots,
+            tips,
+            names: HashMap::new(),
+        })
+    }
+
+    /// Start a new branch with keyload `root`.
+    pub fn start(&mut self, root: Rel) {
+        self.roots.insert(root.clone(), root.clone());
+        self.tips.insert(root.clone(), root);
+    }
+
+    /// Message `msg` linked to `link_to` is added to the branch of `link_to`, if any,
+    /// and becomes its tip.
+    pub fn link_msg(&mut self, msg: Rel, link_to: &Rel) {
+        if let Some(root) = self.roots.get(link_to).cloned() {
+            self.roots.insert(msg.clone(), root.clone());
+            self.tips.insert(root, msg);
+        }
+    }
+
+    /// Root of the branch of `msg`.
+    pub fn root(&self, msg: &Rel) -> Option<&Rel> {
+        self.roots.get(msg)
+    }
+
+    /// Tip of the branch started with keyload `root`.
+    pub fn tip(&self, root: &Rel) -> Option<&Rel> {
+        self.tips.get(root)
+    }
+
+    /// Tips of all branches indexed by branch roots.
+    pub fn tips(&self) -> hash_map::Iter<'_, Rel, Rel> {
+        self.tips.iter()
+    }
+
+    /// Roots of the branches of all known messages indexed by message links.
+    pub fn roots(&self) -> hash_map::Iter<'_, Rel, Rel> {
+        self.roots.iter()
+    }
+
+    /// Name the branch started with keyload `root`, the name may be reassigned.
+    pub fn name(&mut self, name: String, root: Rel) -> Fallible<()> {
+        ensure!(self.tips.contains_key(&root), "Message doesn't start a branch.");
+        self.names.insert(name, root);
+        Ok(())
+    }
+
+    /// Root of the branch `name`.
+    pub fn named(&self, name: &str) -> Option<&Rel> {
+        self.names.get(name)
+    }
+
+    /// Roots of the named branches indexed by branch names.
+    pub fn names(&self) -> hash_map::Iter<'_, String, Rel> {
+        self.names.iter()
+    }
+}
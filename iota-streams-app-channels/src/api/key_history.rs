@@ -0,0 +1,100 @@
+//! History of Author's MSS keys.
+
+use failure::{
+    bail,
+    ensure,
+    Fallible,
+};
+use std::{
+    collections::{
+        hash_map,
+        HashMap,
+    },
+    hash,
+};
+
+/// MSS keys indexed by the link of the message introducing the key, ie. Announce or ChangeKey.
+///
+/// Each known message is also mapped to the link of the key valid on its branch:
+/// a message inherits the key of the message it is linked to, and a message introducing
+/// a key starts a new branch. Thus messages linked to the old branches can still be
+/// signed and verified with the old keys after the key has been changed.
+pub struct KeyHistory<Rel, Key> {
+    /// Keys indexed by the link of the message introducing the key.
+    keys: HashMap<Rel, Key>,
+
+    /// Link of the message introducing the key valid on the branch of a message.
+    branches: HashMap<Rel, Rel>,
+}
+
+impl<Rel, Key> Default for KeyHistory<Rel, Key> {
+    fn default() -> Self {
+        Self {
+            keys: HashMap::new(),
+            branches: HashMap::new(),
+        }
+    }
+}
+
+impl<Rel, Key> KeyHistory<Rel, Key>
+where
+    Rel: Eq + hash::Hash + Clone,
+{
+    /// Restore history from keys and branches, each branch must refer to a known key.
+    pub fn from_parts(keys: HashMap<Rel, Key>, branches: HashMap<Rel, Rel>) -> Fallible<Self> {
+        ensure!(
+            branches.values().all(|key_link| keys.contains_key(key_link)),
+            "Branch refers to unknown key."
+        );
+        Ok(Self { keys, branches })
+    }
+
+    /// Add a new `key` introduced by the message with link `link`.
+    pub fn insert(&mut self, link: Rel, key: Key) {
+        self.branches.insert(link.clone(), link.clone());
+        self.keys.insert(link, key);
+    }
+
+    /// Message `msg` linked to `link_to` inherits the key of `link_to` branch.
+    pub fn link_msg(&mut self, msg: Rel, link_to: &Rel) {
+        if let Some(key_link) = self.branches.get(link_to).cloned() {
+            self.branches.insert(msg, key_link);
+        }
+    }
+
+    /// Link of the message introducing the key valid on the branch of `msg`.
+    pub fn key_link(&self, msg: &Rel) -> Option<&Rel> {
+        self.branches.get(msg)
+    }
+
+    /// Key valid on the branch of `msg`.
+    pub fn get(&self, msg: &Rel) -> Option<&Key> {
+        self.branches.get(msg).and_then(|key_link| self.keys.get(key_link))
+    }
+
+    /// Key valid on the branch of `msg` or error if `msg` is unknown.
+    pub fn try_get(&self, msg: &Rel) -> Fallible<&Key> {
+        match self.get(msg) {
+            Some(key) => Ok(key),
+            None => bail!("No MSS key is known for the branch of the message."),
+        }
+    }
+
+    /// Mutable key valid on the branch of `msg`.
+    pub fn get_mut(&mut self, msg: &Rel) -> Option<&mut Key> {
+        match self.branches.get(msg) {
+            Some(key_link) => self.keys.get_mut(key_link),
+            None => None,
+        }
+    }
+
+    /// Keys indexed by the link of the message introducing the key.
+    pub fn keys(&self) -> hash_map::Iter<'_, Rel, Key> {
+        self.keys.iter()
+    }
+
+    /// Links of the messages introducing the keys indexed by message links.
+    pub fn branches(&self) -> hash_map::Iter<'_, Rel, Rel> {
+        self.branches.iter()
+    }
+}
@@ -3,9 +3,15 @@ use iota_streams_app::{
         HasLink,
         LinkGenerator,
     },
-    transport::tangle::{
-        DefaultTangleLinkGenerator,
-        TangleAddress,
+    transport::{
+        http::{
+            HttpAddress,
+            HttpLinkGenerator,
+        },
+        tangle::{
+            DefaultTangleLinkGenerator,
+            TangleAddress,
+        },
     },
 };
 use iota_streams_core::{
@@ -19,6 +25,7 @@ use iota_streams_core::{
     },
 };
 use iota_streams_core_mss::signature::mss;
+use iota_streams_protobuf3::types::Trytes;
 
 pub trait ChannelLinkGenerator<TW, P, Link>
 where
@@ -35,6 +42,44 @@ where
     P: mss::Parameters<TW>,
 {
 }
+impl<TW, F, P> ChannelLinkGenerator<TW, P, HttpAddress<TW>> for HttpLinkGenerator<TW, F>
+where
+    TW: StringTbitWord + SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW> + Default,
+    P: mss::Parameters<TW>,
+{
+}
+
+/// Result of handling a channel message with `handle_msg` by Author or Subscriber.
+#[derive(Clone)]
+pub enum UnwrappedChannelMessage<TW> {
+    /// Channel has been announced, Subscriber is now registered to the channel instance.
+    Announce,
+
+    /// Author's MSS public key has been changed.
+    ChangeKey,
+
+    /// Session key has been unwrapped from keyload.
+    Keyload,
+
+    /// Signed packet has been unwrapped and its signature verified.
+    SignedPacket {
+        public_payload: Trytes<TW>,
+        masked_payload: Trytes<TW>,
+    },
+
+    /// Tagged packet has been unwrapped and its MAC verified.
+    TaggedPacket {
+        public_payload: Trytes<TW>,
+        masked_payload: Trytes<TW>,
+    },
+
+    /// Subscriber's NTRU public key has been unwrapped.
+    Subscribe,
+
+    /// Unsubscribe message has been unwrapped.
+    Unsubscribe,
+}
 
 /// Generic Channel Author API.
 pub mod author;
@@ -42,5 +87,14 @@ pub mod author;
 /// Generic Channel Subscriber API.
 pub mod subscriber;
 
+/// Password protected Author and Subscriber state.
+pub mod state;
+
+/// History of Author's MSS keys.
+pub mod key_history;
+
+/// Branches of channel messages started with keyloads.
+pub mod branches;
+
 /// Tangle-specific Channel API.
 pub mod tangle;
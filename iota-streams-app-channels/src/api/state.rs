@@ -0,0 +1,149 @@
+//! Password protected container for persisting Author and Subscriber state.
+//!
+//! ```pb3
+//! message State {
+//!     absorb tryte version;
+//!     absorb trytes type;
+//!     absorb tryte nonce[81];
+//!     absorb external trytes password;
+//!     commit;
+//!     absorb size n;
+//!     mask tryte state[n];
+//!     commit;
+//!     squeeze tryte mac[81];
+//! }
+//! ```
+//!
+//! # Fields
+//!
+//! * `version` -- state encoding version.
+//!
+//! * `type` -- type of the state, ie. Author or Subscriber.
+//!
+//! * `nonce` -- random nonce, makes encryption of the same state with the same password unique.
+//!
+//! * `password` -- tryte-encoded password known to the owner of the state.
+//!
+//! * `n` -- size of the state in trytes. It is absorbed rather than masked so that
+//! an incorrect password can't produce a bogus size.
+//!
+//! * `state` -- `skip`-encoded Author or Subscriber state.
+//!
+//! * `mac` -- MAC of the state; an incorrect password is detected with it.
+//!
+
+use failure::{
+    bail,
+    ensure,
+    Fallible,
+};
+use std::str::FromStr;
+
+use iota_streams_core::{
+    prng,
+    sponge::{
+        prp::PRP,
+        spongos,
+    },
+    tbits::{
+        trinary,
+        word::{
+            RngTbitWord,
+            SpongosTbitWord,
+            StringTbitWord,
+        },
+        Tbits,
+    },
+};
+use iota_streams_protobuf3::{
+    command::*,
+    types::*,
+};
+
+/// Current state encoding version.
+pub const STATE_VER: Trint3 = Trint3(1);
+
+fn password_tbits<TW>(password: &str) -> Fallible<NTrytes<TW>>
+where
+    TW: StringTbitWord,
+{
+    match Tbits::<TW>::from_str(password) {
+        Ok(tbits) if !tbits.is_empty() => Ok(NTrytes(tbits)),
+        _ => bail!("Password must be a non-empty tryte string."),
+    }
+}
+
+/// Encrypt `skip`-encoded `state` of type `state_type` with `password`.
+pub fn wrap_state<TW, F>(state_type: &str, password: &str, state: Tbits<TW>) -> Fallible<Tbits<TW>>
+where
+    TW: RngTbitWord + StringTbitWord + SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW> + Default,
+{
+    let password = password_tbits::<TW>(password)?;
+    let state_type = Trytes(Tbits::<TW>::from_str(state_type).map_err(|_| failure::err_msg("Bad state type."))?);
+    let nonce = NTrytes(prng::random_nonce(spongos::Spongos::<TW, F>::NONCE_SIZE));
+    let state_size = Size(state.size() / 3);
+    let state = NTrytes(state);
+    let mac = Mac(spongos::Spongos::<TW, F>::MAC_SIZE);
+
+    let buf_size = {
+        let mut ctx = sizeof::Context::<TW, F>::new();
+        ctx.absorb(&STATE_VER)?
+            .absorb(&state_type)?
+            .absorb(&nonce)?
+            .absorb(External(&password))?
+            .commit()?
+            .absorb(&state_size)?
+            .mask(&state)?
+            .commit()?
+            .squeeze(&mac)?;
+        ctx.get_size()
+    };
+
+    let mut buf = Tbits::<TW>::zero(buf_size);
+    {
+        let mut ctx = wrap::Context::<TW, F, _>::new(buf.slice_mut());
+        ctx.absorb(&STATE_VER)?
+            .absorb(&state_type)?
+            .absorb(&nonce)?
+            .absorb(External(&password))?
+            .commit()?
+            .absorb(&state_size)?
+            .mask(&state)?
+            .commit()?
+            .squeeze(&mac)?;
+        ensure!(ctx.stream.is_empty(), "OStream has not been exhausted.");
+    }
+    Ok(buf)
+}
+
+/// Decrypt state of type `state_type` with `password` and check its integrity.
+pub fn unwrap_state<TW, F>(state_type: &str, password: &str, buf: &Tbits<TW>) -> Fallible<Tbits<TW>>
+where
+    TW: StringTbitWord + SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW> + Default,
+{
+    let password = password_tbits::<TW>(password)?;
+    let mut version = Trint3(0);
+    let mut unwrapped_type = Trytes::<TW>::default();
+    let mut nonce = NTrytes::<TW>::zero(spongos::Spongos::<TW, F>::NONCE_SIZE);
+    let mut state_size = Size(0);
+    let mac = Mac(spongos::Spongos::<TW, F>::MAC_SIZE);
+
+    let mut ctx = unwrap::Context::<TW, F, _>::new(buf.slice());
+    ctx.absorb(&mut version)?
+        .guard(version == STATE_VER, "Unsupported state version")?
+        .absorb(&mut unwrapped_type)?
+        .guard((unwrapped_type.0).eq_str(state_type), "Unexpected state type")?
+        .absorb(&mut nonce)?
+        .absorb(External(&password))?
+        .commit()?
+        .absorb(&mut state_size)?
+        .guard(state_size.0 * 3 <= buf.size(), "Bad state size")?;
+    let mut state = NTrytes::<TW>::zero(state_size.0 * 3);
+    ctx.mask(&mut state)?
+        .commit()?
+        .squeeze(&mac)?;
+    ensure!(ctx.stream.is_empty(), "Trailing trits after state.");
+    Ok(state.0)
+}
@@ -5,7 +5,9 @@ use failure::{
 };
 use std::{
     cell::RefCell,
+    collections::HashMap,
     fmt::Debug,
+    hash,
     str::FromStr,
 };
 
@@ -31,9 +33,16 @@ use iota_streams_app::message::{
     header::Header,
     *,
 };
-use iota_streams_protobuf3::types::*;
+use iota_streams_protobuf3::{
+    command::*,
+    types::*,
+};
 
-use super::*;
+use super::{
+    branches::Branches,
+    key_history::KeyHistory,
+    *,
+};
 use crate::message::*;
 
 /// Generic Channel Subscriber type parametrised by the type of links, link store and
@@ -54,6 +63,7 @@ use crate::message::*;
 pub struct SubscriberT<TW, F, P, Link, Store, LinkGen>
 where
     P: mss::Parameters<TW>,
+    Link: HasLink,
 {
     /// PRNG used for NTRU, Spongos key generation, etc.
     prng: prng::Prng<TW, P::PrngG>,
@@ -68,14 +78,23 @@ where
     /// the channel instance.
     pub(crate) appinst: Option<Link>,
 
-    /// Author's MSS public key, or nothing if Subscriber is not registered to
+    /// Author's latest MSS public key, or nothing if Subscriber is not registered to
     /// the channel instance.
-    //TODO: Store also Author's old MSS public keys?
     pub(crate) author_mss_pk: Option<mss::PublicKey<TW, P>>,
 
+    /// Author's MSS public keys indexed by the link of Announce or ChangeKey message.
+    /// Signatures are verified with the key valid on the branch the message is linked to.
+    pub(crate) author_mss_pks: KeyHistory<<Link as HasLink>::Rel, mss::PublicKey<TW, P>>,
+
     /// Author's NTRU public key or nothing if Author has no NTRU key pair.
     pub(crate) author_ntru_pk: Option<ntru::PublicKey<TW, F>>,
 
+    /// Branches of messages started with keyloads Subscriber has access to.
+    pub(crate) branches: Branches<<Link as HasLink>::Rel>,
+
+    /// Reject keyloads not signed by Author. Signed keyloads are always verified.
+    pub require_signed_keyloads: bool,
+
     /// Link store.
     store: RefCell<Store>,
 
@@ -90,7 +109,7 @@ where
     P: mss::Parameters<TW>,
     Link: HasLink + AbsorbExternalFallback<TW, F> + Default + Clone + Eq,
     <Link as HasLink>::Base: Eq + Debug,
-    <Link as HasLink>::Rel: Eq + Debug + Default + SkipFallback<TW, F>,
+    <Link as HasLink>::Rel: Eq + hash::Hash + Clone + Debug + Default + SkipFallback<TW, F>,
     Store: LinkStore<TW, F, <Link as HasLink>::Rel>,
     LinkGen: ChannelLinkGenerator<TW, P, Link>,
 {
@@ -118,13 +137,43 @@ where
 
             appinst: None,
             author_mss_pk: None,
+            author_mss_pks: KeyHistory::default(),
             author_ntru_pk: None,
+            branches: Branches::default(),
+            require_signed_keyloads: false,
 
             store: RefCell::new(store),
             link_gen: link_gen,
         }
     }
 
+    /// Has the message with link `rel` been processed, ie. is it in the link store?
+    pub fn is_processed(&self, rel: &<Link as HasLink>::Rel) -> bool {
+        self.store.borrow().lookup(rel).is_ok()
+    }
+
+    /// Message `msg` linked to `link_to` inherits Author's MSS key and branch of `link_to`.
+    fn link_msg(&mut self, msg: <Link as HasLink>::Rel, link_to: &<Link as HasLink>::Rel) {
+        self.author_mss_pks.link_msg(msg.clone(), link_to);
+        self.branches.link_msg(msg, link_to);
+    }
+
+    /// Keyload `msg` linked to `link_to` inherits Author's MSS key and starts a new branch.
+    fn link_keyload(&mut self, msg: <Link as HasLink>::Rel, link_to: &<Link as HasLink>::Rel) {
+        self.author_mss_pks.link_msg(msg.clone(), link_to);
+        self.branches.start(msg);
+    }
+
+    /// Roots of known branches, ie. links of keyloads Subscriber has access to.
+    pub fn branch_roots(&self) -> Vec<&<Link as HasLink>::Rel> {
+        self.branches.tips().map(|(root, _)| root).collect()
+    }
+
+    /// Tip of the branch started with keyload `root`.
+    pub fn branch_tip(&self, root: &<Link as HasLink>::Rel) -> Option<&<Link as HasLink>::Rel> {
+        self.branches.tip(root)
+    }
+
     fn ensure_appinst<'a>(&self, preparsed: &PreparsedMessage<'a, TW, F, Link>) -> Fallible<()> {
         ensure!(self.appinst.is_some(), "Subscriber is not subscribed to a channel.");
         ensure!(
@@ -140,7 +189,7 @@ where
         link_to: &'a <Link as HasLink>::Rel,
         psks: Psks,
         ntru_pks: NtruPks,
-    ) -> Fallible<PreparedMessage<'a, TW, F, Link, Store, keyload::ContentWrap<'a, TW, F, P::PrngG, Link, Psks, NtruPks>>>
+    ) -> Fallible<PreparedMessage<'a, TW, F, Link, Store, keyload::ContentWrap<'a, TW, F, P::PrngG, P, Link, Psks, NtruPks>>>
     where
         Psks: Clone + ExactSizeIterator<Item = psk::IPsk<'a, TW>>,
         NtruPks: Clone + ExactSizeIterator<Item = ntru::INtruPk<'a, TW, F>>,
@@ -154,6 +203,7 @@ where
             psks: psks,
             prng: &self.prng,
             ntru_pks: ntru_pks,
+            mss_sk: None,
             _phantom: std::marker::PhantomData,
         };
         Ok(PreparedMessage::new(self.store.borrow(), header, content))
@@ -174,6 +224,7 @@ where
                 TW,
                 F,
                 P::PrngG,
+                P,
                 Link,
                 std::option::IntoIter<psk::IPsk<'a, TW>>,
                 std::option::IntoIter<ntru::INtruPk<'a, TW, F>>,
@@ -197,7 +248,9 @@ where
         info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
     ) -> Fallible<TbinaryMessage<TW, F, Link>> {
         let wrapped = self.prepare_keyload(link_to)?.wrap()?;
-        wrapped.commit(self.store.borrow_mut(), info)
+        let msg = wrapped.commit(self.store.borrow_mut(), info)?;
+        self.link_keyload(msg.link.rel().clone(), link_to);
+        Ok(msg)
     }
 
     /// Prepare TaggedPacket message.
@@ -209,7 +262,7 @@ where
     ) -> Fallible<PreparedMessage<'a, TW, F, Link, Store, tagged_packet::ContentWrap<'a, TW, F, Link>>> {
         let header = self.link_gen.header_from(link_to, tagged_packet::TYPE);
         let content = tagged_packet::ContentWrap {
-            link: link_to,
+            msgid: link_to,
             public_payload: public_payload,
             masked_payload: masked_payload,
             _phantom: std::marker::PhantomData,
@@ -229,7 +282,9 @@ where
         let wrapped = self
             .prepare_tagged_packet(link_to, public_payload, masked_payload)?
             .wrap()?;
-        wrapped.commit(self.store.borrow_mut(), info)
+        let msg = wrapped.commit(self.store.borrow_mut(), info)?;
+        self.link_msg(msg.link.rel().clone(), link_to);
+        Ok(msg)
     }
 
     /// Prepare Subscribe message.
@@ -267,7 +322,9 @@ where
         info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
     ) -> Fallible<TbinaryMessage<TW, F, Link>> {
         let wrapped = self.prepare_subscribe(link_to)?.wrap()?;
-        wrapped.commit(self.store.borrow_mut(), info)
+        let msg = wrapped.commit(self.store.borrow_mut(), info)?;
+        self.link_msg(msg.link.rel().clone(), link_to);
+        Ok(msg)
     }
 
     /// Prepare Unsubscribe message.
@@ -290,7 +347,9 @@ where
         info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
     ) -> Fallible<TbinaryMessage<TW, F, Link>> {
         let wrapped = self.prepare_unsubscribe(link_to)?.wrap()?;
-        wrapped.commit(self.store.borrow_mut(), info)
+        let msg = wrapped.commit(self.store.borrow_mut(), info)?;
+        self.link_msg(msg.link.rel().clone(), link_to);
+        Ok(msg)
     }
 
     pub fn unwrap_announcement<'a>(
@@ -328,32 +387,39 @@ where
         //TODO: Verify appinst (address) == MSS public key.
         // At the moment the Author is free to choose any address, not tied to MSS PK.
 
+        self.author_mss_pks.insert(link.rel().clone(), content.mss_pk.clone());
         self.appinst = Some(link);
         self.author_mss_pk = Some(content.mss_pk);
         self.author_ntru_pk = content.ntru_pk;
         Ok(())
     }
 
-    pub fn unwrap_change_key<'a, 'b>(
-        &'b self,
+    pub fn unwrap_change_key<'a>(
+        &self,
         preparsed: PreparsedMessage<'a, TW, F, Link>,
-    ) -> Fallible<UnwrappedMessage<TW, F, Link, change_key::ContentUnwrap<'b, TW, P, Link>>> {
+    ) -> Fallible<UnwrappedMessage<TW, F, Link, change_key::ContentUnwrap<TW, P, Link>>> {
         self.ensure_appinst(&preparsed)?;
-        let mss_linked_pk = self.author_mss_pk.as_ref().unwrap();
-        let content = change_key::ContentUnwrap::new(mss_linked_pk);
+        let content = change_key::ContentUnwrap::new();
         preparsed.unwrap(&*self.store.borrow(), content)
     }
 
-    /// Verify new Author's MSS public key and update Author's MSS public key.
+    /// Verify new Author's MSS public key with the key valid on the linked branch
+    /// and update Author's MSS public key. Old keys are retained.
     pub fn handle_change_key<'a>(
         &mut self,
         preparsed: PreparsedMessage<'a, TW, F, Link>,
         info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
     ) -> Fallible<()> {
         ensure!(self.author_mss_pk.is_some(), "No Author's MSS public key found.");
-        let content = self
-            .unwrap_change_key(preparsed)?
-            .commit(self.store.borrow_mut(), info)?;
+        let unwrapped = self.unwrap_change_key(preparsed)?;
+        ensure!(
+            *self.author_mss_pks.try_get(&unwrapped.content.link)? == unwrapped.content.mss_linked_pk,
+            "Bad change key signature."
+        );
+        let link = unwrapped.link.rel().clone();
+        let content = unwrapped.commit(self.store.borrow_mut(), info)?;
+        self.branches.link_msg(link.clone(), &content.link);
+        self.author_mss_pks.insert(link, content.mss_pk.clone());
         self.author_mss_pk = Some(content.mss_pk);
         Ok(())
     }
@@ -393,6 +459,7 @@ where
                 'b,
                 TW,
                 F,
+                P,
                 Link,
                 Self,
                 for<'c> fn(&'c Self, &psk::PskId<TW>) -> Option<&'c psk::Psk<TW>>,
@@ -405,6 +472,7 @@ where
             'b,
             TW,
             F,
+            P,
             Link,
             Self,
             for<'c> fn(&'c Self, &psk::PskId<TW>) -> Option<&'c psk::Psk<TW>>,
@@ -414,17 +482,69 @@ where
     }
 
     /// Try unwrapping session key from keyload using Subscriber's pre-shared key or NTRU private key (if any).
+    ///
+    /// Signed keyload must be signed with Author's MSS private key, unsigned keyload is
+    /// rejected if `require_signed_keyloads` is set.
     pub fn handle_keyload<'a>(
         &mut self,
         preparsed: PreparsedMessage<'a, TW, F, Link>,
         info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
     ) -> Fallible<()> {
-        let _content = self.unwrap_keyload(preparsed)?.commit(self.store.borrow_mut(), info)?;
+        let unwrapped = self.unwrap_keyload(preparsed)?;
+        self.ensure_keyload_signature(&unwrapped.content.link, &unwrapped.content.mss_pk)?;
+        let link = unwrapped.link.rel().clone();
+        let content = unwrapped.commit(self.store.borrow_mut(), info)?;
+        let link_to = content.link.clone();
+        self.link_keyload(link, &link_to);
         // Unwrapped nonce and key in content are not used explicitly.
         // The resulting spongos state is joined into a protected message state.
         Ok(())
     }
 
+    /// Check MSS public key recovered from signature of keyload linked to `link_to`, if any.
+    fn ensure_keyload_signature(
+        &self,
+        link_to: &<Link as HasLink>::Rel,
+        mss_pk: &Option<mss::PublicKey<TW, P>>,
+    ) -> Fallible<()> {
+        match mss_pk {
+            Some(mss_pk) => ensure!(
+                self.author_mss_pks.get(link_to) == Some(mss_pk),
+                "Bad keyload signature."
+            ),
+            None => ensure!(!self.require_signed_keyloads, "Keyload is not signed."),
+        }
+        Ok(())
+    }
+
+    pub fn unwrap_keyload_private<'a, 'b>(
+        &'b self,
+        preparsed: PreparsedMessage<'a, TW, F, Link>,
+    ) -> Fallible<UnwrappedMessage<TW, F, Link, private_keyload::ContentUnwrap<'b, TW, F, P, Link>>> {
+        self.ensure_appinst(&preparsed)?;
+        let psks = self.opt_psk.iter().map(|(pskid, psk)| (pskid, psk)).collect();
+        let ntru_sk = self.opt_ntru.as_ref().map(|(ntru_sk, _)| ntru_sk);
+        let content = private_keyload::ContentUnwrap::new(psks, ntru_sk);
+        preparsed.unwrap(&*self.store.borrow(), content)
+    }
+
+    /// Try unwrapping session key from PrivateKeyload using Subscriber's pre-shared key or NTRU private key (if any).
+    ///
+    /// Signature is checked the same way as in `handle_keyload`.
+    pub fn handle_keyload_private<'a>(
+        &mut self,
+        preparsed: PreparsedMessage<'a, TW, F, Link>,
+        info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
+    ) -> Fallible<()> {
+        let unwrapped = self.unwrap_keyload_private(preparsed)?;
+        self.ensure_keyload_signature(&unwrapped.content.link, &unwrapped.content.mss_pk)?;
+        let link = unwrapped.link.rel().clone();
+        let content = unwrapped.commit(self.store.borrow_mut(), info)?;
+        let link_to = content.link.clone();
+        self.link_keyload(link, &link_to);
+        Ok(())
+    }
+
     pub fn unwrap_signed_packet<'a>(
         &self,
         preparsed: PreparsedMessage<'a, TW, F, Link>,
@@ -438,22 +558,21 @@ where
         preparsed.unwrap(&*self.store.borrow(), content)
     }
 
-    /// Verify new Author's MSS public key and update Author's MSS public key.
+    /// Verify signature with Author's MSS public key valid on the linked branch.
     pub fn handle_signed_packet<'a>(
         &mut self,
         preparsed: PreparsedMessage<'a, TW, F, Link>,
         info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
     ) -> Fallible<(Trytes<TW>, Trytes<TW>)> {
         ensure!(self.author_mss_pk.is_some(), "No Author's MSS public key found.");
-        let content = self
-            .unwrap_signed_packet(preparsed)?
-            .commit(self.store.borrow_mut(), info)?;
+        let unwrapped = self.unwrap_signed_packet(preparsed)?;
         ensure!(
-            self.author_mss_pk
-                .as_ref()
-                .map_or(false, |mss_pk| *mss_pk == content.mss_pk),
+            *self.author_mss_pks.try_get(&unwrapped.content.link)? == unwrapped.content.mss_pk,
             "Bad signed packet signature."
         );
+        let link = unwrapped.link.rel().clone();
+        let content = unwrapped.commit(self.store.borrow_mut(), info)?;
+        self.link_msg(link, &content.link);
         Ok((content.public_payload, content.masked_payload))
     }
 
@@ -472,50 +591,351 @@ where
         preparsed: PreparsedMessage<'a, TW, F, Link>,
         info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
     ) -> Fallible<(Trytes<TW>, Trytes<TW>)> {
-        let content = self
-            .unwrap_tagged_packet(preparsed)?
-            .commit(self.store.borrow_mut(), info)?;
+        let unwrapped = self.unwrap_tagged_packet(preparsed)?;
+        let link = unwrapped.link.rel().clone();
+        let content = unwrapped.commit(self.store.borrow_mut(), info)?;
+        self.link_msg(link, &content.msgid);
         Ok((content.public_payload, content.masked_payload))
     }
 
-    /*
-       /// Unwrap message.
-       pub fn handle_msg(
-           &mut self,
-           msg: &TbinaryMessage<TW, F, Link>,
-           info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
-       ) -> Fallible<()> {
-           if self.appinst.is_some() {
-               ensure!(
-                   self.appinst.as_ref().unwrap().base() == msg.link().base(),
-                   "Bad message application instance."
-               );
-           }
-
-           let preparsed = msg.parse_header()?;
-
-           if preparsed.check_content_type(announce::TYPE) {
-               self.handle_announcement(preparsed, info)?;
-               Ok(())
-           } else if preparsed.check_content_type(change_key::TYPE) {
-               self.handle_change_key(preparsed, info)?;
-               Ok(())
-           } else if preparsed.check_content_type(signed_packet::TYPE) {
-               self.handle_signed_packet(preparsed, info)?;
-               Ok(())
-           } else if preparsed.check_content_type(tagged_packet::TYPE) {
-               self.handle_tagged_packet(preparsed, info)?;
-               Ok(())
-           } else
-           /*
-           if preparsed.check_content_type(keyload::TYPE) {
-               self.handle_keyload(preparsed, info)?;
-               Ok(())
-           } else
-            */
-           {
-               bail!("Unsupported content type: '{}'.", preparsed.content_type())
-           }
-       }
-    */
+    /// Unwrap message with default logic.
+    ///
+    /// Subscriber can handle announce, change_key, keyload, signed and tagged packet messages.
+    pub fn handle_msg(
+        &mut self,
+        msg: &TbinaryMessage<TW, F, Link>,
+        info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
+    ) -> Fallible<UnwrappedChannelMessage<TW>> {
+        let preparsed = msg.parse_header()?;
+
+        if preparsed.check_content_type(announce::TYPE) {
+            self.handle_announcement(preparsed, info)?;
+            Ok(UnwrappedChannelMessage::Announce)
+        } else if preparsed.check_content_type(change_key::TYPE) {
+            self.handle_change_key(preparsed, info)?;
+            Ok(UnwrappedChannelMessage::ChangeKey)
+        } else if preparsed.check_content_type(keyload::TYPE) {
+            self.handle_keyload(preparsed, info)?;
+            Ok(UnwrappedChannelMessage::Keyload)
+        } else if preparsed.check_content_type(private_keyload::TYPE) {
+            self.handle_keyload_private(preparsed, info)?;
+            Ok(UnwrappedChannelMessage::Keyload)
+        } else if preparsed.check_content_type(signed_packet::TYPE) {
+            let (public_payload, masked_payload) = self.handle_signed_packet(preparsed, info)?;
+            Ok(UnwrappedChannelMessage::SignedPacket {
+                public_payload,
+                masked_payload,
+            })
+        } else if preparsed.check_content_type(tagged_packet::TYPE) {
+            let (public_payload, masked_payload) = self.handle_tagged_packet(preparsed, info)?;
+            Ok(UnwrappedChannelMessage::TaggedPacket {
+                public_payload,
+                masked_payload,
+            })
+        } else if preparsed.check_content_type(subscribe::TYPE) {
+            bail!("Can't handle subscribe message.")
+        } else if preparsed.check_content_type(unsubscribe::TYPE) {
+            bail!("Can't handle unsubscribe message.")
+        } else {
+            bail!("Unsupported content type: '{}'.", preparsed.content_type())
+        }
+    }
+}
+
+/// Type of Subscriber state, see `state` module.
+const STATE_TYPE: &str = "STREAMS9CHANNEL9SUBSCRIBER";
+
+/// Subscriber state is persisted with the following message.
+///
+/// ```pb3
+/// message SubscriberState {
+///     skip tryte prng_key[81];
+///     skip oneof {
+///         null empty = 0;
+///         Psk psk = 1;
+///     }
+///     skip oneof {
+///         null empty = 0;
+///         NtruKeyPair ntru = 1;
+///     }
+///     skip oneof {
+///         null empty = 0;
+///         link appinst = 1;
+///     }
+///     skip oneof {
+///         null empty = 0;
+///         tryte author_mss_pk[81] = 1;
+///     }
+///     skip size h;
+///     skip repeated(h) {
+///         skip link key_link;
+///         skip tryte author_mss_pk[81];
+///     }
+///     skip size b;
+///     skip repeated(b) {
+///         skip link msg;
+///         skip link key_link;
+///     }
+///     skip oneof {
+///         null empty = 0;
+///         tryte author_ntru_pk[3072] = 1;
+///     }
+///     skip size r;
+///     skip repeated(r) {
+///         skip link msg;
+///         skip link root;
+///     }
+///     skip size t;
+///     skip repeated(t) {
+///         skip link root;
+///         skip link tip;
+///     }
+///     skip tryte require_signed_keyloads;
+///     skip link_gen link_gen;
+///     skip store store;
+/// }
+/// message Psk {
+///     skip tryte pskid[27];
+///     skip tryte psk[81];
+/// }
+/// message NtruKeyPair {
+///     skip trit ntru_sk[1024];
+///     skip tryte ntru_pk[3072];
+/// }
+/// ```
+///
+/// Unlike Author, Subscriber's NTRU key pair depends on the nonce passed to `gen`,
+/// so it is stored explicitly. Author's old MSS public keys are stored together with the links
+/// of known messages referring to them. Link store contains spongos states of the joined
+/// keyloads, so messages linked to them can be unwrapped after restoring the state.
+impl<TW, F, P, Link, Store, LinkGen> SubscriberT<TW, F, P, Link, Store, LinkGen>
+where
+    TW: RngTbitWord + IntTbitWord + StringTbitWord + SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW> + Clone + Default,
+    P: mss::Parameters<TW>,
+    Link: HasLink + AbsorbExternalFallback<TW, F> + SkipFallback<TW, F> + Default + Clone + Eq,
+    <Link as HasLink>::Base: Eq + Debug,
+    <Link as HasLink>::Rel: Eq + hash::Hash + Clone + Debug + Default + SkipFallback<TW, F>,
+    Store: LinkStore<TW, F, <Link as HasLink>::Rel> + SkipFallback<TW, F> + Default,
+    LinkGen: ChannelLinkGenerator<TW, P, Link> + SkipFallback<TW, F> + Default,
+{
+    /// Export Subscriber state encrypted with `password`.
+    pub fn export(&self, password: &str) -> Fallible<Tbits<TW>> {
+        let prng_key = NTrytes(self.prng.secret_key().clone());
+        let opt_psk = self
+            .opt_psk
+            .as_ref()
+            .map(|(pskid, psk)| (NTrytes(pskid.clone()), NTrytes(psk.clone())));
+        let opt_ntru = self.opt_ntru.as_ref().map(|(sk, pk)| (sk, NTrytes(pk.tbits().clone())));
+        let opt_author_mss_pk = self.author_mss_pk.as_ref().map(|pk| NTrytes(pk.tbits().clone()));
+        let author_mss_pks: Vec<_> = self
+            .author_mss_pks
+            .keys()
+            .map(|(key_link, pk)| (key_link, NTrytes(pk.tbits().clone())))
+            .collect();
+        let opt_author_ntru_pk = self.author_ntru_pk.as_ref().map(|pk| NTrytes(pk.tbits().clone()));
+        let require_signed_keyloads = if self.require_signed_keyloads { Trint3(1) } else { Trint3(0) };
+        let store = self.store.borrow();
+
+        let buf_size = {
+            let mut ctx = sizeof::Context::<TW, F>::new();
+            ctx.skip(&prng_key)?
+                .optional(OneofMode::Skip, opt_psk.as_ref(), |ctx, (pskid, psk)| {
+                    ctx.skip(pskid)?.skip(psk)
+                })?
+                .optional(OneofMode::Skip, opt_ntru.as_ref(), |ctx, (sk, pk)| {
+                    ctx.skip(*sk)?.skip(pk)
+                })?
+                .optional(OneofMode::Skip, self.appinst.as_ref(), |ctx, appinst| {
+                    ctx.skip(<&Fallback<Link>>::from(appinst))
+                })?
+                .optional(OneofMode::Skip, opt_author_mss_pk.as_ref(), |ctx, pk| ctx.skip(pk))?
+                .skip(Size(author_mss_pks.len()))?
+                .repeated(author_mss_pks.iter(), |ctx, (key_link, mss_pk)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(*key_link))?
+                        .skip(mss_pk)
+                })?
+                .skip(Size(self.author_mss_pks.branches().len()))?
+                .repeated(self.author_mss_pks.branches(), |ctx, (msg, key_link)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(msg))?
+                        .skip(<&Fallback<<Link as HasLink>::Rel>>::from(key_link))
+                })?
+                .optional(OneofMode::Skip, opt_author_ntru_pk.as_ref(), |ctx, pk| ctx.skip(pk))?
+                .skip(Size(self.branches.roots().len()))?
+                .repeated(self.branches.roots(), |ctx, (msg, root)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(msg))?
+                        .skip(<&Fallback<<Link as HasLink>::Rel>>::from(root))
+                })?
+                .skip(Size(self.branches.tips().len()))?
+                .repeated(self.branches.tips(), |ctx, (root, tip)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(root))?
+                        .skip(<&Fallback<<Link as HasLink>::Rel>>::from(tip))
+                })?
+                .skip(&require_signed_keyloads)?
+                .skip(<&Fallback<LinkGen>>::from(&self.link_gen))?
+                .skip(<&Fallback<Store>>::from(&*store))?;
+            ctx.get_size()
+        };
+
+        let mut buf = Tbits::<TW>::zero(buf_size);
+        {
+            let mut ctx = wrap::Context::<TW, F, _>::new(buf.slice_mut());
+            ctx.skip(&prng_key)?
+                .optional(OneofMode::Skip, opt_psk.as_ref(), |ctx, (pskid, psk)| {
+                    ctx.skip(pskid)?.skip(psk)
+                })?
+                .optional(OneofMode::Skip, opt_ntru.as_ref(), |ctx, (sk, pk)| {
+                    ctx.skip(*sk)?.skip(pk)
+                })?
+                .optional(OneofMode::Skip, self.appinst.as_ref(), |ctx, appinst| {
+                    ctx.skip(<&Fallback<Link>>::from(appinst))
+                })?
+                .optional(OneofMode::Skip, opt_author_mss_pk.as_ref(), |ctx, pk| ctx.skip(pk))?
+                .skip(Size(author_mss_pks.len()))?
+                .repeated(author_mss_pks.iter(), |ctx, (key_link, mss_pk)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(*key_link))?
+                        .skip(mss_pk)
+                })?
+                .skip(Size(self.author_mss_pks.branches().len()))?
+                .repeated(self.author_mss_pks.branches(), |ctx, (msg, key_link)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(msg))?
+                        .skip(<&Fallback<<Link as HasLink>::Rel>>::from(key_link))
+                })?
+                .optional(OneofMode::Skip, opt_author_ntru_pk.as_ref(), |ctx, pk| ctx.skip(pk))?
+                .skip(Size(self.branches.roots().len()))?
+                .repeated(self.branches.roots(), |ctx, (msg, root)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(msg))?
+                        .skip(<&Fallback<<Link as HasLink>::Rel>>::from(root))
+                })?
+                .skip(Size(self.branches.tips().len()))?
+                .repeated(self.branches.tips(), |ctx, (root, tip)| {
+                    ctx.skip(<&Fallback<<Link as HasLink>::Rel>>::from(root))?
+                        .skip(<&Fallback<<Link as HasLink>::Rel>>::from(tip))
+                })?
+                .skip(&require_signed_keyloads)?
+                .skip(<&Fallback<LinkGen>>::from(&self.link_gen))?
+                .skip(<&Fallback<Store>>::from(&*store))?;
+            ensure!(ctx.stream.is_empty(), "OStream has not been exhausted.");
+        }
+
+        state::wrap_state::<TW, F>(STATE_TYPE, password, buf)
+    }
+
+    /// Import Subscriber state previously exported with `export` and encrypted with `password`.
+    pub fn import(state: &Tbits<TW>, password: &str) -> Fallible<Self> {
+        let state = state::unwrap_state::<TW, F>(STATE_TYPE, password, state)?;
+
+        let mut prng_key = NTrytes::zero(prng::Prng::<TW, P::PrngG>::KEY_SIZE);
+        let mut has_psk = None;
+        let mut pskid = NTrytes::zero(psk::PSKID_SIZE);
+        let mut psk = NTrytes::zero(psk::PSK_SIZE);
+        let mut opt_ntru: Option<(ntru::PrivateKey<TW, F>, ntru::PublicKey<TW, F>)> = None;
+        let mut appinst = None;
+        let mut author_mss_pk: Option<mss::PublicKey<TW, P>> = None;
+        let mut repeated_author_mss_pks = Size(0);
+        let mut author_mss_pks = HashMap::new();
+        let mut repeated_author_mss_branches = Size(0);
+        let mut author_mss_branches = HashMap::new();
+        let mut author_ntru_pk: Option<ntru::PublicKey<TW, F>> = None;
+        let mut repeated_branch_roots = Size(0);
+        let mut branch_roots = HashMap::new();
+        let mut repeated_branch_tips = Size(0);
+        let mut branch_tips = HashMap::new();
+        let mut require_signed_keyloads = Trint3(-1);
+        let mut link_gen = LinkGen::default();
+        let mut store = Store::default();
+
+        {
+            let mut ctx = unwrap::Context::<TW, F, _>::new(state.slice());
+            ctx.skip(&mut prng_key)?
+                .optional(OneofMode::Skip, &mut has_psk, |ctx, ()| {
+                    ctx.skip(&mut pskid)?.skip(&mut psk)
+                })?
+                .optional(OneofMode::Skip, &mut opt_ntru, |ctx, (sk, pk)| {
+                    let mut pk_trits = NTrytes::zero(ntru::PK_SIZE);
+                    ctx.skip(sk)?.skip(&mut pk_trits)?;
+                    *pk.tbits_mut() = pk_trits.0;
+                    ensure!(pk.validate(), "Bad NTRU public key.");
+                    Ok(ctx)
+                })?
+                .optional(OneofMode::Skip, &mut appinst, |ctx, link| {
+                    ctx.skip(<&mut Fallback<Link>>::from(link))
+                })?
+                .optional(OneofMode::Skip, &mut author_mss_pk, |ctx, pk| {
+                    let mut pk_trits = NTrytes::zero(P::PUBLIC_KEY_SIZE);
+                    ctx.skip(&mut pk_trits)?;
+                    *pk.tbits_mut() = pk_trits.0;
+                    Ok(ctx)
+                })?
+                .skip(&mut repeated_author_mss_pks)?
+                .repeated(repeated_author_mss_pks, |ctx| {
+                    let mut key_link = <Link as HasLink>::Rel::default();
+                    let mut pk = mss::PublicKey::<TW, P>::default();
+                    let mut pk_trits = NTrytes::zero(P::PUBLIC_KEY_SIZE);
+                    ctx.skip(<&mut Fallback<<Link as HasLink>::Rel>>::from(&mut key_link))?
+                        .skip(&mut pk_trits)?;
+                    *pk.tbits_mut() = pk_trits.0;
+                    author_mss_pks.insert(key_link, pk);
+                    Ok(ctx)
+                })?
+                .skip(&mut repeated_author_mss_branches)?
+                .repeated(repeated_author_mss_branches, |ctx| {
+                    let mut msg = <Link as HasLink>::Rel::default();
+                    let mut key_link = <Link as HasLink>::Rel::default();
+                    ctx.skip(<&mut Fallback<<Link as HasLink>::Rel>>::from(&mut msg))?
+                        .skip(<&mut Fallback<<Link as HasLink>::Rel>>::from(&mut key_link))?;
+                    author_mss_branches.insert(msg, key_link);
+                    Ok(ctx)
+                })?
+                .optional(OneofMode::Skip, &mut author_ntru_pk, |ctx, pk| {
+                    let mut pk_trits = NTrytes::zero(ntru::PK_SIZE);
+                    ctx.skip(&mut pk_trits)?;
+                    *pk.tbits_mut() = pk_trits.0;
+                    ensure!(pk.validate(), "Bad Author NTRU public key.");
+                    Ok(ctx)
+                })?
+                .skip(&mut repeated_branch_roots)?
+                .repeated(repeated_branch_roots, |ctx| {
+                    let mut msg = <Link as HasLink>::Rel::default();
+                    let mut root = <Link as HasLink>::Rel::default();
+                    ctx.skip(<&mut Fallback<<Link as HasLink>::Rel>>::from(&mut msg))?
+                        .skip(<&mut Fallback<<Link as HasLink>::Rel>>::from(&mut root))?;
+                    branch_roots.insert(msg, root);
+                    Ok(ctx)
+                })?
+                .skip(&mut repeated_branch_tips)?
+                .repeated(repeated_branch_tips, |ctx| {
+                    let mut root = <Link as HasLink>::Rel::default();
+                    let mut tip = <Link as HasLink>::Rel::default();
+                    ctx.skip(<&mut Fallback<<Link as HasLink>::Rel>>::from(&mut root))?
+                        .skip(<&mut Fallback<<Link as HasLink>::Rel>>::from(&mut tip))?;
+                    branch_tips.insert(root, tip);
+                    Ok(ctx)
+                })?
+                .skip(&mut require_signed_keyloads)?
+                .guard(
+                    require_signed_keyloads == Trint3(0) || require_signed_keyloads == Trint3(1),
+                    "Bad require signed keyloads flag",
+                )?
+                .skip(<&mut Fallback<LinkGen>>::from(&mut link_gen))?
+                .skip(<&mut Fallback<Store>>::from(&mut store))?;
+            ensure!(ctx.stream.is_empty(), "Trailing trits after Subscriber state.");
+        }
+
+        Ok(Self {
+            prng: prng::Prng::init(prng_key.0),
+            opt_psk: has_psk.map(|()| (pskid.0, psk.0)),
+            opt_ntru,
+
+            appinst,
+            author_mss_pk,
+            author_mss_pks: KeyHistory::from_parts(author_mss_pks, author_mss_branches)?,
+            author_ntru_pk,
+            branches: Branches::from_parts(branch_roots, branch_tips)?,
+            require_signed_keyloads: require_signed_keyloads == Trint3(1),
+
+            store: RefCell::new(store),
+            link_gen,
+        })
+    }
 }
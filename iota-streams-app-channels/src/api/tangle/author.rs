@@ -1,6 +1,9 @@
 //! Customize Author with default implementation for use over the Tangle.
 
-use failure::Fallible;
+use failure::{
+    ensure,
+    Fallible,
+};
 use std::str::FromStr;
 
 use super::*;
@@ -35,6 +38,18 @@ impl Author {
         }
     }
 
+    /// Export Author state encrypted with tryte-encoded `password`.
+    pub fn export(&self, password: &str) -> Fallible<Trytes> {
+        Ok(pb3_types::Trytes(self.imp.export(password)?))
+    }
+
+    /// Import Author state exported with `export` and encrypted with `password`.
+    pub fn import(state: &Trytes, password: &str) -> Fallible<Self> {
+        Ok(Self {
+            imp: AuthorT::import(&state.0, password)?,
+        })
+    }
+
     /// Channel app instance.
     pub fn channel_address(&self) -> &ChannelAddress {
         &self.imp.appinst.appinst
@@ -50,17 +65,115 @@ impl Author {
         self.imp.change_key(link_to.rel(), MsgInfo::ChangeKey)
     }
 
+    /// Sign keyloads with Author's MSS private key.
+    pub fn set_sign_keyloads(&mut self, sign_keyloads: bool) {
+        self.imp.sign_keyloads = sign_keyloads;
+    }
+
     /// Create a new keyload for a list of subscribers.
     pub fn share_keyload(&mut self, link_to: &Address, psk_ids: &PskIds, ntru_pkids: &NtruPkids) -> Fallible<Message> {
         self.imp
             .share_keyload(link_to.rel(), psk_ids, ntru_pkids, MsgInfo::Keyload)
     }
 
+    /// Create a new keyload for a list of subscribers without revealing their identities.
+    pub fn share_keyload_private(
+        &mut self,
+        link_to: &Address,
+        psk_ids: &PskIds,
+        ntru_pkids: &NtruPkids,
+    ) -> Fallible<Message> {
+        self.imp
+            .share_keyload_private(link_to.rel(), psk_ids, ntru_pkids, MsgInfo::Keyload)
+    }
+
     /// Create keyload for all subscribed subscribers.
     pub fn share_keyload_for_everyone(&mut self, link_to: &Address) -> Fallible<Message> {
         self.imp.share_keyload_for_everyone(link_to.rel(), MsgInfo::Keyload)
     }
 
+    /// Create keyload for all subscribed subscribers without revealing their identities.
+    pub fn share_keyload_private_for_everyone(&mut self, link_to: &Address) -> Fallible<Message> {
+        self.imp
+            .share_keyload_private_for_everyone(link_to.rel(), MsgInfo::Keyload)
+    }
+
+    /// Start branch `name` with a new keyload for a list of subscribers, `name` must be a tryte string.
+    pub fn new_branch(
+        &mut self,
+        name: &str,
+        link_to: &Address,
+        psk_ids: &PskIds,
+        ntru_pkids: &NtruPkids,
+    ) -> Fallible<Message> {
+        ensure!(Tbits::<DefaultTW>::from_str(name).is_ok(), "Branch name '{}' is not a tryte string.", name);
+        ensure!(self.imp.branch_root(name).is_none(), "Branch '{}' already exists.", name);
+        let msg = self.share_keyload(link_to, psk_ids, ntru_pkids)?;
+        self.imp.name_branch(name, msg.link.rel())?;
+        Ok(msg)
+    }
+
+    /// Start branch `name` with a new keyload for all subscribed subscribers, `name` must be a tryte string.
+    pub fn new_branch_for_everyone(&mut self, name: &str, link_to: &Address) -> Fallible<Message> {
+        ensure!(Tbits::<DefaultTW>::from_str(name).is_ok(), "Branch name '{}' is not a tryte string.", name);
+        ensure!(self.imp.branch_root(name).is_none(), "Branch '{}' already exists.", name);
+        let msg = self.share_keyload_for_everyone(link_to)?;
+        self.imp.name_branch(name, msg.link.rel())?;
+        Ok(msg)
+    }
+
+    /// Name the branch started with keyload `root`.
+    pub fn name_branch(&mut self, name: &str, root: &Address) -> Fallible<()> {
+        ensure!(
+            self.imp.branch_tip(root.rel()).is_some(),
+            "Message {} doesn't start a branch.",
+            root
+        );
+        self.imp.name_branch(name, root.rel())
+    }
+
+    /// Names of the known branches.
+    pub fn branch_names(&self) -> Vec<&str> {
+        self.imp.branch_names()
+    }
+
+    /// Link to the last message in branch `name`.
+    pub fn branch_tip(&self, name: &str) -> Option<Address> {
+        self.imp
+            .branch_root(name)
+            .and_then(|root| self.imp.branch_tip(root))
+            .map(|tip| Address::from_base_rel(self.channel_address(), tip))
+    }
+
+    fn try_branch_tip(&self, name: &str) -> Fallible<Address> {
+        match self.branch_tip(name) {
+            Some(tip) => Ok(tip),
+            None => bail!("Branch '{}' is not known.", name),
+        }
+    }
+
+    /// Create a signed packet linked to the last message in branch `name`.
+    pub fn sign_packet_in_branch(
+        &mut self,
+        name: &str,
+        public_payload: &Trytes,
+        masked_payload: &Trytes,
+    ) -> Fallible<Message> {
+        let tip = self.try_branch_tip(name)?;
+        self.sign_packet(&tip, public_payload, masked_payload)
+    }
+
+    /// Create a tagged packet linked to the last message in branch `name`.
+    pub fn tag_packet_in_branch(
+        &mut self,
+        name: &str,
+        public_payload: &Trytes,
+        masked_payload: &Trytes,
+    ) -> Fallible<Message> {
+        let tip = self.try_branch_tip(name)?;
+        self.tag_packet(&tip, public_payload, masked_payload)
+    }
+
     /// Create a signed packet.
     pub fn sign_packet(
         &mut self,
@@ -72,6 +185,33 @@ impl Author {
             .sign_packet(link_to.rel(), public_payload, masked_payload, MsgInfo::SignedPacket)
     }
 
+    /// Set policy for changing MSS key in `sign_packet_with_key_rotation`.
+    pub fn set_key_rotation_policy(&mut self, policy: KeyRotationPolicy) {
+        self.imp.key_rotation = policy;
+    }
+
+    /// Number of one-time signatures left in MSS key valid on the branch of `link_to`.
+    pub fn mss_keys_left(&self, link_to: &Address) -> Fallible<usize> {
+        self.imp.mss_keys_left(link_to.rel())
+    }
+
+    /// Create a signed packet, change key first if required by the key rotation policy.
+    /// Returns ChangeKey message, if any, and the signed packet.
+    pub fn sign_packet_with_key_rotation(
+        &mut self,
+        link_to: &Address,
+        public_payload: &Trytes,
+        masked_payload: &Trytes,
+    ) -> Fallible<(Option<Message>, Message)> {
+        self.imp.sign_packet_with_key_rotation(
+            link_to.rel(),
+            public_payload,
+            masked_payload,
+            MsgInfo::ChangeKey,
+            MsgInfo::SignedPacket,
+        )
+    }
+
     /// Create a tagged packet.
     pub fn tag_packet(
         &mut self,
@@ -88,6 +228,66 @@ impl Author {
         self.imp.handle_tagged_packet(preparsed, MsgInfo::TaggedPacket)
     }
 
+    /// Unwrap message with default logic, see `AuthorT::handle_msg`.
+    pub fn handle_msg(&mut self, msg: &Message) -> Fallible<UnwrappedChannelMessage> {
+        let info = MsgInfo::from_preparsed(&msg.parse_header()?)?;
+        self.imp.handle_msg(msg, info)
+    }
+
+    /// Handle the first valid message of `msgs` found at the same link, returns its index.
+    ///
+    /// Messages are tried in order; Tangle transport returns them earliest first.
+    pub fn handle_first_valid_msg(&mut self, msgs: &[Message]) -> Fallible<(usize, UnwrappedChannelMessage)> {
+        handle_first_valid(msgs, |msg| self.handle_msg(msg))
+    }
+
+    /// Receive messages with link `link` and handle the first valid one.
+    pub fn recv_msg_with_options<T: Transport>(
+        &mut self,
+        transport: &mut T,
+        link: &Address,
+        recv_opt: T::RecvOptions,
+    ) -> Fallible<UnwrappedChannelMessage> {
+        let msgs = transport.recv_messages_with_options(link, recv_opt)?;
+        Ok(self.handle_first_valid_msg(&msgs)?.1)
+    }
+
+    /// Receive messages with link `link` with default receive options and handle the first valid one.
+    pub fn recv_msg<T: Transport>(&mut self, transport: &mut T, link: &Address) -> Fallible<UnwrappedChannelMessage>
+    where
+        T::RecvOptions: Default,
+    {
+        self.recv_msg_with_options(transport, link, T::RecvOptions::default())
+    }
+
+    /// Receive messages with link `link` and handle the first valid one, returns its transport metadata too.
+    pub fn recv_msg_with_metadata_and_options<T: Transport>(
+        &mut self,
+        transport: &mut T,
+        link: &Address,
+        recv_opt: T::RecvOptions,
+    ) -> Fallible<(UnwrappedChannelMessage, MessageMetadata)> {
+        let (msgs, mut metadata): (Vec<Message>, Vec<MessageMetadata>) = transport
+            .recv_messages_with_metadata_and_options(link, recv_opt)?
+            .into_iter()
+            .unzip();
+        let (i, unwrapped) = self.handle_first_valid_msg(&msgs)?;
+        Ok((unwrapped, metadata.swap_remove(i)))
+    }
+
+    /// Receive messages with link `link` with default receive options and handle the first valid one,
+    /// returns its transport metadata too.
+    pub fn recv_msg_with_metadata<T: Transport>(
+        &mut self,
+        transport: &mut T,
+        link: &Address,
+    ) -> Fallible<(UnwrappedChannelMessage, MessageMetadata)>
+    where
+        T::RecvOptions: Default,
+    {
+        self.recv_msg_with_metadata_and_options(transport, link, T::RecvOptions::default())
+    }
+
     /// Subscribe a new subscriber.
     pub fn unwrap_subscribe<'a>(&mut self, preparsed: Preparsed<'a>) -> Fallible<()> {
         self.imp.handle_subscribe(preparsed, MsgInfo::Subscribe)
@@ -97,4 +297,33 @@ impl Author {
     pub fn unwrap_unsubscribe<'a>(&mut self, preparsed: Preparsed<'a>) -> Fallible<()> {
         self.imp.handle_unsubscribe(preparsed, MsgInfo::Unsubscribe)
     }
+
+    /// Set policy for approving new subscribers.
+    pub fn set_subscribe_policy(&mut self, policy: SubscribePolicy) {
+        self.imp.subscribe_policy = policy;
+    }
+
+    /// Approve the subscriber registered with Subscribe message `link`.
+    pub fn approve_subscriber(&mut self, link: &Address) -> Fallible<()> {
+        self.imp.approve_subscriber(link.rel())
+    }
+
+    /// Remove the subscriber registered with Subscribe message `link`.
+    pub fn remove_subscriber(&mut self, link: &Address) -> Fallible<()> {
+        self.imp.remove_subscriber(link.rel())
+    }
+
+    /// Is the subscriber registered with Subscribe message `link` approved?
+    pub fn is_subscriber_approved(&self, link: &Address) -> bool {
+        self.imp.is_subscriber_approved(link.rel())
+    }
+
+    /// Links to Subscribe messages of registered subscribers, approved or not.
+    pub fn subscribers(&self) -> Vec<Address> {
+        self.imp
+            .subscribers
+            .keys()
+            .map(|msgid| Address::from_base_rel(self.channel_address(), msgid))
+            .collect()
+    }
 }
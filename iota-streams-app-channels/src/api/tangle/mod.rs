@@ -1,5 +1,10 @@
 //! Default parameters for Author and Subscriber types.
 
+use failure::{
+    bail,
+    Fallible,
+};
+
 use iota_streams_app::{
     message,
     transport::{
@@ -10,13 +15,36 @@ use iota_streams_app::{
 use iota_streams_core::{
     psk,
     sponge::prp::troika::Troika,
-    tbits::trinary::Trit,
+    tbits::trinary::{
+        Trint3,
+        Trit,
+    },
 };
 use iota_streams_core_mss::signature::mss;
 use iota_streams_core_ntru::key_encapsulation::ntru;
+use crate::message::{
+    announce,
+    change_key,
+    keyload,
+    private_keyload,
+    signed_packet,
+    subscribe,
+    tagged_packet,
+    unsubscribe,
+};
 use iota_streams_protobuf3::{
+    command::{
+        sizeof,
+        unwrap,
+        wrap,
+        Skip,
+    },
+    io,
     types as pb3_types,
-    types::DefaultLinkStore,
+    types::{
+        DefaultLinkStore,
+        SkipFallback,
+    },
 };
 
 /// Default tbit word encoding.
@@ -47,16 +75,35 @@ pub type Message = message::TbinaryMessage<DefaultTW, DefaultF, Address>;
 /// Message type with parsed header.
 pub type Preparsed<'a> = message::PreparsedMessage<'a, DefaultTW, DefaultF, Address>;
 
+/// Policy for trusting NTRU public keys of new subscribers.
+pub type SubscribePolicy = crate::api::author::SubscribePolicy<DefaultTW, DefaultF>;
+
+/// Policy for changing Author's MSS key automatically.
+pub use crate::api::author::KeyRotationPolicy;
+
+/// Transport metadata of a received message, eg. Tangle timestamps and hashes.
+pub use iota_streams_app::transport::MessageMetadata;
+
+/// Result of handling a message with `handle_msg`.
+pub type UnwrappedChannelMessage = crate::api::UnwrappedChannelMessage<DefaultTW>;
+
 /// Link Generator specifies algorithm for generating new message addressed.
 pub type LinkGen = DefaultTangleLinkGenerator<DefaultTW, DefaultF>;
 
 /// Test Transport.
 pub type BucketTransport = transport::BucketTransport<DefaultTW, DefaultF, Address>;
 
+/// Transport storing messages in a directory.
+pub type FileTransport = transport::FileTransport<DefaultTW, DefaultF>;
+
 pub trait Transport: transport::Transport<DefaultTW, DefaultF, Address> {}
 
 impl<T> Transport for T where T: transport::Transport<DefaultTW, DefaultF, Address> {}
 
+pub trait AsyncTransport: transport::AsyncTransport<DefaultTW, DefaultF, Address> {}
+
+impl<T> AsyncTransport for T where T: transport::AsyncTransport<DefaultTW, DefaultF, Address> {}
+
 /// Message associated info, just message type indicator.
 #[derive(Copy, Clone)]
 pub enum MsgInfo {
@@ -69,6 +116,104 @@ pub enum MsgInfo {
     Unsubscribe,
 }
 
+/// Placeholder value, only used when restoring link store.
+impl Default for MsgInfo {
+    fn default() -> Self {
+        MsgInfo::Announce
+    }
+}
+
+impl MsgInfo {
+    fn to_trint3(self) -> Trint3 {
+        match self {
+            MsgInfo::Announce => Trint3(0),
+            MsgInfo::ChangeKey => Trint3(1),
+            MsgInfo::Keyload => Trint3(2),
+            MsgInfo::SignedPacket => Trint3(3),
+            MsgInfo::TaggedPacket => Trint3(4),
+            MsgInfo::Subscribe => Trint3(5),
+            MsgInfo::Unsubscribe => Trint3(6),
+        }
+    }
+
+    /// Message info corresponding to the content type of the message.
+    fn from_preparsed(preparsed: &Preparsed) -> Fallible<Self> {
+        if preparsed.check_content_type(announce::TYPE) {
+            Ok(MsgInfo::Announce)
+        } else if preparsed.check_content_type(change_key::TYPE) {
+            Ok(MsgInfo::ChangeKey)
+        } else if preparsed.check_content_type(keyload::TYPE) || preparsed.check_content_type(private_keyload::TYPE) {
+            Ok(MsgInfo::Keyload)
+        } else if preparsed.check_content_type(signed_packet::TYPE) {
+            Ok(MsgInfo::SignedPacket)
+        } else if preparsed.check_content_type(tagged_packet::TYPE) {
+            Ok(MsgInfo::TaggedPacket)
+        } else if preparsed.check_content_type(subscribe::TYPE) {
+            Ok(MsgInfo::Subscribe)
+        } else if preparsed.check_content_type(unsubscribe::TYPE) {
+            Ok(MsgInfo::Unsubscribe)
+        } else {
+            bail!("Unsupported content type: '{}'.", preparsed.content_type())
+        }
+    }
+
+    fn from_trint3(t: Trint3) -> Fallible<Self> {
+        Ok(match t {
+            Trint3(0) => MsgInfo::Announce,
+            Trint3(1) => MsgInfo::ChangeKey,
+            Trint3(2) => MsgInfo::Keyload,
+            Trint3(3) => MsgInfo::SignedPacket,
+            Trint3(4) => MsgInfo::TaggedPacket,
+            Trint3(5) => MsgInfo::Subscribe,
+            Trint3(6) => MsgInfo::Unsubscribe,
+            _ => bail!("Bad MsgInfo: {:?}", t),
+        })
+    }
+}
+
+/// Message info is stored in link store and is `skip`ped when the store is persisted.
+impl SkipFallback<DefaultTW, DefaultF> for MsgInfo {
+    fn sizeof_skip(&self, ctx: &mut sizeof::Context<DefaultTW, DefaultF>) -> Fallible<()> {
+        ctx.skip(self.to_trint3())?;
+        Ok(())
+    }
+    fn wrap_skip<OS: io::OStream<DefaultTW>>(&self, ctx: &mut wrap::Context<DefaultTW, DefaultF, OS>) -> Fallible<()> {
+        ctx.skip(self.to_trint3())?;
+        Ok(())
+    }
+    fn unwrap_skip<IS: io::IStream<DefaultTW>>(
+        &mut self,
+        ctx: &mut unwrap::Context<DefaultTW, DefaultF, IS>,
+    ) -> Fallible<()> {
+        let mut t = Trint3(-1);
+        ctx.skip(&mut t)?;
+        *self = Self::from_trint3(t)?;
+        Ok(())
+    }
+}
+
+/// Handle the first of `msgs` accepted by `handle`, returns its index and the result.
+///
+/// Several messages can be found at the same link, eg. spam or a competing bundle, only
+/// the one that unwraps against the link store is genuine. Error of the last message is
+/// returned if none is accepted.
+fn handle_first_valid<R>(
+    msgs: &[Message],
+    mut handle: impl FnMut(&Message) -> Fallible<R>,
+) -> Fallible<(usize, R)> {
+    let mut last_error = None;
+    for (i, msg) in msgs.iter().enumerate() {
+        match handle(msg) {
+            Ok(r) => return Ok((i, r)),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    match last_error {
+        Some(e) => Err(e.context(format!("None of {} messages is valid.", msgs.len())).into()),
+        None => bail!("No messages to handle."),
+    }
+}
+
 /// Link Store.
 pub type Store = DefaultLinkStore<DefaultTW, DefaultF, MsgId<DefaultTW>, MsgInfo>;
 
@@ -78,7 +223,10 @@ mod subscriber;
 /// Tangle-specific Channel Author type.
 pub use author::Author;
 /// Tangle-specific Channel Subscriber type.
-pub use subscriber::Subscriber;
+pub use subscriber::{
+    Subscriber,
+    SYNC_LOOKAHEAD,
+};
 
 #[cfg(test)]
 mod test;
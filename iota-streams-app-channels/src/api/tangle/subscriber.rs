@@ -1,10 +1,17 @@
 //! Customize Subscriber with default parameters for use over the Tangle.
 
-use failure::Fallible;
+use failure::{
+    ensure,
+    Fallible,
+};
 use std::str::FromStr;
 
 use super::*;
-use crate::api::subscriber::SubscriberT;
+use crate::api::{
+    branches::Branches,
+    key_history::KeyHistory,
+    subscriber::SubscriberT,
+};
 use iota_streams_app::message::HasLink as _;
 
 use iota_streams_core::{
@@ -14,26 +21,60 @@ use iota_streams_core::{
 
 type SubscriberImp = SubscriberT<DefaultTW, DefaultF, DefaultP, Address, Store, LinkGen>;
 
+/// Number of Author's message counters probed ahead of the last found message.
+pub const SYNC_LOOKAHEAD: usize = 3;
+
+/// Channel synchronisation state.
+///
+/// Links of Author's messages are derived from the link of the message they are attached to
+/// and Author's message counter. Announce has counter 1 and each subsequent Author's message
+/// increments it.
+#[derive(Default)]
+struct SyncState {
+    /// Counter of the last handled Author's message.
+    counter: usize,
+
+    /// Ids of handled messages; new messages can be attached to any of them.
+    msgids: Vec<MsgId<DefaultTW>>,
+}
+
 /// Subscriber type.
 pub struct Subscriber {
     imp: SubscriberImp,
+    sync: SyncState,
 }
 
 impl Subscriber {
     /// Create a new Subscriber instance, optionally generate NTRU keypair.
+    ///
+    /// Message counter is derived from `seed`, so that messages of different Subscribers
+    /// attached to the same message (eg. Subscribe) get distinct links. Note, Subscribers
+    /// created by earlier versions started with zero counter and derived different links
+    /// from the same seed; exported Subscriber state keeps its counter.
     pub fn new(seed: &str, with_ntru: bool) -> Self {
         let nonce = Tbits::from_str("TANGLESUBSCRIBER").unwrap();
+        let prng = prng::dbg_init_str(seed);
+        let counter_nonce = Tbits::from_str("LINKGENCOUNTER").unwrap();
+        let counter = prng.gen_tbits(&counter_nonce, 27).slice().get_usize();
         Self {
-            imp: SubscriberT::gen(
-                Store::default(),
-                LinkGen::default(),
-                prng::dbg_init_str(seed),
-                &nonce,
-                with_ntru,
-            ),
+            imp: SubscriberT::gen(Store::default(), LinkGen::with_counter(counter), prng, &nonce, with_ntru),
+            sync: SyncState::default(),
         }
     }
 
+    /// Export Subscriber state encrypted with tryte-encoded `password`.
+    pub fn export(&self, password: &str) -> Fallible<Trytes> {
+        Ok(pb3_types::Trytes(self.imp.export(password)?))
+    }
+
+    /// Import Subscriber state exported with `export` and encrypted with `password`.
+    pub fn import(state: &Trytes, password: &str) -> Fallible<Self> {
+        Ok(Self {
+            imp: SubscriberT::import(&state.0, password)?,
+            sync: SyncState::default(),
+        })
+    }
+
     /// Ie. has Announce message been handled?
     pub fn is_registered(&self) -> bool {
         self.imp.appinst.is_some()
@@ -43,7 +84,10 @@ impl Subscriber {
     pub fn unregister(&mut self) {
         self.imp.appinst = None;
         self.imp.author_mss_pk = None;
+        self.imp.author_mss_pks = KeyHistory::default();
         self.imp.author_ntru_pk = None;
+        self.imp.branches = Branches::default();
+        self.sync = SyncState::default();
     }
 
     /// Return Channel app instance.
@@ -61,6 +105,11 @@ impl Subscriber {
         &self.imp.author_ntru_pk
     }
 
+    /// Reject keyloads not signed by Author.
+    pub fn set_require_signed_keyloads(&mut self, require_signed_keyloads: bool) {
+        self.imp.require_signed_keyloads = require_signed_keyloads;
+    }
+
     /// Create tagged packet.
     pub fn tag_packet(
         &mut self,
@@ -72,6 +121,40 @@ impl Subscriber {
             .tag_packet(link_to.rel(), public_payload, masked_payload, MsgInfo::TaggedPacket)
     }
 
+    /// Links to keyloads starting the branches Subscriber has access to.
+    pub fn branches(&self) -> Vec<Address> {
+        match self.channel_address() {
+            Some(appinst) => self
+                .imp
+                .branch_roots()
+                .into_iter()
+                .map(|root| Address::from_base_rel(appinst, root))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Link to the last handled message in the branch started with keyload `root`.
+    /// Branch tips advance as messages are handled, eg. with `sync_state`.
+    pub fn branch_tip(&self, root: &Address) -> Option<Address> {
+        self.imp
+            .branch_tip(root.rel())
+            .map(|tip| Address::from_base_rel(&root.appinst, tip))
+    }
+
+    /// Create tagged packet linked to the last message in the branch started with keyload `root`.
+    pub fn tag_packet_in_branch(
+        &mut self,
+        root: &Address,
+        public_payload: &Trytes,
+        masked_payload: &Trytes,
+    ) -> Fallible<Message> {
+        match self.branch_tip(root) {
+            Some(tip) => self.tag_packet(&tip, public_payload, masked_payload),
+            None => bail!("Branch {} is not known.", root),
+        }
+    }
+
     /// Subscribe to a Channel app instance.
     pub fn subscribe(&mut self, link_to: &Address) -> Fallible<Message> {
         //TODO: remove link_to
@@ -105,6 +188,12 @@ impl Subscriber {
         Ok(())
     }
 
+    /// Handle keyload hiding recipients' identities.
+    pub fn unwrap_keyload_private<'a>(&mut self, preparsed: Preparsed<'a>) -> Fallible<()> {
+        self.imp.handle_keyload_private(preparsed, MsgInfo::Keyload)?;
+        Ok(())
+    }
+
     /// Unwrap and verify signed packet.
     pub fn unwrap_signed_packet<'a>(&mut self, preparsed: Preparsed<'a>) -> Fallible<(Trytes, Trytes)> {
         self.imp.handle_signed_packet(preparsed, MsgInfo::SignedPacket)
@@ -114,4 +203,179 @@ impl Subscriber {
     pub fn unwrap_tagged_packet<'a>(&mut self, preparsed: Preparsed<'a>) -> Fallible<(Trytes, Trytes)> {
         self.imp.handle_tagged_packet(preparsed, MsgInfo::TaggedPacket)
     }
+
+    /// Unwrap message with default logic, see `SubscriberT::handle_msg`.
+    pub fn handle_msg(&mut self, msg: &Message) -> Fallible<UnwrappedChannelMessage> {
+        let info = MsgInfo::from_preparsed(&msg.parse_header()?)?;
+        let unwrapped = self.imp.handle_msg(msg, info)?;
+        if let UnwrappedChannelMessage::Announce = unwrapped {
+            self.imp
+                .link_gen
+                .reset_appinst(self.imp.appinst.as_ref().unwrap().base().clone());
+        }
+        Ok(unwrapped)
+    }
+
+    /// Handle the first valid message of `msgs` found at the same link, returns its index.
+    ///
+    /// Messages are tried in order; Tangle transport returns them earliest first.
+    pub fn handle_first_valid_msg(&mut self, msgs: &[Message]) -> Fallible<(usize, UnwrappedChannelMessage)> {
+        handle_first_valid(msgs, |msg| self.handle_msg(msg))
+    }
+
+    /// Receive messages with link `link` and handle the first valid one.
+    pub fn recv_msg_with_options<T: Transport>(
+        &mut self,
+        transport: &mut T,
+        link: &Address,
+        recv_opt: T::RecvOptions,
+    ) -> Fallible<UnwrappedChannelMessage> {
+        let msgs = transport.recv_messages_with_options(link, recv_opt)?;
+        Ok(self.handle_first_valid_msg(&msgs)?.1)
+    }
+
+    /// Receive messages with link `link` with default receive options and handle the first valid one.
+    pub fn recv_msg<T: Transport>(&mut self, transport: &mut T, link: &Address) -> Fallible<UnwrappedChannelMessage>
+    where
+        T::RecvOptions: Default,
+    {
+        self.recv_msg_with_options(transport, link, T::RecvOptions::default())
+    }
+
+    /// Receive messages with link `link` and handle the first valid one, returns its transport metadata too.
+    pub fn recv_msg_with_metadata_and_options<T: Transport>(
+        &mut self,
+        transport: &mut T,
+        link: &Address,
+        recv_opt: T::RecvOptions,
+    ) -> Fallible<(UnwrappedChannelMessage, MessageMetadata)> {
+        let (msgs, mut metadata): (Vec<Message>, Vec<MessageMetadata>) = transport
+            .recv_messages_with_metadata_and_options(link, recv_opt)?
+            .into_iter()
+            .unzip();
+        let (i, unwrapped) = self.handle_first_valid_msg(&msgs)?;
+        Ok((unwrapped, metadata.swap_remove(i)))
+    }
+
+    /// Receive messages with link `link` with default receive options and handle the first valid one,
+    /// returns its transport metadata too.
+    pub fn recv_msg_with_metadata<T: Transport>(
+        &mut self,
+        transport: &mut T,
+        link: &Address,
+    ) -> Fallible<(UnwrappedChannelMessage, MessageMetadata)>
+    where
+        T::RecvOptions: Default,
+    {
+        self.recv_msg_with_metadata_and_options(transport, link, T::RecvOptions::default())
+    }
+
+    /// Start synchronisation from the Announce message.
+    fn init_sync(&mut self) -> Fallible<()> {
+        ensure!(self.is_registered(), "Subscriber is not registered to a channel.");
+        if self.sync.msgids.is_empty() {
+            self.sync.counter = 1;
+            self.sync.msgids.push(self.imp.appinst.as_ref().unwrap().msgid.clone());
+        }
+        Ok(())
+    }
+
+    /// Find the next Author's message(s) and handle them.
+    ///
+    /// Links with the next `SYNC_LOOKAHEAD` counters attached to any of the handled messages
+    /// are probed in order until some messages are handled. As counters are probed in order,
+    /// the message an Author's message is attached to is found before the message itself.
+    ///
+    /// Messages that have already been processed (eg. before Subscriber state was exported)
+    /// are skipped but still used to predict links. Messages that can't be handled (eg. spam
+    /// or keyloads for other recipients) are returned with the handler error, they are not used
+    /// to predict links and don't advance the counter. Messages of other Subscribers are not
+    /// predicted. Returns the newly found messages together with their links. Transport errors
+    /// other than `LinkNotFound` are returned as is.
+    pub fn fetch_next_msgs_with_options<T: Transport>(
+        &mut self,
+        transport: &mut T,
+        recv_opt: T::RecvOptions,
+    ) -> Fallible<Vec<(Address, Fallible<UnwrappedChannelMessage>)>>
+    where
+        T::RecvOptions: Copy,
+    {
+        self.init_sync()?;
+        let mut fetched = Vec::new();
+        for counter in self.sync.counter + 1..=self.sync.counter + SYNC_LOOKAHEAD {
+            let mut found = Vec::new();
+            for msgid in &self.sync.msgids {
+                let link = self.imp.link_gen.link_with_counter(msgid, counter);
+                match transport.recv_messages_with_options(&link, recv_opt) {
+                    Ok(msgs) => found.extend(msgs),
+                    Err(e) if transport::is_link_not_found(&e) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+
+            let mut any_handled = false;
+            for msg in found {
+                let handled = if self.imp.is_processed(msg.link.rel()) {
+                    true
+                } else {
+                    let result = self.handle_msg(&msg);
+                    let handled = result.is_ok();
+                    fetched.push((msg.link.clone(), result));
+                    handled
+                };
+                if handled && !self.sync.msgids.contains(&msg.link.msgid) {
+                    self.sync.msgids.push(msg.link.msgid.clone());
+                }
+                any_handled |= handled;
+            }
+            if any_handled {
+                self.sync.counter = counter;
+                break;
+            }
+        }
+        Ok(fetched)
+    }
+
+    /// Probe the next Author's message links with default receive options.
+    pub fn fetch_next_msgs<T: Transport>(
+        &mut self,
+        transport: &mut T,
+    ) -> Fallible<Vec<(Address, Fallible<UnwrappedChannelMessage>)>>
+    where
+        T::RecvOptions: Copy + Default,
+    {
+        self.fetch_next_msgs_with_options(transport, T::RecvOptions::default())
+    }
+
+    /// Fetch messages until no new Author's messages are found, ie. until caught up.
+    pub fn sync_state_with_options<T: Transport>(
+        &mut self,
+        transport: &mut T,
+        recv_opt: T::RecvOptions,
+    ) -> Fallible<Vec<(Address, Fallible<UnwrappedChannelMessage>)>>
+    where
+        T::RecvOptions: Copy,
+    {
+        self.init_sync()?;
+        let mut fetched = Vec::new();
+        loop {
+            let counter = self.sync.counter;
+            fetched.extend(self.fetch_next_msgs_with_options(transport, recv_opt)?);
+            if counter == self.sync.counter {
+                break;
+            }
+        }
+        Ok(fetched)
+    }
+
+    /// Fetch messages until no new messages are found with default receive options.
+    pub fn sync_state<T: Transport>(
+        &mut self,
+        transport: &mut T,
+    ) -> Fallible<Vec<(Address, Fallible<UnwrappedChannelMessage>)>>
+    where
+        T::RecvOptions: Copy + Default,
+    {
+        self.sync_state_with_options(transport, T::RecvOptions::default())
+    }
 }
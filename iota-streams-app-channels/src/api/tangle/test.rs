@@ -1,22 +1,113 @@
 #![allow(non_snake_case)]
 use crate::{
-    api::tangle::{
-        Address,
-        Author,
-        BucketTransport,
-        Subscriber,
-        Transport,
+    api::{
+        author::AuthorT,
+        subscriber::SubscriberT,
+        tangle::{
+            Address,
+            Author,
+            BucketTransport,
+            DefaultF,
+            DefaultP,
+            DefaultTW,
+            FileTransport,
+            KeyRotationPolicy,
+            LinkGen,
+            Message,
+            MessageMetadata,
+            MsgInfo,
+            Store,
+            SubscribePolicy,
+            Subscriber,
+            Transport,
+            UnwrappedChannelMessage,
+        },
     },
     message,
 };
 use failure::{
+    bail,
     ensure,
     Fallible,
 };
-use iota_streams_app::message::HasLink;
-use iota_streams_core::tbits::Tbits;
-use iota_streams_protobuf3::types::Trytes;
-use std::str::FromStr;
+use iota_lib_rs::prelude::iota_client;
+use iota_streams_app::{
+    message::{
+        header::Header,
+        ContentUnwrap as _,
+        ContentWrap,
+        HasLink,
+        PreparedMessage,
+        PreparsedMessage,
+    },
+    transport::{
+        self,
+        http::{
+            mock::MockServer,
+            HttpAddress,
+            HttpLinkGenerator,
+            HttpTransport,
+        },
+        tangle::{
+            client::{
+                self,
+                SendTrytesOptions,
+                MSG_FRAGMENT_SIZE,
+            },
+            mock::MockNode,
+        },
+        AsyncAdapter,
+        CachingTransport,
+        MultiRecvOptions,
+        MultiTransport,
+        RetryPolicy,
+        RetryTransport,
+        SyncAdapter,
+        Transport as _,
+        WithOptions,
+    },
+};
+use iota_streams_core::{
+    prng,
+    psk,
+    sponge::spongos::Spongos,
+    tbits::{
+        trinary::Trit,
+        TbitSliceMut,
+        Tbits,
+    },
+};
+use iota_streams_protobuf3::{
+    command::{
+        sizeof,
+        unwrap::{
+            self,
+            TraceFormat,
+        },
+        wrap,
+        Absorb,
+        Commit,
+        Join,
+        Mask,
+        Squeeze,
+    },
+    io,
+    types::{
+        LinkStore,
+        Mac,
+        Trytes,
+    },
+};
+use std::{
+    cell::RefCell,
+    fs,
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
+
+type TangleAuthor = AuthorT<DefaultTW, DefaultF, DefaultP, Address, Store, LinkGen>;
+type TangleSubscriber = SubscriberT<DefaultTW, DefaultF, DefaultP, Address, Store, LinkGen>;
 
 fn example<T: Transport>(transport: &mut T) -> Fallible<()>
 where
@@ -173,3 +264,1531 @@ fn run_basic_scenario() {
     let mut transport = BucketTransport::new();
     assert!(dbg!(example(&mut transport)).is_ok());
 }
+
+#[test]
+fn run_basic_scenario_over_async_transport() {
+    // Bucket is accessed via its async implementation.
+    let mut transport = SyncAdapter(BucketTransport::new());
+    assert!(dbg!(example(&mut transport)).is_ok());
+
+    // Blocking bucket is adapted to async and back.
+    let mut transport = SyncAdapter(AsyncAdapter(BucketTransport::new()));
+    assert!(dbg!(example(&mut transport)).is_ok());
+}
+
+#[test]
+fn run_basic_scenario_over_mock_node() {
+    // PoW is done by the node.
+    let node = MockNode::start(3).unwrap();
+    let send_opt = SendTrytesOptions {
+        local_pow: false,
+        ..SendTrytesOptions::default()
+    };
+    let mut transport = WithOptions::new(iota_client::Client::new(node.uri()), send_opt, ());
+    assert!(dbg!(example(&mut transport)).is_ok());
+
+    // Local PoW with the node's difficulty, a new node as the scenario reuses links.
+    let node = MockNode::start(3).unwrap();
+    let send_opt = SendTrytesOptions {
+        min_weight_magnitude: 3,
+        local_pow: true,
+        threads: 2,
+        ..SendTrytesOptions::default()
+    };
+    let mut transport = WithOptions::new(iota_client::Client::new(node.uri()), send_opt, ());
+    assert!(dbg!(example(&mut transport)).is_ok());
+}
+
+fn file_transport(dir: &Path) -> Fallible<()> {
+    // Author and Subscriber don't share transport, only the directory.
+    let mut author_transport = FileTransport::new(dir);
+    let mut subscriber_transport = FileTransport::new(dir);
+
+    let mut author = Author::new("AUTHOR9SEED", 2, true);
+    let mut subscriber = Subscriber::new("SUBSCRIBERA9SEED", false);
+
+    let announcement = author.announce()?;
+    ensure!(subscriber_transport.recv_messages(&announcement.link).is_err());
+    author_transport.send_message(&announcement)?;
+    {
+        let msg = subscriber_transport.recv_message(&announcement.link)?;
+        ensure!(msg.link == announcement.link);
+        subscriber.unwrap_announcement(msg.parse_header()?)?;
+        ensure!(subscriber.channel_address() == Some(author.channel_address()));
+    }
+
+    // Messages sent to the same link are all kept.
+    author_transport.send_message(&announcement)?;
+    ensure!(subscriber_transport.recv_messages(&announcement.link)?.len() == 2);
+    ensure!(subscriber_transport.recv_message(&announcement.link).is_err());
+    Ok(())
+}
+
+#[test]
+fn run_file_transport() {
+    let dir = std::env::temp_dir().join(format!("iota-streams-file-transport-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    let mut transport = FileTransport::new(dir.join("example"));
+    assert!(dbg!(example(&mut transport)).is_ok());
+    assert!(dbg!(file_transport(&dir.join("sharing"))).is_ok());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+/// Transport failing to find messages `failures` times and counting receive requests.
+struct FlakyTransport {
+    inner: BucketTransport,
+    failures: usize,
+    recv_count: usize,
+}
+
+impl transport::Transport<DefaultTW, DefaultF, Address> for FlakyTransport {
+    type SendOptions = ();
+
+    fn send_message_with_options(&mut self, msg: &Message, opt: ()) -> Fallible<()> {
+        self.inner.send_message_with_options(msg, opt)
+    }
+
+    type RecvOptions = ();
+
+    fn recv_messages_with_options(&mut self, link: &Address, opt: ()) -> Fallible<Vec<Message>> {
+        self.recv_count += 1;
+        if 0 < self.failures {
+            self.failures -= 1;
+            bail!("Message not found.");
+        }
+        self.inner.recv_messages_with_options(link, opt)
+    }
+}
+
+fn transport_middleware(policy: RetryPolicy) -> Fallible<()> {
+    let mut author = Author::new("AUTHOR9SEED", 2, true);
+    let announcement = author.announce()?;
+    let link = &announcement.link;
+
+    let flaky = FlakyTransport {
+        inner: BucketTransport::new(),
+        failures: 2,
+        recv_count: 0,
+    };
+    let mut transport = CachingTransport::new(RetryTransport::new(flaky, policy));
+    transport.send_message(&announcement)?;
+
+    // Failures are retried.
+    transport.recv_message(link)?;
+    ensure!(transport.inner().inner().recv_count == 3);
+
+    // Message is cached.
+    ensure!(transport.is_cached(link));
+    transport.recv_message(link)?;
+    ensure!(transport.inner().inner().recv_count == 3);
+
+    // Sending a message to the link drops the cached entry.
+    transport.send_message(&announcement)?;
+    ensure!(!transport.is_cached(link));
+    ensure!(transport.recv_messages(link)?.len() == 2);
+    ensure!(transport.inner().inner().recv_count == 4);
+
+    // Retries stop after `max_attempts` failures.
+    transport.clear();
+    transport.inner_mut().inner_mut().failures = policy.max_attempts;
+    ensure!(transport.recv_messages(link).is_err());
+    ensure!(transport.inner().inner().recv_count == 4 + policy.max_attempts);
+    Ok(())
+}
+
+#[test]
+fn run_transport_middleware() {
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(2),
+        deadline: Duration::from_secs(10),
+    };
+    assert!(dbg!(transport_middleware(policy)).is_ok());
+
+    let mut transport = CachingTransport::new(RetryTransport::new(BucketTransport::new(), policy));
+    assert!(dbg!(example(&mut transport)).is_ok());
+}
+
+/// Transport failing all requests.
+struct DeadTransport;
+
+impl transport::Transport<DefaultTW, DefaultF, Address> for DeadTransport {
+    type SendOptions = ();
+
+    fn send_message_with_options(&mut self, _msg: &Message, _opt: ()) -> Fallible<()> {
+        bail!("Transport is dead.")
+    }
+
+    type RecvOptions = ();
+
+    fn recv_messages_with_options(&mut self, _link: &Address, _opt: ()) -> Fallible<Vec<Message>> {
+        bail!("Transport is dead.")
+    }
+}
+
+fn multi_transport(dir: &Path) -> Fallible<()> {
+    let mut author = Author::new("AUTHOR9SEED", 2, true);
+    let announcement = author.announce()?;
+    let link = &announcement.link;
+
+    let mut transport = MultiTransport::new(2);
+    transport.add(DeadTransport);
+    transport.add(BucketTransport::new());
+    transport.add(FileTransport::new(dir));
+    ensure!(transport.recv_messages(link).is_err());
+
+    // Two of three backends accept the message.
+    transport.send_message(&announcement)?;
+    transport.set_quorum(3);
+    ensure!(transport.send_message(&announcement).is_err());
+    transport.set_quorum(4);
+    ensure!(transport.send_message(&announcement).is_err());
+
+    // Both bucket and directory contain two copies of the message, it is received once.
+    ensure!(FileTransport::new(dir).recv_messages(link)?.len() == 2);
+    ensure!(transport.recv_messages(link)?.len() == 1);
+    ensure!(transport.recv_messages_with_options(link, MultiRecvOptions::All)?.len() == 1);
+    let msg = transport.recv_message(link)?;
+    ensure!(msg.link == announcement.link && msg.body == announcement.body);
+    Ok(())
+}
+
+#[test]
+fn run_multi_transport() {
+    let dir = std::env::temp_dir().join(format!("iota-streams-multi-transport-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    assert!(dbg!(multi_transport(&dir.join("multi"))).is_ok());
+
+    let mut transport = MultiTransport::new(2);
+    transport.add(BucketTransport::new());
+    transport.add(FileTransport::new(dir.join("example")));
+    assert!(dbg!(example(&mut transport)).is_ok());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+type HttpAuthor = AuthorT<DefaultTW, DefaultF, DefaultP, HttpAddress<DefaultTW>, Store, HttpLinkGen>;
+type HttpSubscriber = SubscriberT<DefaultTW, DefaultF, DefaultP, HttpAddress<DefaultTW>, Store, HttpLinkGen>;
+type HttpLinkGen = HttpLinkGenerator<DefaultTW, DefaultF>;
+
+fn http_transport(url: &str) -> Fallible<()> {
+    let mut transport = HttpTransport::<DefaultTW, DefaultF>::new();
+    let mut author = HttpAuthor::gen(
+        Store::default(),
+        HttpLinkGen::new(url),
+        prng::dbg_init_str("AUTHOR9SEED"),
+        &Tbits::from_str("HTTPAUTHOR").unwrap(),
+        2,
+        false,
+    );
+    let mut subscriber = HttpSubscriber::gen(
+        Store::default(),
+        HttpLinkGen::default(),
+        prng::dbg_init_str("SUBSCRIBER9SEED"),
+        &Tbits::from_str("HTTPSUBSCRIBER").unwrap(),
+        false,
+    );
+
+    let public_payload = Trytes(Tbits::from_str("PUBLICPAYLOAD").unwrap());
+    let masked_payload = Trytes(Tbits::from_str("MASKEDPAYLOAD").unwrap());
+
+    let announcement = author.announce(MsgInfo::Announce)?;
+    ensure!(announcement.link.base().url == url);
+    ensure!(transport.recv_messages(&announcement.link).is_err(), "Message found before it's sent.");
+    transport.send_message(&announcement)?;
+    let (msg, metadata) = transport
+        .recv_messages_with_metadata(&announcement.link)?
+        .pop()
+        .unwrap();
+    ensure!(metadata.source == Some(announcement.link.to_string()));
+    match subscriber.handle_msg(&msg, MsgInfo::Announce)? {
+        UnwrappedChannelMessage::Announce => {}
+        _ => bail!("Expected Announce."),
+    }
+    subscriber
+        .link_gen
+        .reset_base(subscriber.appinst.as_ref().unwrap().base().clone());
+
+    let signed_packet = author.sign_packet(
+        announcement.link.rel(),
+        &public_payload,
+        &masked_payload,
+        MsgInfo::SignedPacket,
+    )?;
+    ensure!(signed_packet.link.base() == announcement.link.base());
+    transport.send_message(&signed_packet)?;
+    match subscriber.handle_msg(&transport.recv_message(&signed_packet.link)?, MsgInfo::SignedPacket)? {
+        UnwrappedChannelMessage::SignedPacket {
+            public_payload: unwrapped_public,
+            masked_payload: unwrapped_masked,
+        } => {
+            ensure!(public_payload == unwrapped_public);
+            ensure!(masked_payload == unwrapped_masked);
+        }
+        _ => bail!("Expected SignedPacket."),
+    }
+
+    // Server URL is a part of the link byte encoding, too long URLs can't be encoded.
+    let bytes = signed_packet.to_bytes()?;
+    let decoded = iota_streams_app::message::TbinaryMessage::<_, DefaultF, HttpAddress<_>>::from_bytes(&bytes)?;
+    ensure!(decoded.link == signed_packet.link && decoded.body == signed_packet.body);
+    let mut long_url = signed_packet.clone();
+    long_url.link.base.url = format!("{}/{}", url, "9".repeat(u16::MAX as usize));
+    ensure!(long_url.to_bytes().is_err(), "Too long URL encoded.");
+
+    // Server URL is not part of the message, so it can be mirrored to the Tangle unchanged.
+    let mut tangle_subscriber = Subscriber::new("SUBSCRIBER9SEED", false);
+    let tangle_announcement = Message::new(announcement.link.to_tangle_address(), announcement.body.clone());
+    match tangle_subscriber.handle_msg(&tangle_announcement)? {
+        UnwrappedChannelMessage::Announce => {}
+        _ => bail!("Expected Announce."),
+    }
+
+    // Links are persisted together with the server URL.
+    let imported = HttpAuthor::import(&author.export("AUTHOR9PASSWORD")?, "AUTHOR9PASSWORD")?;
+    ensure!(imported.appinst == author.appinst);
+    ensure!(imported.link_gen.url() == url);
+    Ok(())
+}
+
+#[test]
+fn run_http_transport() {
+    let server = MockServer::start().unwrap();
+    assert!(dbg!(http_transport(server.url())).is_ok());
+}
+
+fn export_import<T: Transport>(transport: &mut T) -> Fallible<()>
+where
+    T::SendOptions: Default,
+    T::RecvOptions: Default,
+{
+    let mut author = Author::new("AUTHOR9SEED", 2, true);
+    let mut subscriber = Subscriber::new("SUBSCRIBER9SEED", true);
+
+    let public_payload = Trytes(Tbits::from_str("PUBLICPAYLOAD").unwrap());
+    let masked_payload = Trytes(Tbits::from_str("MASKEDPAYLOAD").unwrap());
+
+    let announcement_link = {
+        let msg = author.announce()?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    subscriber.unwrap_announcement(transport.recv_message(&announcement_link)?.parse_header()?)?;
+
+    let subscribe_link = {
+        let msg = subscriber.subscribe(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    author.unwrap_subscribe(transport.recv_message(&subscribe_link)?.parse_header()?)?;
+
+    let keyload_link = {
+        let msg = author.share_keyload_for_everyone(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    subscriber.unwrap_keyload(transport.recv_message(&keyload_link)?.parse_header()?)?;
+
+    let state = author.export("AUTHOR9PASSWORD")?;
+    ensure!(Author::import(&state, "WRONG9PASSWORD").is_err());
+    let mut imported = Author::import(&state, "AUTHOR9PASSWORD")?;
+    ensure!(author.channel_address() == imported.channel_address());
+
+    // Imported Author continues generating the same links and signing with the same MSS key.
+    let signed_packet_link = {
+        let msg = imported.sign_packet(&announcement_link, &public_payload, &masked_payload)?;
+        let expected = author.sign_packet(&announcement_link, &public_payload, &masked_payload)?;
+        ensure!(msg.link == expected.link);
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    subscriber.unwrap_signed_packet(transport.recv_message(&signed_packet_link)?.parse_header()?)?;
+
+    // Link store is restored, so imported Author can link to keyload.
+    let tagged_packet_link = {
+        let msg = imported.tag_packet(&keyload_link, &public_payload, &masked_payload)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    let (unwrapped_public, unwrapped_masked) =
+        subscriber.unwrap_tagged_packet(transport.recv_message(&tagged_packet_link)?.parse_header()?)?;
+    ensure!(public_payload == unwrapped_public);
+    ensure!(masked_payload == unwrapped_masked);
+
+    Ok(())
+}
+
+#[test]
+fn run_export_import() {
+    let mut transport = BucketTransport::new();
+    assert!(dbg!(export_import(&mut transport)).is_ok());
+}
+
+fn subscriber_export_import<T: Transport>(transport: &mut T) -> Fallible<()>
+where
+    T::SendOptions: Default,
+    T::RecvOptions: Default,
+{
+    let mut author = Author::new("AUTHOR9SEED", 2, true);
+    let mut subscriber = Subscriber::new("SUBSCRIBER9SEED", true);
+
+    let public_payload = Trytes(Tbits::from_str("PUBLICPAYLOAD").unwrap());
+    let masked_payload = Trytes(Tbits::from_str("MASKEDPAYLOAD").unwrap());
+
+    let announcement_link = {
+        let msg = author.announce()?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    subscriber.unwrap_announcement(transport.recv_message(&announcement_link)?.parse_header()?)?;
+
+    let subscribe_link = {
+        let msg = subscriber.subscribe(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    author.unwrap_subscribe(transport.recv_message(&subscribe_link)?.parse_header()?)?;
+
+    let keyload_link = {
+        let msg = author.share_keyload_for_everyone(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    subscriber.unwrap_keyload(transport.recv_message(&keyload_link)?.parse_header()?)?;
+
+    let state = subscriber.export("SUBSCRIBER9PASSWORD")?;
+    ensure!(Subscriber::import(&state, "WRONG9PASSWORD").is_err());
+    let mut imported = Subscriber::import(&state, "SUBSCRIBER9PASSWORD")?;
+    ensure!(imported.is_registered());
+    ensure!(subscriber.channel_address() == imported.channel_address());
+
+    // Author's MSS public key is restored.
+    let signed_packet_link = {
+        let msg = author.sign_packet(&announcement_link, &public_payload, &masked_payload)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    let (unwrapped_public, unwrapped_masked) =
+        imported.unwrap_signed_packet(transport.recv_message(&signed_packet_link)?.parse_header()?)?;
+    ensure!(public_payload == unwrapped_public);
+    ensure!(masked_payload == unwrapped_masked);
+
+    // Keyload spongos state is restored, keyload is not processed again.
+    let tagged_packet_link = {
+        let msg = author.tag_packet(&keyload_link, &public_payload, &masked_payload)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    let (unwrapped_public, unwrapped_masked) =
+        imported.unwrap_tagged_packet(transport.recv_message(&tagged_packet_link)?.parse_header()?)?;
+    ensure!(public_payload == unwrapped_public);
+    ensure!(masked_payload == unwrapped_masked);
+
+    // Own NTRU private key is restored.
+    let keyload_link = {
+        let msg = author.share_keyload_for_everyone(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    imported.unwrap_keyload(transport.recv_message(&keyload_link)?.parse_header()?)?;
+
+    Ok(())
+}
+
+#[test]
+fn run_subscriber_export_import() {
+    let mut transport = BucketTransport::new();
+    assert!(dbg!(subscriber_export_import(&mut transport)).is_ok());
+}
+
+fn handle_msg<T: Transport>(transport: &mut T) -> Fallible<()>
+where
+    T::SendOptions: Default,
+    T::RecvOptions: Default,
+{
+    let mut author = Author::new("AUTHOR9SEED", 2, true);
+    let mut subscriber = Subscriber::new("SUBSCRIBER9SEED", true);
+
+    let public_payload = Trytes(Tbits::from_str("PUBLICPAYLOAD").unwrap());
+    let masked_payload = Trytes(Tbits::from_str("MASKEDPAYLOAD").unwrap());
+
+    let announcement_link = {
+        let msg = author.announce()?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    {
+        let msg = transport.recv_message(&announcement_link)?;
+        ensure!(author.handle_msg(&msg).is_err());
+        match subscriber.handle_msg(&msg)? {
+            UnwrappedChannelMessage::Announce => {}
+            _ => failure::bail!("Expected Announce."),
+        }
+        ensure!(subscriber.is_registered());
+    }
+
+    let subscribe_link = {
+        let msg = subscriber.subscribe(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    {
+        let msg = transport.recv_message(&subscribe_link)?;
+        ensure!(subscriber.handle_msg(&msg).is_err());
+        match author.handle_msg(&msg)? {
+            UnwrappedChannelMessage::Subscribe => {}
+            _ => failure::bail!("Expected Subscribe."),
+        }
+    }
+
+    let keyload_link = {
+        let msg = author.share_keyload_for_everyone(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    match subscriber.handle_msg(&transport.recv_message(&keyload_link)?)? {
+        UnwrappedChannelMessage::Keyload => {}
+        _ => failure::bail!("Expected Keyload."),
+    }
+
+    let signed_packet_link = {
+        let msg = author.sign_packet(&announcement_link, &public_payload, &masked_payload)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    match subscriber.handle_msg(&transport.recv_message(&signed_packet_link)?)? {
+        UnwrappedChannelMessage::SignedPacket {
+            public_payload: unwrapped_public,
+            masked_payload: unwrapped_masked,
+        } => {
+            ensure!(public_payload == unwrapped_public);
+            ensure!(masked_payload == unwrapped_masked);
+        }
+        _ => failure::bail!("Expected SignedPacket."),
+    }
+
+    let tagged_packet_link = {
+        let msg = subscriber.tag_packet(&keyload_link, &public_payload, &masked_payload)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    match author.handle_msg(&transport.recv_message(&tagged_packet_link)?)? {
+        UnwrappedChannelMessage::TaggedPacket {
+            public_payload: unwrapped_public,
+            masked_payload: unwrapped_masked,
+        } => {
+            ensure!(public_payload == unwrapped_public);
+            ensure!(masked_payload == unwrapped_masked);
+        }
+        _ => failure::bail!("Expected TaggedPacket."),
+    }
+
+    let change_key_link = {
+        let msg = author.change_key(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    match subscriber.handle_msg(&transport.recv_message(&change_key_link)?)? {
+        UnwrappedChannelMessage::ChangeKey => {}
+        _ => failure::bail!("Expected ChangeKey."),
+    }
+
+    let unsubscribe_link = {
+        let msg = subscriber.unsubscribe(&subscribe_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    match author.handle_msg(&transport.recv_message(&unsubscribe_link)?)? {
+        UnwrappedChannelMessage::Unsubscribe => {}
+        _ => failure::bail!("Expected Unsubscribe."),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn run_handle_msg() {
+    let mut transport = BucketTransport::new();
+    assert!(dbg!(handle_msg(&mut transport)).is_ok());
+}
+
+fn spam_at_link<T: Transport>(transport: &mut T) -> Fallible<()>
+where
+    T::SendOptions: Default,
+    T::RecvOptions: Default,
+{
+    let mut author = Author::new("AUTHOR9SEED", 2, true);
+    let mut subscriber = Subscriber::new("SUBSCRIBER9SEED", true);
+    let mut mallory = Author::new("MALLORY9SEED", 2, true);
+
+    let public_payload = Trytes(Tbits::from_str("PUBLICPAYLOAD").unwrap());
+    let masked_payload = Trytes(Tbits::from_str("MASKEDPAYLOAD").unwrap());
+
+    let announcement_link = {
+        let msg = author.announce()?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    match subscriber.recv_msg(transport, &announcement_link)? {
+        UnwrappedChannelMessage::Announce => {}
+        _ => bail!("Expected Announce."),
+    }
+
+    let signed_packet = author.sign_packet(&announcement_link, &public_payload, &masked_payload)?;
+    let link = signed_packet.link.clone();
+
+    // Junk and a message of another channel are published at the same link before the genuine one.
+    let junk = Message::new(link.clone(), Tbits::from_str("JUNK9JUNK9JUNK9JUNK").unwrap());
+    let forged = {
+        let mallory_announcement_link = mallory.announce()?.link;
+        let msg = mallory.sign_packet(&mallory_announcement_link, &public_payload, &masked_payload)?;
+        Message::new(link.clone(), msg.body)
+    };
+    transport.send_message(&junk)?;
+    transport.send_message(&forged)?;
+    transport.send_message(&signed_packet)?;
+
+    ensure!(transport.recv_message(&link).is_err(), "Expected several messages at the link.");
+    let msgs = transport.recv_messages(&link)?;
+    match subscriber.handle_first_valid_msg(&msgs)? {
+        (2, UnwrappedChannelMessage::SignedPacket {
+            public_payload: unwrapped_public,
+            masked_payload: unwrapped_masked,
+        }) => {
+            ensure!(public_payload == unwrapped_public);
+            ensure!(masked_payload == unwrapped_masked);
+        }
+        _ => bail!("Expected the third message to be a SignedPacket."),
+    }
+
+    ensure!(subscriber.handle_first_valid_msg(&msgs[..2]).is_err());
+    ensure!(subscriber.handle_first_valid_msg(&[]).is_err());
+    Ok(())
+}
+
+#[test]
+fn run_spam_at_link() {
+    let mut transport = BucketTransport::new();
+    assert!(dbg!(spam_at_link(&mut transport)).is_ok());
+}
+
+fn message_metadata<T: Transport>(transport: &mut T) -> Fallible<MessageMetadata>
+where
+    T::SendOptions: Default,
+    T::RecvOptions: Default,
+{
+    let mut author = Author::new("AUTHOR9SEED", 2, false);
+    let mut subscriber = Subscriber::new("SUBSCRIBER9SEED", false);
+
+    let announcement = author.announce()?;
+    transport.send_message(&announcement)?;
+    ensure!(author.recv_msg_with_metadata(transport, &announcement.link).is_err());
+    match subscriber.recv_msg_with_metadata(transport, &announcement.link)? {
+        (UnwrappedChannelMessage::Announce, metadata) => Ok(metadata),
+        _ => bail!("Expected Announce."),
+    }
+}
+
+#[test]
+fn run_message_metadata() {
+    // Bucket keeps no metadata.
+    let metadata = message_metadata(&mut BucketTransport::new());
+    assert!(dbg!(&metadata).as_ref().map_or(false, |m| *m == MessageMetadata::default()));
+
+    let dir = std::env::temp_dir().join(format!("iota-streams-message-metadata-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let metadata = message_metadata(&mut FileTransport::new(&dir));
+    assert!(dbg!(&metadata).as_ref().map_or(false, |m| {
+        m.timestamp.is_some() && m.source.as_ref().map_or(false, |path| Path::new(path).starts_with(&dir))
+    }));
+    let _ = fs::remove_dir_all(&dir);
+
+    let node = MockNode::start(3).unwrap();
+    let send_opt = SendTrytesOptions {
+        min_weight_magnitude: 3,
+        threads: 1,
+        ..SendTrytesOptions::default()
+    };
+    let mut transport = WithOptions::new(iota_client::Client::new(node.uri()), send_opt, ());
+    let metadata = message_metadata(&mut transport);
+    assert!(dbg!(&metadata).as_ref().map_or(false, |m| {
+        m.timestamp.is_some()
+            && m.attachment_timestamp.is_some()
+            && m.bundle_hash.is_some()
+            && !m.tx_hashes.is_empty()
+            && m.source.as_deref() == Some(node.uri())
+    }));
+}
+
+fn binary_encoding() -> Fallible<()> {
+    let mut author = Author::new("AUTHOR9SEED", 2, false);
+    let mut subscriber = Subscriber::new("SUBSCRIBER9SEED", false);
+
+    let public_payload = Trytes(Tbits::from_str("PUBLICPAYLOAD").unwrap());
+    let masked_payload = Trytes(Tbits::from_str("MASKEDPAYLOAD").unwrap());
+
+    let announcement = author.announce()?;
+    let signed_packet = author.sign_packet(&announcement.link, &public_payload, &masked_payload)?;
+    for msg in &[&announcement, &signed_packet] {
+        let bytes = msg.to_bytes()?;
+        ensure!(bytes.len() < msg.body.to_string().len(), "Encoding is longer than tryte string.");
+        let decoded = Message::from_bytes(&bytes)?;
+        ensure!(decoded.link == msg.link && decoded.body == msg.body);
+
+        ensure!(Message::from_bytes(&bytes[..bytes.len() - 1]).is_err(), "Truncated message decoded.");
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        ensure!(Message::from_bytes(&trailing).is_err(), "Message with trailing bytes decoded.");
+        let mut invalid = bytes.clone();
+        invalid[0] = 255;
+        ensure!(Message::from_bytes(&invalid).is_err(), "Invalid B1T5 word decoded.");
+    }
+
+    match subscriber.handle_msg(&Message::from_bytes(&announcement.to_bytes()?)?)? {
+        UnwrappedChannelMessage::Announce => {}
+        _ => bail!("Expected Announce."),
+    }
+    match subscriber.handle_msg(&Message::from_bytes(&signed_packet.to_bytes()?)?)? {
+        UnwrappedChannelMessage::SignedPacket {
+            public_payload: unwrapped_public,
+            masked_payload: unwrapped_masked,
+        } => {
+            ensure!(public_payload == unwrapped_public);
+            ensure!(masked_payload == unwrapped_masked);
+        }
+        _ => bail!("Expected SignedPacket."),
+    }
+    Ok(())
+}
+
+#[test]
+fn run_binary_encoding() {
+    assert!(dbg!(binary_encoding()).is_ok());
+}
+
+/// Wrap `content` and check it against `expected` hand-written wrap of the same fields.
+fn check_content_wrap<C>(
+    store: &Store,
+    content: &C,
+    expected: impl Fn(&mut wrap::Context<DefaultTW, DefaultF, TbitSliceMut<DefaultTW>>) -> Fallible<()>,
+) -> Fallible<Tbits<DefaultTW>>
+where
+    C: ContentWrap<DefaultTW, DefaultF, Store>,
+{
+    let size = content.sizeof(&mut sizeof::Context::new())?.get_size();
+    let mut buf = Tbits::zero(size);
+    content.wrap(store, &mut wrap::Context::new(buf.slice_mut()))?;
+
+    let mut expected_buf = Tbits::zero(size);
+    {
+        let mut ctx = wrap::Context::new(expected_buf.slice_mut());
+        expected(&mut ctx)?;
+        ensure!(ctx.stream.is_empty(), "Content size differs from hand-written wrap.");
+    }
+    ensure!(buf == expected_buf, "Content differs from hand-written wrap.");
+    Ok(buf)
+}
+
+fn content_encoding() -> Fallible<()> {
+    let mut author = Author::new("AUTHOR9SEED", 2, false);
+    let msgid = author.announce()?.link.msgid;
+    let mut spongos = Spongos::<DefaultTW, DefaultF>::init();
+    spongos.absorb(msgid.as_ref().slice());
+    spongos.commit();
+    let mut store = Store::default();
+    store.update(&msgid, spongos, MsgInfo::Announce)?;
+    let mac = Mac(Spongos::<DefaultTW, DefaultF>::MAC_SIZE);
+
+    let public_payload = Trytes(Tbits::from_str("PUBLICPAYLOAD").unwrap());
+    let masked_payload = Trytes(Tbits::from_str("MASKEDPAYLOAD").unwrap());
+    let content = message::tagged_packet::ContentWrap::<DefaultTW, DefaultF, Address> {
+        msgid: &msgid,
+        public_payload: &public_payload,
+        masked_payload: &masked_payload,
+        _phantom: std::marker::PhantomData,
+    };
+    let buf = check_content_wrap(&store, &content, |ctx| {
+        ctx.join(&store, &msgid)?
+            .absorb(&public_payload)?
+            .mask(&masked_payload)?
+            .commit()?
+            .squeeze(&mac)?;
+        Ok(())
+    })?;
+    let mut unwrapped = message::tagged_packet::ContentUnwrap::<DefaultTW, DefaultF, Address>::new();
+    unwrapped.unwrap(&store, &mut unwrap::Context::new(buf.slice()))?;
+    ensure!(unwrapped.msgid == msgid);
+    ensure!(unwrapped.public_payload == public_payload && unwrapped.masked_payload == masked_payload);
+
+    let content = message::unsubscribe::ContentWrap::<DefaultTW, DefaultF, Address> {
+        link: &msgid,
+        _phantom: std::marker::PhantomData,
+    };
+    let buf = check_content_wrap(&store, &content, |ctx| {
+        ctx.join(&store, &msgid)?.commit()?.squeeze(&mac)?;
+        Ok(())
+    })?;
+    let mut unwrapped = message::unsubscribe::ContentUnwrap::<DefaultTW, DefaultF, Address>::new();
+    unwrapped.unwrap(&store, &mut unwrap::Context::new(buf.slice()))?;
+    ensure!(unwrapped.link == msgid);
+    Ok(())
+}
+
+#[test]
+fn run_content_encoding() {
+    assert!(dbg!(content_encoding()).is_ok());
+}
+
+fn chunked_message() -> Fallible<()> {
+    let mut author = Author::new("AUTHOR9SEED", 2, false);
+    let announcement_link = author.announce()?.link;
+    let msgid = announcement_link.msgid.clone();
+    let mut spongos = Spongos::<DefaultTW, DefaultF>::init();
+    spongos.absorb(msgid.as_ref().slice());
+    spongos.commit();
+    let mut store = Store::default();
+    store.update(&msgid, spongos, MsgInfo::Announce)?;
+    let store = RefCell::new(store);
+
+    // Payload spans several transactions.
+    let public_payload = Trytes(Tbits::cycle_str(2 * MSG_FRAGMENT_SIZE, "PUBLIC"));
+    let masked_payload = Trytes(Tbits::cycle_str(MSG_FRAGMENT_SIZE, "MASKED"));
+    let link = Address::from_str(&announcement_link.appinst.to_string(), &"CHUNKED99".repeat(3)).unwrap();
+    let prepared = PreparedMessage::new(
+        store.borrow(),
+        Header::new_with_type(link.clone(), message::tagged_packet::TYPE),
+        message::tagged_packet::ContentWrap::<DefaultTW, DefaultF, Address> {
+            msgid: &msgid,
+            public_payload: &public_payload,
+            masked_payload: &masked_payload,
+            _phantom: std::marker::PhantomData,
+        },
+    );
+
+    let body = prepared.wrap()?.message.body;
+    let wrapped = prepared.wrap_stream(io::ChunkedOStream::new(MSG_FRAGMENT_SIZE))?;
+    drop(prepared);
+    let fragments = wrapped.commit(store.borrow_mut(), MsgInfo::TaggedPacket)?.into_chunks();
+    ensure!(fragments.len() == (body.size() + MSG_FRAGMENT_SIZE - 1) / MSG_FRAGMENT_SIZE);
+    ensure!(fragments.iter().all(|fragment| fragment.size() <= MSG_FRAGMENT_SIZE));
+    let mut chunked_body = Tbits::zero(0);
+    for fragment in &fragments {
+        chunked_body += fragment;
+    }
+    ensure!(chunked_body == body, "Chunked wrap differs from wrap.");
+    ensure!(store.borrow().lookup(link.rel()).is_ok(), "Chunked message link is not committed.");
+
+    let bundle = client::fragments_to_bundle(&link, &fragments, 0)?;
+    ensure!(bundle.len() == fragments.len());
+    let (bundle_link, bundle_fragments) = client::fragments_from_bundle(&bundle);
+    ensure!(bundle_link == link);
+
+    let preparsed = PreparsedMessage::<DefaultTW, DefaultF, Address, _>::parse_header(
+        bundle_link,
+        io::ChunkedIStream::new(&bundle_fragments),
+    )?;
+    ensure!(preparsed.check_content_type(message::tagged_packet::TYPE));
+    let unwrapped = preparsed
+        .unwrap(
+            &*store.borrow(),
+            message::tagged_packet::ContentUnwrap::<DefaultTW, DefaultF, Address>::new(),
+        )?
+        .content;
+    ensure!(unwrapped.msgid == msgid);
+    ensure!(unwrapped.public_payload == public_payload && unwrapped.masked_payload == masked_payload);
+
+    // Bad fragment fails MAC check.
+    let mut bad_fragments = bundle_fragments.clone();
+    bad_fragments[1] = Tbits::cycle_str(MSG_FRAGMENT_SIZE, "BAD");
+    let preparsed = PreparsedMessage::<DefaultTW, DefaultF, Address, _>::parse_header(
+        link,
+        io::ChunkedIStream::new(&bad_fragments),
+    )?;
+    ensure!(preparsed
+        .unwrap(
+            &*store.borrow(),
+            message::tagged_packet::ContentUnwrap::<DefaultTW, DefaultF, Address>::new(),
+        )
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn run_chunked_message() {
+    assert!(dbg!(chunked_message()).is_ok());
+}
+
+fn dump_message() -> Fallible<()> {
+    let mut author = Author::new("AUTHOR9SEED", 2, true);
+    let announcement = author.announce()?;
+
+    let mut text = String::new();
+    let mut content = message::announce::ContentUnwrap::<DefaultTW, DefaultF, DefaultP>::default();
+    announcement.dump(&(), &mut content, TraceFormat::Text, &mut text)?;
+    ensure!(
+        text.lines().any(|line| line.contains(" commit ")) && text.lines().last().unwrap().contains(" mssig "),
+        "Bad announce trace:\n{}",
+        text
+    );
+
+    let body = &announcement.body;
+    let truncated = Message::new(announcement.link.clone(), Tbits::from_slice(body.slice().take(body.size() - 3)));
+    let mut json = String::new();
+    let mut content = message::announce::ContentUnwrap::<DefaultTW, DefaultF, DefaultP>::default();
+    ensure!(
+        truncated.dump(&(), &mut content, TraceFormat::Json, &mut json).is_err(),
+        "Truncated message unwrapped."
+    );
+    ensure!(
+        json.starts_with("{\"entries\":[{") && json.contains("],\"error\":\"Input slice too short.\"}"),
+        "Bad truncated announce trace: {}",
+        json
+    );
+    Ok(())
+}
+
+#[test]
+fn run_dump_message() {
+    assert!(dbg!(dump_message()).is_ok());
+}
+
+fn sync_state<T: Transport>(transport: &mut T) -> Fallible<()>
+where
+    T::SendOptions: Default,
+    T::RecvOptions: Copy + Default,
+{
+    let mut author = Author::new("AUTHOR9SEED", 2, true);
+    let mut subscriber = Subscriber::new("SUBSCRIBER9SEED", true);
+
+    let public_payload = Trytes(Tbits::from_str("PUBLICPAYLOAD").unwrap());
+    let masked_payload = Trytes(Tbits::from_str("MASKEDPAYLOAD").unwrap());
+
+    let announcement_link = {
+        let msg = author.announce()?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    subscriber.unwrap_announcement(transport.recv_message(&announcement_link)?.parse_header()?)?;
+    ensure!(subscriber.sync_state(transport)?.is_empty());
+
+    let subscribe_link = {
+        let msg = subscriber.subscribe(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    author.unwrap_subscribe(transport.recv_message(&subscribe_link)?.parse_header()?)?;
+
+    // Messages are sent in reverse order, but handled in the order they were created.
+    let keyload = author.share_keyload_for_everyone(&announcement_link)?;
+    let tagged_packet = author.tag_packet(&keyload.link, &public_payload, &masked_payload)?;
+    // Links of messages attached to Subscriber's messages are not predicted.
+    let unpredicted = author.tag_packet(&subscribe_link, &public_payload, &masked_payload)?;
+    let signed_packet = author.sign_packet(&announcement_link, &public_payload, &masked_payload)?;
+    for msg in &[&signed_packet, &unpredicted, &tagged_packet, &keyload] {
+        transport.send_message(msg)?;
+    }
+
+    let msgs = subscriber.sync_state(transport)?;
+    ensure!(msgs.len() == 3);
+    ensure!(msgs.iter().all(|(_, unwrapped)| unwrapped.is_ok()));
+    ensure!(msgs[0].0 == keyload.link);
+    ensure!(msgs[1].0 == tagged_packet.link);
+    ensure!(msgs[2].0 == signed_packet.link);
+    match &msgs[1].1 {
+        Ok(UnwrappedChannelMessage::TaggedPacket {
+            public_payload: unwrapped_public,
+            masked_payload: unwrapped_masked,
+        }) => {
+            ensure!(public_payload == *unwrapped_public);
+            ensure!(masked_payload == *unwrapped_masked);
+        }
+        _ => failure::bail!("Expected TaggedPacket."),
+    }
+    ensure!(subscriber.fetch_next_msgs(transport)?.is_empty());
+
+    // Restored Subscriber skips already processed messages.
+    let state = subscriber.export("SUBSCRIBER9PASSWORD")?;
+    let mut imported = Subscriber::import(&state, "SUBSCRIBER9PASSWORD")?;
+    let signed_packet = author.sign_packet(&keyload.link, &public_payload, &masked_payload)?;
+    transport.send_message(&signed_packet)?;
+    let msgs = imported.sync_state(transport)?;
+    ensure!(msgs.len() == 1);
+    ensure!(msgs[0].0 == signed_packet.link);
+
+    Ok(())
+}
+
+#[test]
+fn run_sync_state() {
+    let mut transport = BucketTransport::new();
+    assert!(dbg!(sync_state(&mut transport)).is_ok());
+}
+
+#[test]
+fn run_sync_state_over_retrying_mock_node() {
+    // Unpublished links are probed until retries run out, sync must stop with `LinkNotFound`.
+    let node = MockNode::start(3).unwrap();
+    let policy = RetryPolicy {
+        max_attempts: 2,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(2),
+        deadline: Duration::from_secs(10),
+    };
+    let send_opt = SendTrytesOptions {
+        local_pow: false,
+        ..SendTrytesOptions::default()
+    };
+    let client = WithOptions::new(iota_client::Client::new(node.uri()), send_opt, ());
+    let mut transport = RetryTransport::new(client, policy);
+    assert!(dbg!(sync_state(&mut transport)).is_ok());
+}
+
+fn sync_spam<T: Transport>(transport: &mut T) -> Fallible<()>
+where
+    T::SendOptions: Default,
+    T::RecvOptions: Copy + Default,
+{
+    let mut author = Author::new("AUTHOR9SEED", 2, true);
+    let mut subscriber = Subscriber::new("SUBSCRIBER9SEED", true);
+
+    let public_payload = Trytes(Tbits::from_str("PUBLICPAYLOAD").unwrap());
+    let masked_payload = Trytes(Tbits::from_str("MASKEDPAYLOAD").unwrap());
+
+    let announcement = author.announce()?;
+    transport.send_message(&announcement)?;
+    subscriber.unwrap_announcement(announcement.parse_header()?)?;
+
+    // Spam at the predicted link is reported, but doesn't advance the counter.
+    let signed_packet = author.sign_packet(&announcement.link, &public_payload, &masked_payload)?;
+    let spam = Message::new(signed_packet.link.clone(), Tbits::from_str("SPAM").unwrap());
+    transport.send_message(&spam)?;
+    let msgs = subscriber.sync_state(transport)?;
+    ensure!(msgs.len() == 1 && msgs[0].0 == spam.link && msgs[0].1.is_err());
+
+    // Genuine message published at the spammed link is still found.
+    transport.send_message(&signed_packet)?;
+    let msgs = subscriber.sync_state(transport)?;
+    ensure!(msgs.iter().any(|(link, unwrapped)| *link == signed_packet.link && unwrapped.is_ok()));
+    Ok(())
+}
+
+#[test]
+fn run_sync_spam() {
+    let mut transport = BucketTransport::new();
+    assert!(dbg!(sync_spam(&mut transport)).is_ok());
+}
+
+fn subscriber_registry<T: Transport>(transport: &mut T) -> Fallible<()>
+where
+    T::SendOptions: Default,
+    T::RecvOptions: Default,
+{
+    let mut author = Author::new("AUTHOR9SEED", 2, true);
+    author.set_subscribe_policy(SubscribePolicy::Manual);
+    let mut subscriberA = Subscriber::new("SUBSCRIBERA9SEED", true);
+    let mut subscriberB = Subscriber::new("SUBSCRIBERB9SEED", true);
+
+    let announcement_link = {
+        let msg = author.announce()?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    let preparsed = transport.recv_message(&announcement_link)?;
+    subscriberA.unwrap_announcement(preparsed.parse_header()?)?;
+    subscriberB.unwrap_announcement(preparsed.parse_header()?)?;
+
+    let subscribeA_link = {
+        let msg = subscriberA.subscribe(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    author.unwrap_subscribe(transport.recv_message(&subscribeA_link)?.parse_header()?)?;
+    ensure!(author.subscribers() == vec![subscribeA_link.clone()]);
+    ensure!(!author.is_subscriber_approved(&subscribeA_link));
+
+    // Pending subscriber is not a recipient of keyload.
+    let keyload_link = {
+        let msg = author.share_keyload_for_everyone(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    ensure!(subscriberA
+        .unwrap_keyload(transport.recv_message(&keyload_link)?.parse_header()?)
+        .is_err());
+
+    author.approve_subscriber(&subscribeA_link)?;
+    ensure!(author.is_subscriber_approved(&subscribeA_link));
+    let keyload_link = {
+        let msg = author.share_keyload_for_everyone(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    subscriberA.unwrap_keyload(transport.recv_message(&keyload_link)?.parse_header()?)?;
+
+    // Registry is persisted.
+    let state = author.export("AUTHOR9PASSWORD")?;
+    let mut author = Author::import(&state, "AUTHOR9PASSWORD")?;
+    ensure!(author.is_subscriber_approved(&subscribeA_link));
+
+    // Callback rejects subscriberB.
+    author.set_subscribe_policy(SubscribePolicy::Callback(Box::new(|_| false)));
+    let subscribeB_link = {
+        let msg = subscriberB.subscribe(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    author.unwrap_subscribe(transport.recv_message(&subscribeB_link)?.parse_header()?)?;
+    ensure!(!author.is_subscriber_approved(&subscribeB_link));
+    author.remove_subscriber(&subscribeB_link)?;
+    ensure!(author.remove_subscriber(&subscribeB_link).is_err());
+
+    // Unsubscribed subscriber is not a recipient of keyload anymore.
+    let unsubscribe_link = {
+        let msg = subscriberA.unsubscribe(&subscribeA_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    author.unwrap_unsubscribe(transport.recv_message(&unsubscribe_link)?.parse_header()?)?;
+    ensure!(author.subscribers().is_empty());
+    let keyload_link = {
+        let msg = author.share_keyload_for_everyone(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    ensure!(subscriberA
+        .unwrap_keyload(transport.recv_message(&keyload_link)?.parse_header()?)
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn run_subscriber_registry() {
+    let mut transport = BucketTransport::new();
+    assert!(dbg!(subscriber_registry(&mut transport)).is_ok());
+}
+
+fn signed_keyload<T: Transport>(transport: &mut T) -> Fallible<()>
+where
+    T::SendOptions: Default,
+    T::RecvOptions: Default,
+{
+    let mut author = Author::new("AUTHOR9SEED", 2, true);
+    let mut subscriber = Subscriber::new("SUBSCRIBERA9SEED", true);
+
+    let announcement_link = {
+        let msg = author.announce()?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    subscriber.unwrap_announcement(transport.recv_message(&announcement_link)?.parse_header()?)?;
+
+    let subscribe_link = {
+        let msg = subscriber.subscribe(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    author.unwrap_subscribe(transport.recv_message(&subscribe_link)?.parse_header()?)?;
+
+    // Unsigned keyload is accepted unless signed keyloads are required.
+    let keyload_link = {
+        let msg = author.share_keyload_for_everyone(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    subscriber.set_require_signed_keyloads(true);
+    ensure!(subscriber
+        .unwrap_keyload(transport.recv_message(&keyload_link)?.parse_header()?)
+        .is_err());
+    subscriber.set_require_signed_keyloads(false);
+    subscriber.unwrap_keyload(transport.recv_message(&keyload_link)?.parse_header()?)?;
+
+    // Flags are persisted.
+    author.set_sign_keyloads(true);
+    subscriber.set_require_signed_keyloads(true);
+    let mut author = Author::import(&author.export("AUTHOR9PASSWORD")?, "AUTHOR9PASSWORD")?;
+    let mut subscriber = Subscriber::import(&subscriber.export("SUBSCRIBER9PASSWORD")?, "SUBSCRIBER9PASSWORD")?;
+
+    let keyload_link = {
+        let msg = author.share_keyload_for_everyone(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    subscriber.unwrap_keyload(transport.recv_message(&keyload_link)?.parse_header()?)?;
+
+    // Keyload with a forged signature is rejected.
+    let keyload_link = {
+        let mut msg = author.share_keyload_for_everyone(&announcement_link)?;
+        let n = msg.body.size();
+        let t = msg.body.slice().drop(n - 1).get_trit();
+        msg.body.slice_mut().drop(n - 1).put_trit(Trit((t.0 + 1) % 3));
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    ensure!(subscriber
+        .unwrap_keyload(transport.recv_message(&keyload_link)?.parse_header()?)
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn run_signed_keyload() {
+    let mut transport = BucketTransport::new();
+    assert!(dbg!(signed_keyload(&mut transport)).is_ok());
+}
+
+/// Check whether `x` occurs in `body` at any trit offset.
+fn contains_tbits(body: &Tbits<DefaultTW>, x: &Tbits<DefaultTW>) -> bool {
+    x.size() <= body.size() && (0..=body.size() - x.size()).any(|i| body.slice().drop(i).take(x.size()) == x.slice())
+}
+
+fn private_keyload<T: Transport>(transport: &mut T) -> Fallible<()>
+where
+    T::SendOptions: Default,
+    T::RecvOptions: Default,
+{
+    let mut author = Author::new("AUTHOR9SEED", 2, true);
+    let mut subscriberA = Subscriber::new("SUBSCRIBERA9SEED", true);
+    let mut subscriberB = Subscriber::new("SUBSCRIBERB9SEED", true);
+
+    let announcement_link = {
+        let msg = author.announce()?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    let preparsed = transport.recv_message(&announcement_link)?;
+    subscriberA.unwrap_announcement(preparsed.parse_header()?)?;
+    subscriberB.unwrap_announcement(preparsed.parse_header()?)?;
+
+    let subscribe_link = {
+        let msg = subscriberA.subscribe(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    author.unwrap_subscribe(transport.recv_message(&subscribe_link)?.parse_header()?)?;
+
+    // Only subscriberA is a recipient.
+    author.set_sign_keyloads(true);
+    subscriberA.set_require_signed_keyloads(true);
+    let keyload_link = {
+        let msg = author.share_keyload_private_for_everyone(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    let msg = transport.recv_message(&keyload_link)?;
+    match subscriberA.handle_msg(&msg)? {
+        UnwrappedChannelMessage::Keyload => {}
+        _ => failure::bail!("Expected Keyload."),
+    }
+    ensure!(subscriberB.handle_msg(&msg).is_err());
+
+    let public_payload = Trytes(Tbits::from_str("PUBLICPAYLOAD").unwrap());
+    let masked_payload = Trytes(Tbits::from_str("MASKEDPAYLOAD").unwrap());
+    let tagged_packet_link = {
+        let msg = author.tag_packet(&keyload_link, &public_payload, &masked_payload)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    let (unwrapped_public, unwrapped_masked) =
+        subscriberA.unwrap_tagged_packet(transport.recv_message(&tagged_packet_link)?.parse_header()?)?;
+    ensure!(public_payload == unwrapped_public);
+    ensure!(masked_payload == unwrapped_masked);
+
+    // PSK and NTRU recipients, neither pskid nor NTRU pkid is revealed.
+    let mut author = TangleAuthor::gen(
+        Store::default(),
+        LinkGen::default(),
+        prng::dbg_init_str("AUTHOR9SEED"),
+        &Tbits::from_str("PSKAUTHOR").unwrap(),
+        2,
+        true,
+    );
+    let new_subscriber = |seed: &str, with_ntru: bool| {
+        TangleSubscriber::gen(
+            Store::default(),
+            LinkGen::default(),
+            prng::dbg_init_str(seed),
+            &Tbits::from_str("PSKSUBSCRIBER").unwrap(),
+            with_ntru,
+        )
+    };
+    let mut subscriberP = new_subscriber("SUBSCRIBERP9SEED", false);
+    let mut subscriberN = new_subscriber("SUBSCRIBERN9SEED", true);
+    let mut subscriberC = new_subscriber("SUBSCRIBERC9SEED", true);
+
+    let pskid = Tbits::cycle_str(psk::PSKID_SIZE, "PSKID");
+    let psk = Tbits::cycle_str(psk::PSK_SIZE, "PSK");
+    author.psks.insert(pskid.clone(), psk.clone());
+    subscriberP.opt_psk = Some((pskid.clone(), psk));
+
+    let announcement = author.announce(MsgInfo::Announce)?;
+    transport.send_message(&announcement)?;
+    let msg = transport.recv_message(&announcement.link)?;
+    for subscriber in &mut [&mut subscriberP, &mut subscriberN, &mut subscriberC] {
+        subscriber.handle_msg(&msg, MsgInfo::Announce)?;
+        subscriber.link_gen.reset_appinst(announcement.link.base().clone());
+    }
+    let subscribe = subscriberN.subscribe(announcement.link.rel(), MsgInfo::Subscribe)?;
+    transport.send_message(&subscribe)?;
+    author.handle_msg(&transport.recv_message(&subscribe.link)?, MsgInfo::Subscribe)?;
+    let pkid = subscriberN.opt_ntru.as_ref().unwrap().1.get_pkid();
+
+    let psk_ids = vec![pskid.clone()];
+    let ntru_pkids = vec![pkid];
+    let keyload = author.share_keyload_private(announcement.link.rel(), &psk_ids, &ntru_pkids, MsgInfo::Keyload)?;
+    transport.send_message(&keyload)?;
+    let msg = transport.recv_message(&keyload.link)?;
+    for subscriber in &mut [&mut subscriberP, &mut subscriberN] {
+        match subscriber.handle_msg(&msg, MsgInfo::Keyload)? {
+            UnwrappedChannelMessage::Keyload => {}
+            _ => failure::bail!("Expected Keyload."),
+        }
+    }
+    ensure!(subscriberC.handle_msg(&msg, MsgInfo::Keyload).is_err());
+
+    let pkid = ntru_pkids[0].tbits();
+    ensure!(!contains_tbits(&msg.body, &pskid), "Private keyload reveals pskid.");
+    ensure!(!contains_tbits(&msg.body, pkid), "Private keyload reveals NTRU pkid.");
+
+
+    Ok(())
+}
+
+#[test]
+fn run_private_keyload() {
+    let mut transport = BucketTransport::new();
+    assert!(dbg!(private_keyload(&mut transport)).is_ok());
+}
+
+fn key_history<T: Transport>(transport: &mut T) -> Fallible<()>
+where
+    T::SendOptions: Default,
+    T::RecvOptions: Default,
+{
+    let mut author = Author::new("AUTHOR9SEED", 2, true);
+    let mut subscriber = Subscriber::new("SUBSCRIBERA9SEED", false);
+
+    let public_payload = Trytes(Tbits::from_str("PUBLICPAYLOAD").unwrap());
+    let masked_payload = Trytes(Tbits::from_str("MASKEDPAYLOAD").unwrap());
+
+    let announcement_link = {
+        let msg = author.announce()?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    subscriber.unwrap_announcement(transport.recv_message(&announcement_link)?.parse_header()?)?;
+    let old_mss_pk = subscriber.author_mss_public_key().clone();
+
+    let change_key_link = {
+        let msg = author.change_key(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    subscriber.unwrap_change_key(transport.recv_message(&change_key_link)?.parse_header()?)?;
+    ensure!(old_mss_pk != *subscriber.author_mss_public_key(), "Author's MSS public key has not changed.");
+
+    // Messages on the old branch are signed and verified with the old key.
+    let old_signed_link = {
+        let msg = author.sign_packet(&announcement_link, &public_payload, &masked_payload)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    subscriber.unwrap_signed_packet(transport.recv_message(&old_signed_link)?.parse_header()?)?;
+
+    let new_signed_link = {
+        let msg = author.sign_packet(&change_key_link, &public_payload, &masked_payload)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    subscriber.unwrap_signed_packet(transport.recv_message(&new_signed_link)?.parse_header()?)?;
+
+    // Key history is persisted, messages linked to signed packets inherit their branch key.
+    let mut author = Author::import(&author.export("AUTHOR9PASSWORD")?, "AUTHOR9PASSWORD")?;
+    let mut subscriber = Subscriber::import(&subscriber.export("SUBSCRIBER9PASSWORD")?, "SUBSCRIBER9PASSWORD")?;
+
+    for link_to in &[old_signed_link, new_signed_link] {
+        let signed_link = {
+            let msg = author.sign_packet(link_to, &public_payload, &masked_payload)?;
+            transport.send_message(&msg)?;
+            msg.link
+        };
+        let (unwrapped_public, unwrapped_masked) =
+            subscriber.unwrap_signed_packet(transport.recv_message(&signed_link)?.parse_header()?)?;
+        ensure!(public_payload == unwrapped_public, "Public payloads do not match");
+        ensure!(masked_payload == unwrapped_masked, "Masked payloads do not match");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn run_key_history() {
+    let mut transport = BucketTransport::new();
+    assert!(dbg!(key_history(&mut transport)).is_ok());
+}
+
+fn key_rotation<T: Transport>(transport: &mut T) -> Fallible<()>
+where
+    T::SendOptions: Default,
+    T::RecvOptions: Default,
+{
+    let public_payload = Trytes(Tbits::from_str("PUBLICPAYLOAD").unwrap());
+    let masked_payload = Trytes(Tbits::from_str("MASKEDPAYLOAD").unwrap());
+
+    // Exhausted key is never reused, including after import.
+    {
+        let mut author = Author::new("AUTHORA9SEED", 1, true);
+        let announcement_link = {
+            let msg = author.announce()?;
+            transport.send_message(&msg)?;
+            msg.link
+        };
+        ensure!(author.mss_keys_left(&announcement_link)? == 1, "Announce must use a WOTS key.");
+
+        let (change_key, _) =
+            author.sign_packet_with_key_rotation(&announcement_link, &public_payload, &masked_payload)?;
+        ensure!(change_key.is_none(), "Key must not be changed with manual policy.");
+        ensure!(author.mss_keys_left(&announcement_link)? == 0, "Signed packet must use a WOTS key.");
+
+        ensure!(author
+            .sign_packet(&announcement_link, &public_payload, &masked_payload)
+            .is_err());
+        ensure!(author.change_key(&announcement_link).is_err());
+        let mut author = Author::import(&author.export("AUTHOR9PASSWORD")?, "AUTHOR9PASSWORD")?;
+        ensure!(author
+            .sign_packet(&announcement_link, &public_payload, &masked_payload)
+            .is_err());
+    }
+
+    // Key is changed before it's exhausted.
+    let mut author = Author::new("AUTHORB9SEED", 2, true);
+    author.set_key_rotation_policy(KeyRotationPolicy::Threshold(2));
+    let mut subscriber = Subscriber::new("SUBSCRIBERA9SEED", false);
+
+    let announcement_link = {
+        let msg = author.announce()?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    subscriber.handle_msg(&transport.recv_message(&announcement_link)?)?;
+
+    let mut link_to = announcement_link;
+    let mut key_changes = 0;
+    for _ in 0..3 {
+        let (change_key, msg) = author.sign_packet_with_key_rotation(&link_to, &public_payload, &masked_payload)?;
+        if let Some(change_key) = change_key {
+            transport.send_message(&change_key)?;
+            subscriber.handle_msg(&transport.recv_message(&change_key.link)?)?;
+            key_changes += 1;
+        }
+        transport.send_message(&msg)?;
+        subscriber.handle_msg(&transport.recv_message(&msg.link)?)?;
+        ensure!(author.mss_keys_left(&msg.link)? > 0, "Key must be changed before it's exhausted.");
+        link_to = msg.link;
+
+        // Policy is persisted.
+        author = Author::import(&author.export("AUTHOR9PASSWORD")?, "AUTHOR9PASSWORD")?;
+    }
+    ensure!(key_changes == 2, "Key must be changed twice, got {} changes.", key_changes);
+
+    Ok(())
+}
+
+#[test]
+fn run_key_rotation() {
+    let mut transport = BucketTransport::new();
+    assert!(dbg!(key_rotation(&mut transport)).is_ok());
+}
+
+fn branches<T: Transport>(transport: &mut T) -> Fallible<()>
+where
+    T::SendOptions: Default,
+    T::RecvOptions: Copy + Default,
+{
+    let mut author = Author::new("AUTHOR9SEED", 3, true);
+    let mut subscriberA = Subscriber::new("SUBSCRIBERA9SEED", true);
+    let mut subscriberB = Subscriber::new("SUBSCRIBERB9SEED", true);
+
+    let public_payload = Trytes(Tbits::from_str("PUBLICPAYLOAD").unwrap());
+    let masked_payload = Trytes(Tbits::from_str("MASKEDPAYLOAD").unwrap());
+
+    let announcement_link = {
+        let msg = author.announce()?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    subscriberA.unwrap_announcement(transport.recv_message(&announcement_link)?.parse_header()?)?;
+    subscriberB.unwrap_announcement(transport.recv_message(&announcement_link)?.parse_header()?)?;
+
+    // Only A has access to ALPHA branch, both A and B have access to BETA branch.
+    let subscribe_link = {
+        let msg = subscriberA.subscribe(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    author.unwrap_subscribe(transport.recv_message(&subscribe_link)?.parse_header()?)?;
+    let alpha = author.new_branch_for_everyone("ALPHA", &announcement_link)?;
+    let subscribe_link = {
+        let msg = subscriberB.subscribe(&announcement_link)?;
+        transport.send_message(&msg)?;
+        msg.link
+    };
+    author.unwrap_subscribe(transport.recv_message(&subscribe_link)?.parse_header()?)?;
+    let beta = author.new_branch_for_everyone("BETA", &announcement_link)?;
+    ensure!(author.new_branch_for_everyone("BETA", &announcement_link).is_err());
+
+    let signed_packet = author.sign_packet_in_branch("ALPHA", &public_payload, &masked_payload)?;
+    ensure!(author.branch_tip("ALPHA") == Some(signed_packet.link.clone()));
+    let tagged_packet = author.tag_packet_in_branch("ALPHA", &public_payload, &masked_payload)?;
+    let beta_packet = author.tag_packet_in_branch("BETA", &public_payload, &masked_payload)?;
+    ensure!(author.branch_tip("ALPHA") == Some(tagged_packet.link.clone()));
+    ensure!(author.branch_tip("BETA") == Some(beta_packet.link.clone()));
+    ensure!(author.branch_names().len() == 2);
+    for msg in &[&alpha, &beta, &signed_packet, &tagged_packet, &beta_packet] {
+        transport.send_message(msg)?;
+    }
+
+    // Subscribers follow the branches they have keyloads for.
+    subscriberA.sync_state(transport)?;
+    subscriberB.sync_state(transport)?;
+    ensure!(subscriberA.branches().len() == 2);
+    ensure!(subscriberB.branches() == vec![beta.link.clone()]);
+    ensure!(subscriberA.branch_tip(&alpha.link) == author.branch_tip("ALPHA"));
+    ensure!(subscriberA.branch_tip(&beta.link) == author.branch_tip("BETA"));
+    ensure!(subscriberB.branch_tip(&alpha.link).is_none());
+
+    // Subscriber publishes the next message in the branch.
+    let subscriber_packet = subscriberB.tag_packet_in_branch(&beta.link, &public_payload, &masked_payload)?;
+    ensure!(subscriberB.branch_tip(&beta.link) == Some(subscriber_packet.link.clone()));
+    author.handle_msg(&subscriber_packet)?;
+    ensure!(author.branch_tip("BETA") == Some(subscriber_packet.link.clone()));
+
+    // Branches and their names are persisted.
+    let mut author = Author::import(&author.export("AUTHOR9PASSWORD")?, "AUTHOR9PASSWORD")?;
+    ensure!(author.branch_names().len() == 2);
+    ensure!(author.branch_tip("ALPHA") == Some(tagged_packet.link.clone()));
+    ensure!(author.branch_tip("BETA") == Some(subscriber_packet.link.clone()));
+    ensure!(author.new_branch_for_everyone("ALPHA", &announcement_link).is_err());
+    ensure!(author.name_branch("GAMMA", &signed_packet.link).is_err());
+    ensure!(author.new_branch_for_everyone("alpha", &announcement_link).is_err());
+    let next_packet = author.tag_packet_in_branch("ALPHA", &public_payload, &masked_payload)?;
+    ensure!(author.branch_tip("ALPHA") == Some(next_packet.link.clone()));
+    transport.send_message(&next_packet)?;
+    subscriberA.sync_state(transport)?;
+    ensure!(subscriberA.branch_tip(&alpha.link) == Some(next_packet.link.clone()));
+    let subscriberA = Subscriber::import(&subscriberA.export("SUBSCRIBER9PASSWORD")?, "SUBSCRIBER9PASSWORD")?;
+    ensure!(subscriberA.branch_tip(&alpha.link) == Some(next_packet.link));
+
+    Ok(())
+}
+
+#[test]
+fn run_branches() {
+    let mut transport = BucketTransport::new();
+    assert!(dbg!(branches(&mut transport)).is_ok());
+}
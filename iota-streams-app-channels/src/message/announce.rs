@@ -28,10 +28,7 @@
 //! * `sig` -- signature of `tag` field produced with the MSS private key corresponding to `msspk`.
 //!
 
-use failure::{
-    bail,
-    Fallible,
-};
+use failure::Fallible;
 
 use iota_streams_app::message;
 use iota_streams_core::{
@@ -68,16 +65,9 @@ where
     P: mss::Parameters<TW>,
 {
     fn sizeof<'c>(&self, ctx: &'c mut sizeof::Context<TW, F>) -> Fallible<&'c mut sizeof::Context<TW, F>> {
-        ctx.absorb(self.mss_sk.public_key())?;
-        let oneof: Trint3;
-        if let Some(ntru_pk) = self.ntru_pk {
-            oneof = Trint3(1);
-            ctx.absorb(&oneof)?.absorb(ntru_pk)?;
-        } else {
-            oneof = Trint3(0);
-            ctx.absorb(&oneof)?;
-        }
-        ctx.mssig(self.mss_sk, MssHashSig)?;
+        ctx.absorb(self.mss_sk.public_key())?
+            .optional(OneofMode::Absorb, self.ntru_pk, |ctx, ntru_pk| ctx.absorb(ntru_pk))?
+            .mssig(self.mss_sk, MssHashSig)?;
         Ok(ctx)
     }
 
@@ -86,16 +76,9 @@ where
         _store: &Store,
         ctx: &'c mut wrap::Context<TW, F, OS>,
     ) -> Fallible<&'c mut wrap::Context<TW, F, OS>> {
-        ctx.absorb(self.mss_sk.public_key())?;
-        let oneof: Trint3;
-        if let Some(ntru_pk) = self.ntru_pk {
-            oneof = Trint3(1);
-            ctx.absorb(&oneof)?.absorb(ntru_pk)?;
-        } else {
-            oneof = Trint3(0);
-            ctx.absorb(&oneof)?;
-        }
-        ctx.mssig(self.mss_sk, MssHashSig)?;
+        ctx.absorb(self.mss_sk.public_key())?
+            .optional(OneofMode::Absorb, self.ntru_pk, |ctx, ntru_pk| ctx.absorb(ntru_pk))?
+            .mssig(self.mss_sk, MssHashSig)?;
         Ok(ctx)
     }
 }
@@ -129,19 +112,9 @@ where
         _store: &Store,
         ctx: &'c mut unwrap::Context<TW, F, IS>,
     ) -> Fallible<&'c mut unwrap::Context<TW, F, IS>> {
-        ctx.absorb(&mut self.mss_pk)?;
-        let mut oneof = Trint3(-1);
-        ctx.absorb(&mut oneof)?;
-        self.ntru_pk = match oneof {
-            Trint3(0) => None,
-            Trint3(1) => {
-                let mut ntru_pk = ntru::PublicKey::default();
-                ctx.absorb(&mut ntru_pk)?;
-                Some(ntru_pk)
-            }
-            _ => bail!("Announce: bad oneof: {:?}", oneof),
-        };
-        ctx.mssig(&self.mss_pk, MssHashSig)?;
+        ctx.absorb(&mut self.mss_pk)?
+            .optional(OneofMode::Absorb, &mut self.ntru_pk, |ctx, ntru_pk| ctx.absorb(ntru_pk))?
+            .mssig(&self.mss_pk, MssHashSig)?;
         Ok(ctx)
     }
 }
@@ -1,5 +1,5 @@
 //! `ChangeKey` message content. This message is published by channel author.
-//! The message is linked to either `Announce` or `ChangeKey` message.
+//! The message is linked to `Announce`, `ChangeKey` or another message on the branch of a key.
 //!
 //! ```pb3
 //! message ChangeKey {
@@ -14,7 +14,8 @@
 //!
 //! # Fields:
 //!
-//! * `msgid` -- link to the message containing a trusted MSS public key.
+//! * `msgid` -- link to a message on the branch of a trusted MSS public key, ie.
+//! the `Announce` or `ChangeKey` message containing the key or a message linked to it.
 //! This key is used to derive trust relationship to the `msspk` public key.
 //!
 //! * `msspk` -- a new MSS public key.
@@ -25,7 +26,7 @@
 //! to the public key contained in `msspk` field -- proof of knowledge of private key.
 //!
 //! * `sig_with_linked_msspk` -- signature generated with the MSS private key
-//! corresponding to the *trusted* public key contained in the linked message.
+//! corresponding to the *trusted* public key valid on the branch of the linked message.
 //!
 
 use failure::Fallible;
@@ -126,34 +127,47 @@ where
     }
 }
 
-pub struct ContentUnwrap<'a, TW, P, Link>
+pub struct ContentUnwrap<TW, P, Link>
 where
     Link: HasLink,
 {
     pub(crate) link: <Link as HasLink>::Rel,
     pub(crate) mss_pk: mss::PublicKey<TW, P>,
-    pub(crate) mss_linked_pk: &'a mss::PublicKey<TW, P>,
+    /// Recovered public key, the caller must check it's trusted, ie. valid on the branch of `link`.
+    pub(crate) mss_linked_pk: mss::PublicKey<TW, P>,
     _phantom: std::marker::PhantomData<Link>,
 }
 
-impl<'a, TW, P, Link> ContentUnwrap<'a, TW, P, Link>
+impl<TW, P, Link> ContentUnwrap<TW, P, Link>
 where
     TW: BasicTbitWord,
     P: mss::Parameters<TW>,
     Link: HasLink,
-    <Link as HasLink>::Rel: 'a + Default,
+    <Link as HasLink>::Rel: Default,
 {
-    pub fn new(mss_linked_pk: &'a mss::PublicKey<TW, P>) -> Self {
+    pub fn new() -> Self {
         Self {
             link: <<Link as HasLink>::Rel as Default>::default(),
             mss_pk: mss::PublicKey::<TW, P>::default(),
-            mss_linked_pk: mss_linked_pk,
+            mss_linked_pk: mss::PublicKey::<TW, P>::default(),
             _phantom: std::marker::PhantomData,
         }
     }
 }
 
-impl<'a, TW, F, P, Link, Store> message::ContentUnwrap<TW, F, Store> for ContentUnwrap<'a, TW, P, Link>
+impl<TW, P, Link> Default for ContentUnwrap<TW, P, Link>
+where
+    TW: BasicTbitWord,
+    P: mss::Parameters<TW>,
+    Link: HasLink,
+    <Link as HasLink>::Rel: Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<TW, F, P, Link, Store> message::ContentUnwrap<TW, F, Store> for ContentUnwrap<TW, P, Link>
 where
     TW: IntTbitWord + SpongosTbitWord + trinary::TritWord,
     F: PRP<TW>,
@@ -173,9 +187,7 @@ where
             .commit()?
             .squeeze(&mut hash)?
             .mssig(&self.mss_pk, &hash)?
-            .mssig(self.mss_linked_pk, &hash)?;
-        //TODO: Lookup mss_linked_pk first and verify?
-        //Or recover mss_linked_pk and lookup info in the store and verify?
+            .mssig(&mut self.mss_linked_pk, &hash)?;
         Ok(ctx)
     }
 }
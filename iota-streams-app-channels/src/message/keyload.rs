@@ -48,8 +48,10 @@
 //!     One possible solution is to use ephemeral NTRU keys and `mask` keys `id`s
 //!     instead of `absorb`ing them. Then two keyload messages can be published consequently
 //!     and identities of the latter keyload will be protected with the key from the former.
-//! 2) Keyload is not authenticated (signed). It can later be implicitly authenticated
-//!     via `SignedPacket`.
+//!     `PrivateKeyload` message hides identities of the recipients in a single message.
+//! 2) Unsigned keyload is not authenticated. It can later be implicitly authenticated
+//!     via `SignedPacket`. Signature is computed over the spongos state after the session
+//!     key has been absorbed, so it can only be verified by legit recipients.
 
 use failure::Fallible;
 use iota_streams_app::message::{
@@ -72,6 +74,7 @@ use iota_streams_core::{
         },
     },
 };
+use iota_streams_core_mss::signature::mss;
 use iota_streams_core_ntru::key_encapsulation::ntru;
 use iota_streams_protobuf3::{
     command::*,
@@ -82,22 +85,27 @@ use iota_streams_protobuf3::{
 /// Type of `Keyload` message content.
 pub const TYPE: &str = "STREAMS9CHANNEL9KEYLOAD";
 
-pub struct ContentWrap<'a, TW, F, G, Link: HasLink, Psks, NtruPks> {
+pub struct ContentWrap<'a, TW, F, G, P, Link: HasLink, Psks, NtruPks>
+where
+    P: mss::Parameters<TW>,
+{
     pub(crate) link: &'a <Link as HasLink>::Rel,
     pub nonce: NTrytes<TW>,
     pub key: NTrytes<TW>,
     pub(crate) psks: Psks,
     pub(crate) prng: &'a prng::Prng<TW, G>,
     pub(crate) ntru_pks: NtruPks,
+    pub(crate) mss_sk: Option<&'a mss::PrivateKey<TW, P>>,
     pub(crate) _phantom: std::marker::PhantomData<(F, Link)>,
 }
 
-impl<'a, TW, F, G, Link, Store, Psks, NtruPks> message::ContentWrap<TW, F, Store>
-    for ContentWrap<'a, TW, F, G, Link, Psks, NtruPks>
+impl<'a, TW, F, G, P, Link, Store, Psks, NtruPks> message::ContentWrap<TW, F, Store>
+    for ContentWrap<'a, TW, F, G, P, Link, Psks, NtruPks>
 where
     TW: IntTbitWord + SpongosTbitWord + trinary::TritWord,
     F: 'a + PRP<TW> + Clone, // weird 'a constraint, but compiler requires it somehow?!
     G: PRP<TW> + Clone + Default,
+    P: mss::Parameters<TW>,
     Link: HasLink,
     <Link as HasLink>::Rel: 'a + Eq + SkipFallback<TW, F>,
     Store: LinkStore<TW, F, <Link as HasLink>::Rel>,
@@ -125,7 +133,10 @@ where
                 ctx.fork(|ctx| ctx.mask(&NTrytes(ntru_pk.get_pkid().0))?.ntrukem(ntru_pk, &self.key))
             })?
             .absorb(External(&self.key))?
-            .commit()?;
+            .commit()?
+            .fork(|ctx| {
+                ctx.optional(OneofMode::Skip, self.mss_sk, |ctx, mss_sk| ctx.mssig(mss_sk, MssHashSig))
+            })?;
         Ok(ctx)
     }
 
@@ -155,28 +166,37 @@ where
                 })
             })?
             .absorb(External(&self.key))?
-            .commit()?;
+            .commit()?
+            .fork(|ctx| {
+                ctx.optional(OneofMode::Skip, self.mss_sk, |ctx, mss_sk| ctx.mssig(mss_sk, MssHashSig))
+            })?;
         Ok(ctx)
     }
 }
 
 //This whole mess with `'a` and `LookupArg: 'a` is needed in order to allow `LookupPsk`
 //and `LookupNtruSk` avoid copying and return `&'a Psk` and `&'a NtruSk`.
-pub struct ContentUnwrap<'a, TW, F, Link: HasLink, LookupArg: 'a, LookupPsk, LookupNtruSk> {
+pub struct ContentUnwrap<'a, TW, F, P, Link: HasLink, LookupArg: 'a, LookupPsk, LookupNtruSk>
+where
+    P: mss::Parameters<TW>,
+{
     pub link: <Link as HasLink>::Rel,
     pub nonce: NTrytes<TW>,
     pub(crate) lookup_arg: &'a LookupArg,
     pub(crate) lookup_psk: LookupPsk,
     pub(crate) lookup_ntru_sk: LookupNtruSk,
     pub key: NTrytes<TW>,
+    /// MSS public key recovered from the signature or nothing if keyload is not signed.
+    pub mss_pk: Option<mss::PublicKey<TW, P>>,
     _phantom: std::marker::PhantomData<(F, Link)>,
 }
 
-impl<'a, TW, F, Link, LookupArg, LookupPsk, LookupNtruSk>
-    ContentUnwrap<'a, TW, F, Link, LookupArg, LookupPsk, LookupNtruSk>
+impl<'a, TW, F, P, Link, LookupArg, LookupPsk, LookupNtruSk>
+    ContentUnwrap<'a, TW, F, P, Link, LookupArg, LookupPsk, LookupNtruSk>
 where
     TW: BasicTbitWord,
     F: PRP<TW>,
+    P: mss::Parameters<TW>,
     Link: HasLink,
     <Link as HasLink>::Rel: Eq + Default + SkipFallback<TW, F>,
     LookupArg: 'a,
@@ -191,16 +211,18 @@ where
             lookup_psk,
             lookup_ntru_sk,
             key: NTrytes::zero(spongos::Spongos::<TW, F>::KEY_SIZE),
+            mss_pk: None,
             _phantom: std::marker::PhantomData,
         }
     }
 }
 
-impl<'a, TW, F, Link, Store, LookupArg, LookupPsk, LookupNtruSk> message::ContentUnwrap<TW, F, Store>
-    for ContentUnwrap<'a, TW, F, Link, LookupArg, LookupPsk, LookupNtruSk>
+impl<'a, TW, F, P, Link, Store, LookupArg, LookupPsk, LookupNtruSk> message::ContentUnwrap<TW, F, Store>
+    for ContentUnwrap<'a, TW, F, P, Link, LookupArg, LookupPsk, LookupNtruSk>
 where
     TW: IntTbitWord + SpongosTbitWord + trinary::TritWord,
     F: PRP<TW> + Clone,
+    P: mss::Parameters<TW>,
     Link: HasLink,
     <Link as HasLink>::Rel: Eq + Default + SkipFallback<TW, F>,
     Store: LinkStore<TW, F, <Link as HasLink>::Rel>,
@@ -267,7 +289,10 @@ where
             })?
             .guard(key_found, "Key not found")?
             .absorb(External(&self.key))?
-            .commit()?;
+            .commit()?
+            .fork(|ctx| {
+                ctx.optional(OneofMode::Skip, &mut self.mss_pk, |ctx, mss_pk| ctx.mssig(mss_pk, MssHashSig))
+            })?;
         Ok(ctx)
     }
 }
@@ -9,6 +9,9 @@ pub mod change_key;
 /// Keyload message.
 pub mod keyload;
 
+/// Keyload message hiding recipients' identities.
+pub mod private_keyload;
+
 /// SignedPacket message.
 pub mod signed_packet;
 
@@ -0,0 +1,296 @@
+//! `PrivateKeyload` message content. Similar to `Keyload` this message contains key information
+//! for the set of recipients, but it does not reveal identities of the recipients.
+//!
+//! ```pb3
+//! message PrivateKeyload {
+//!     join link msgid;
+//!     absorb tryte nonce[27];
+//!     skip repeated {
+//!         fork;
+//!         absorb external tryte psk[81];
+//!         commit;
+//!         squeeze tryte tag[27];
+//!         mask(key) tryte ekey[81];
+//!     }
+//!     skip repeated {
+//!         fork;
+//!         ntrukem(key) tryte ekey[3072];
+//!     }
+//!     absorb external tryte key[81];
+//!     commit;
+//! }
+//! fork {
+//!     skip oneof {
+//!         null unsigned = 0;
+//!         MSSig sig = 1;
+//!     }
+//! }
+//! ```
+//!
+//! # Fields:
+//!
+//! * `nonce` -- A nonce to be used with the key encapsulated in the keyload.
+//! A unique nonce allows for session keys to be reused.
+//!
+//! * `psk` -- Pre-shared key known to the author and to a legit recipient.
+//!
+//! * `tag` -- Pre-shared key tag; it depends on the pre-shared key and the nonce and
+//! replaces pre-shared key identifier.
+//!
+//! * `ekey` -- Masked session key; session key is either encrypted with spongos or with NTRU.
+//!
+//! * `key` -- Session key; a legit recipient gets it from `ekey`.
+//!
+//! * `sig` -- Optional signature; allows to authenticate keyload.
+//!
+//! Notes:
+//! 1) Pre-shared key tags can only be computed by the holders of pre-shared keys and
+//!     are different in different keyloads as nonce is unique. NTRU public key identifiers
+//!     are omitted, a recipient tries to decapsulate each `ekey` with its NTRU private key.
+//!     Thus the recipients can't be linked across keyloads by outside observers, although
+//!     the number of recipients is not hidden.
+//! 2) NTRU decapsulation is expensive, so unwrapping cost grows with the number of
+//!     NTRU recipients.
+
+use failure::Fallible;
+use iota_streams_app::message::{
+    self,
+    HasLink,
+};
+use iota_streams_core::{
+    prng,
+    psk,
+    sponge::{
+        prp::PRP,
+        spongos,
+    },
+    tbits::{
+        trinary,
+        word::{
+            BasicTbitWord,
+            IntTbitWord,
+            SpongosTbitWord,
+        },
+    },
+};
+use iota_streams_core_mss::signature::mss;
+use iota_streams_core_ntru::key_encapsulation::ntru;
+use iota_streams_protobuf3::{
+    command::*,
+    io,
+    types::*,
+};
+
+/// Type of `PrivateKeyload` message content.
+pub const TYPE: &str = "STREAMS9CHANNEL9PRIVATEKEYLOAD";
+
+/// Size of pre-shared key tag.
+pub const PSKTAG_SIZE: usize = psk::PSKID_SIZE;
+
+pub struct ContentWrap<'a, TW, F, G, P, Link: HasLink, Psks, NtruPks>
+where
+    P: mss::Parameters<TW>,
+{
+    pub(crate) link: &'a <Link as HasLink>::Rel,
+    pub nonce: NTrytes<TW>,
+    pub key: NTrytes<TW>,
+    pub(crate) psks: Psks,
+    pub(crate) prng: &'a prng::Prng<TW, G>,
+    pub(crate) ntru_pks: NtruPks,
+    pub(crate) mss_sk: Option<&'a mss::PrivateKey<TW, P>>,
+    pub(crate) _phantom: std::marker::PhantomData<(F, Link)>,
+}
+
+impl<'a, TW, F, G, P, Link, Store, Psks, NtruPks> message::ContentWrap<TW, F, Store>
+    for ContentWrap<'a, TW, F, G, P, Link, Psks, NtruPks>
+where
+    TW: IntTbitWord + SpongosTbitWord + trinary::TritWord,
+    F: 'a + PRP<TW> + Clone,
+    G: PRP<TW> + Clone + Default,
+    P: mss::Parameters<TW>,
+    Link: HasLink,
+    <Link as HasLink>::Rel: 'a + Eq + SkipFallback<TW, F>,
+    Store: LinkStore<TW, F, <Link as HasLink>::Rel>,
+    Psks: Clone + ExactSizeIterator<Item = psk::IPsk<'a, TW>>,
+    NtruPks: Clone + ExactSizeIterator<Item = ntru::INtruPk<'a, TW, F>>,
+{
+    fn sizeof<'c>(&self, ctx: &'c mut sizeof::Context<TW, F>) -> Fallible<&'c mut sizeof::Context<TW, F>> {
+        let store = EmptyLinkStore::<TW, F, <Link as HasLink>::Rel, ()>::default();
+        let repeated_psks = Size(self.psks.len());
+        let repeated_ntru_pks = Size(self.ntru_pks.len());
+        ctx.join(&store, self.link)?
+            .absorb(&self.nonce)?
+            .skip(repeated_psks)?
+            .repeated(self.psks.clone(), |ctx, (_pskid, psk)| {
+                ctx.fork(|ctx| {
+                    ctx.absorb(External(&NTrytes(psk.clone())))?
+                        .commit()?
+                        .squeeze(&Mac(PSKTAG_SIZE))?
+                        .mask(&self.key)
+                })
+            })?
+            .skip(repeated_ntru_pks)?
+            .repeated(self.ntru_pks.clone(), |ctx, ntru_pk| {
+                ctx.fork(|ctx| ctx.ntrukem(ntru_pk, &self.key))
+            })?
+            .absorb(External(&self.key))?
+            .commit()?
+            .fork(|ctx| {
+                ctx.optional(OneofMode::Skip, self.mss_sk, |ctx, mss_sk| ctx.mssig(mss_sk, MssHashSig))
+            })?;
+        Ok(ctx)
+    }
+
+    fn wrap<'c, OS: io::OStream<TW>>(
+        &self,
+        store: &Store,
+        ctx: &'c mut wrap::Context<TW, F, OS>,
+    ) -> Fallible<&'c mut wrap::Context<TW, F, OS>> {
+        let repeated_psks = Size(self.psks.len());
+        let repeated_ntru_pks = Size(self.ntru_pks.len());
+        ctx.join(store, self.link)?
+            .absorb(&self.nonce)?
+            .skip(repeated_psks)?
+            .repeated(self.psks.clone(), |ctx, (_pskid, psk)| {
+                ctx.fork(|ctx| {
+                    ctx.absorb(External(&NTrytes(psk.clone())))?
+                        .commit()?
+                        .squeeze(&Mac(PSKTAG_SIZE))?
+                        .mask(&self.key)
+                })
+            })?
+            .skip(repeated_ntru_pks)?
+            .repeated(self.ntru_pks.clone(), |ctx, ntru_pk| {
+                ctx.fork(|ctx| ctx.ntrukem((ntru_pk, self.prng, &self.nonce.0), &self.key))
+            })?
+            .absorb(External(&self.key))?
+            .commit()?
+            .fork(|ctx| {
+                ctx.optional(OneofMode::Skip, self.mss_sk, |ctx, mss_sk| ctx.mssig(mss_sk, MssHashSig))
+            })?;
+        Ok(ctx)
+    }
+}
+
+pub struct ContentUnwrap<'a, TW, F, P, Link: HasLink>
+where
+    P: mss::Parameters<TW>,
+{
+    pub link: <Link as HasLink>::Rel,
+    pub nonce: NTrytes<TW>,
+    pub(crate) psks: Vec<psk::IPsk<'a, TW>>,
+    pub(crate) ntru_sk: Option<&'a ntru::PrivateKey<TW, F>>,
+    pub key: NTrytes<TW>,
+    /// MSS public key recovered from the signature or nothing if keyload is not signed.
+    pub mss_pk: Option<mss::PublicKey<TW, P>>,
+    _phantom: std::marker::PhantomData<(F, Link)>,
+}
+
+impl<'a, TW, F, P, Link> ContentUnwrap<'a, TW, F, P, Link>
+where
+    TW: BasicTbitWord,
+    F: PRP<TW>,
+    P: mss::Parameters<TW>,
+    Link: HasLink,
+    <Link as HasLink>::Rel: Eq + Default + SkipFallback<TW, F>,
+{
+    /// Recipient's own pre-shared keys and NTRU private key are tried in order to unwrap the session key.
+    pub fn new(psks: Vec<psk::IPsk<'a, TW>>, ntru_sk: Option<&'a ntru::PrivateKey<TW, F>>) -> Self {
+        Self {
+            link: <<Link as HasLink>::Rel as Default>::default(),
+            nonce: NTrytes::zero(spongos::Spongos::<TW, F>::NONCE_SIZE),
+            psks,
+            ntru_sk,
+            key: NTrytes::zero(spongos::Spongos::<TW, F>::KEY_SIZE),
+            mss_pk: None,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<'a, TW, F, P, Link, Store> message::ContentUnwrap<TW, F, Store> for ContentUnwrap<'a, TW, F, P, Link>
+where
+    TW: IntTbitWord + SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW> + Clone,
+    P: mss::Parameters<TW>,
+    Link: HasLink,
+    <Link as HasLink>::Rel: Eq + Default + SkipFallback<TW, F>,
+    Store: LinkStore<TW, F, <Link as HasLink>::Rel>,
+{
+    fn unwrap<'c, IS: io::IStream<TW>>(
+        &mut self,
+        store: &Store,
+        ctx: &'c mut unwrap::Context<TW, F, IS>,
+    ) -> Fallible<&'c mut unwrap::Context<TW, F, IS>> {
+        let mut repeated_psks = Size(0);
+        let mut repeated_ntru_pks = Size(0);
+        let mut tag = NTrytes::zero(PSKTAG_SIZE);
+        let mut key_found = false;
+
+        ctx.join(store, &mut self.link)?
+            .absorb(&mut self.nonce)?
+            .skip(&mut repeated_psks)?;
+
+        // Tags of own pre-shared keys only depend on the nonce, compute them once.
+        let mut own_tags = Vec::with_capacity(self.psks.len());
+        for (_pskid, psk) in &self.psks {
+            let mut own_tag = External(NTrytes::zero(PSKTAG_SIZE));
+            ctx.fork(|ctx| {
+                ctx.absorb(External(&NTrytes((*psk).clone())))?
+                    .commit()?
+                    .squeeze(&mut own_tag)
+            })?;
+            own_tags.push(own_tag);
+        }
+
+        ctx.repeated(repeated_psks, |ctx| {
+            if !key_found {
+                ctx.skip(&mut tag)?;
+                if let Some(i) = own_tags.iter().position(|own_tag| (own_tag.0).0 == tag.0) {
+                    ctx.fork(|ctx| {
+                        // Tag has already been squeezed, squeeze it again to get the same spongos state.
+                        let mut own_tag = External(NTrytes::zero(PSKTAG_SIZE));
+                        ctx.absorb(External(&NTrytes((self.psks[i].1).clone())))?
+                            .commit()?
+                            .squeeze(&mut own_tag)?
+                            .mask(&mut self.key)
+                    })?;
+                    key_found = true;
+                    Ok(ctx)
+                } else {
+                    ctx.drop(Size(spongos::Spongos::<TW, F>::KEY_SIZE))
+                }
+            } else {
+                // Drop entire fork.
+                ctx.drop(Size(PSKTAG_SIZE + spongos::Spongos::<TW, F>::KEY_SIZE))
+            }
+        })?
+        .skip(&mut repeated_ntru_pks)?
+        .repeated(repeated_ntru_pks, |ctx| {
+            match self.ntru_sk {
+                Some(ntru_sk) if !key_found => {
+                    let mut key = NTrytes::zero(spongos::Spongos::<TW, F>::KEY_SIZE);
+                    let mut decapsulated = false;
+                    ctx.fork(|ctx| {
+                        // Failure to decapsulate means the key is encapsulated for another recipient.
+                        decapsulated = ctx.ntrukem(ntru_sk, &mut key).is_ok();
+                        Ok(ctx)
+                    })?;
+                    if decapsulated {
+                        self.key = key;
+                        key_found = true;
+                    }
+                    Ok(ctx)
+                }
+                _ => ctx.drop(Size(ntru::EKEY_SIZE)),
+            }
+        })?
+        .guard(key_found, "Key not found")?
+        .absorb(External(&self.key))?
+        .commit()?
+        .fork(|ctx| {
+            ctx.optional(OneofMode::Skip, &mut self.mss_pk, |ctx, mss_pk| ctx.mssig(mss_pk, MssHashSig))
+        })?;
+        Ok(ctx)
+    }
+}
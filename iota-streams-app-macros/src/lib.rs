@@ -0,0 +1,255 @@
+//! Procedural macros for IOTA Streams Applications.
+//!
+//! Message content is usually processed three times: `sizeof` computes the size of the wrapped
+//! content, `wrap` and `unwrap` encode and decode it. `pb3_message!` generates all three from
+//! a single PB3 definition so that they can't get out of sync.
+
+extern crate proc_macro;
+
+mod schema;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::parse_macro_input;
+
+use schema::{
+    Command,
+    Message,
+    Statement,
+    Type,
+};
+
+/// Generate `ContentWrap` and `ContentUnwrap` types of a message from its PB3 definition.
+///
+/// ```ignore
+/// pb3_message! {
+///     message Unsubscribe {
+///         join link msgid;
+///         commit;
+///         squeeze tryte mac[81];
+///     }
+/// }
+/// ```
+///
+/// Each joined link and each absorbed, masked or skipped value becomes a field:
+/// `ContentWrap<'a, TW, F, Link>` holds references to the values to be wrapped and
+/// `ContentUnwrap<TW, F, Link>` owns the unwrapped values. The `Link` type parameter is present
+/// only if the message joins a link. Squeezed MACs are not stored in the content.
+///
+/// PB3 types map to `iota_streams_protobuf3::types`: `tryte x` to `Trint3`, `tryte x[n]` to
+/// `NTrytes` of `n` trytes, `trytes x` to `Trytes` and `size x` to `Size`.
+///
+/// Only `join`, `absorb`, `mask`, `skip`, `commit` and `squeeze` commands are supported.
+/// `fork`, `repeated`, `mssig`, `ntrukem` and `oneof` are not, so messages using them,
+/// eg. `announce`, `keyload` and `signed_packet`, keep hand-written implementations.
+///
+/// The generated code refers to `failure`, `iota_streams_core`, `iota_streams_protobuf3` and
+/// `iota_streams_app` crates which must be dependencies of the calling crate.
+#[proc_macro]
+pub fn pb3_message(input: TokenStream) -> TokenStream {
+    let msg = parse_macro_input!(input as Message);
+    expand(&msg).into()
+}
+
+fn field_type(ty: Type) -> TokenStream2 {
+    match ty {
+        Type::Tryte => quote!(::iota_streams_protobuf3::types::Trint3),
+        Type::NTrytes(_) => quote!(::iota_streams_protobuf3::types::NTrytes<TW>),
+        Type::Trytes => quote!(::iota_streams_protobuf3::types::Trytes<TW>),
+        Type::Size => quote!(::iota_streams_protobuf3::types::Size),
+    }
+}
+
+fn field_default(ty: Type) -> TokenStream2 {
+    match ty {
+        Type::Tryte => quote!(::iota_streams_protobuf3::types::Trint3::default()),
+        Type::NTrytes(n) => {
+            let n = n * 3;
+            quote!(::iota_streams_protobuf3::types::NTrytes::<TW>::zero(#n))
+        }
+        Type::Trytes => quote!(::iota_streams_protobuf3::types::Trytes::<TW>::default()),
+        Type::Size => quote!(::iota_streams_protobuf3::types::Size::default()),
+    }
+}
+
+fn command_trait(command: Command) -> TokenStream2 {
+    match command {
+        Command::Absorb => quote!(::iota_streams_protobuf3::command::Absorb::absorb),
+        Command::Mask => quote!(::iota_streams_protobuf3::command::Mask::mask),
+        Command::Skip => quote!(::iota_streams_protobuf3::command::Skip::skip),
+    }
+}
+
+/// Commands of `sizeof`, `wrap` or `unwrap` applied to `ctx`, `store` and fields `self.x`.
+fn commands(msg: &Message, unwrap: bool) -> Vec<TokenStream2> {
+    let field = |name| {
+        if unwrap {
+            quote!(&mut self.#name)
+        } else {
+            quote!(self.#name)
+        }
+    };
+    msg.statements
+        .iter()
+        .map(|statement| match statement {
+            Statement::Join(name) => {
+                let link = field(name);
+                quote!(::iota_streams_protobuf3::command::Join::join(&mut *ctx, store, #link)?;)
+            }
+            Statement::Field(command, _, name) => {
+                let command = command_trait(*command);
+                let value = field(name);
+                quote!(#command(&mut *ctx, #value)?;)
+            }
+            Statement::Commit => quote!(::iota_streams_protobuf3::command::Commit::commit(&mut *ctx)?;),
+            Statement::Squeeze(_, n) => {
+                let n = n * 3;
+                quote!(::iota_streams_protobuf3::command::Squeeze::squeeze(
+                    &mut *ctx,
+                    &::iota_streams_protobuf3::types::Mac(#n),
+                )?;)
+            }
+        })
+        .collect()
+}
+
+fn expand(msg: &Message) -> TokenStream2 {
+    let has_link = msg.has_link();
+    let rel = quote!(<Link as ::iota_streams_app::message::HasLink>::Rel);
+    let (link_param, link_arg, link_bounds, store_bounds, store) = if has_link {
+        (
+            quote!(, Link: ::iota_streams_app::message::HasLink),
+            quote!(, Link),
+            quote!(Link: ::iota_streams_app::message::HasLink,),
+            quote!(
+                #rel: ::std::cmp::Eq + ::iota_streams_protobuf3::types::SkipFallback<TW, F>,
+                Store: ::iota_streams_protobuf3::types::LinkStore<TW, F, #rel>,
+            ),
+            quote!(store),
+        )
+    } else {
+        (quote!(), quote!(), quote!(), quote!(), quote!(_store))
+    };
+    let default_bounds = if has_link {
+        quote!(#rel: ::std::default::Default,)
+    } else {
+        quote!()
+    };
+
+    let mut wrap_fields = Vec::new();
+    let mut unwrap_fields = Vec::new();
+    let mut unwrap_defaults = Vec::new();
+    for statement in &msg.statements {
+        match statement {
+            Statement::Join(name) => {
+                wrap_fields.push(quote!(pub(crate) #name: &'a #rel,));
+                unwrap_fields.push(quote!(pub #name: #rel,));
+                unwrap_defaults.push(quote!(#name: <#rel as ::std::default::Default>::default(),));
+            }
+            Statement::Field(_, ty, name) => {
+                let field_type = field_type(*ty);
+                let field_default = field_default(*ty);
+                wrap_fields.push(quote!(pub(crate) #name: &'a #field_type,));
+                unwrap_fields.push(quote!(pub #name: #field_type,));
+                unwrap_defaults.push(quote!(#name: #field_default,));
+            }
+            Statement::Commit | Statement::Squeeze(_, _) => {}
+        }
+    }
+
+    let sizeof_store = if has_link {
+        quote!(let store = &::iota_streams_protobuf3::types::EmptyLinkStore::<TW, F, #rel, ()>::default();)
+    } else {
+        quote!()
+    };
+    let wrap_commands = commands(msg, false);
+    let unwrap_commands = commands(msg, true);
+
+    let wrap_doc = format!("Content of `{}` message to be wrapped.\n\n```pb3\n{}\n```", msg.name, msg);
+    let unwrap_doc = format!("Unwrapped content of `{}` message.\n\n```pb3\n{}\n```", msg.name, msg);
+
+    quote! {
+        #[doc = #wrap_doc]
+        pub struct ContentWrap<'a, TW, F #link_param> {
+            #(#wrap_fields)*
+            pub(crate) _phantom: ::std::marker::PhantomData<(&'a (), TW, F #link_arg)>,
+        }
+
+        impl<'a, TW, F #link_arg, Store> ::iota_streams_app::message::ContentWrap<TW, F, Store>
+            for ContentWrap<'a, TW, F #link_arg>
+        where
+            TW: ::iota_streams_core::tbits::word::SpongosTbitWord + ::iota_streams_core::tbits::trinary::TritWord,
+            F: ::iota_streams_core::sponge::prp::PRP<TW>,
+            #link_bounds
+            #store_bounds
+        {
+            fn sizeof<'c>(
+                &self,
+                ctx: &'c mut ::iota_streams_protobuf3::command::sizeof::Context<TW, F>,
+            ) -> ::failure::Fallible<&'c mut ::iota_streams_protobuf3::command::sizeof::Context<TW, F>> {
+                #sizeof_store
+                #(#wrap_commands)*
+                Ok(ctx)
+            }
+
+            fn wrap<'c, OS: ::iota_streams_protobuf3::io::OStream<TW>>(
+                &self,
+                #store: &Store,
+                ctx: &'c mut ::iota_streams_protobuf3::command::wrap::Context<TW, F, OS>,
+            ) -> ::failure::Fallible<&'c mut ::iota_streams_protobuf3::command::wrap::Context<TW, F, OS>> {
+                #(#wrap_commands)*
+                Ok(ctx)
+            }
+        }
+
+        #[doc = #unwrap_doc]
+        pub struct ContentUnwrap<TW, F #link_param> {
+            #(#unwrap_fields)*
+            pub(crate) _phantom: ::std::marker::PhantomData<(TW, F #link_arg)>,
+        }
+
+        impl<TW, F #link_arg> ContentUnwrap<TW, F #link_arg>
+        where
+            TW: ::iota_streams_core::tbits::word::BasicTbitWord,
+            #link_bounds
+            #default_bounds
+        {
+            pub fn new() -> Self {
+                Self {
+                    #(#unwrap_defaults)*
+                    _phantom: ::std::marker::PhantomData,
+                }
+            }
+        }
+
+        impl<TW, F #link_arg> ::std::default::Default for ContentUnwrap<TW, F #link_arg>
+        where
+            TW: ::iota_streams_core::tbits::word::BasicTbitWord,
+            #link_bounds
+            #default_bounds
+        {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<TW, F #link_arg, Store> ::iota_streams_app::message::ContentUnwrap<TW, F, Store>
+            for ContentUnwrap<TW, F #link_arg>
+        where
+            TW: ::iota_streams_core::tbits::word::SpongosTbitWord + ::iota_streams_core::tbits::trinary::TritWord,
+            F: ::iota_streams_core::sponge::prp::PRP<TW>,
+            #link_bounds
+            #store_bounds
+        {
+            fn unwrap<'c, IS: ::iota_streams_protobuf3::io::IStream<TW>>(
+                &mut self,
+                #store: &Store,
+                ctx: &'c mut ::iota_streams_protobuf3::command::unwrap::Context<TW, F, IS>,
+            ) -> ::failure::Fallible<&'c mut ::iota_streams_protobuf3::command::unwrap::Context<TW, F, IS>> {
+                #(#unwrap_commands)*
+                Ok(ctx)
+            }
+        }
+    }
+}
@@ -0,0 +1,264 @@
+//! Protobuf3 message definitions supported by `pb3_message!`.
+//!
+//! ```pb3
+//! message Name {
+//!     join link msgid;
+//!     absorb tryte version;
+//!     mask tryte key[81];
+//!     skip trytes payload;
+//!     absorb size n;
+//!     commit;
+//!     squeeze tryte mac[27];
+//! }
+//! ```
+
+use std::fmt;
+use syn::{
+    braced,
+    bracketed,
+    parse::{
+        Parse,
+        ParseStream,
+    },
+    Error,
+    Ident,
+    LitInt,
+    Result,
+    Token,
+};
+
+/// PB3 type of a message field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Type {
+    /// `tryte x` -- `Trint3`.
+    Tryte,
+    /// `tryte x[n]` -- `NTrytes` of `n` trytes.
+    NTrytes(usize),
+    /// `trytes x` -- `Trytes`.
+    Trytes,
+    /// `size x` -- `Size`.
+    Size,
+}
+
+/// PB3 command processing a message field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    Absorb,
+    Mask,
+    Skip,
+}
+
+impl Command {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Command::Absorb => "absorb",
+            Command::Mask => "mask",
+            Command::Skip => "skip",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Statement {
+    /// `join link name;`
+    Join(Ident),
+    /// `absorb|mask|skip type name;`
+    Field(Command, Type, Ident),
+    /// `commit;`
+    Commit,
+    /// `squeeze tryte name[n];` -- MAC of `n` trytes, not stored in the content.
+    Squeeze(Ident, usize),
+}
+
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub name: Ident,
+    pub statements: Vec<Statement>,
+}
+
+impl Message {
+    /// Check whether the message joins a link and content types are generic over link type.
+    pub fn has_link(&self) -> bool {
+        self.statements.iter().any(|s| matches!(s, Statement::Join(_)))
+    }
+}
+
+fn parse_keyword(input: ParseStream, keyword: &str) -> Result<()> {
+    let ident: Ident = input.parse()?;
+    if ident == keyword {
+        Ok(())
+    } else {
+        Err(Error::new(ident.span(), format!("Expected `{}`.", keyword)))
+    }
+}
+
+/// Parse optional array size `[n]`.
+fn parse_array_size(input: ParseStream) -> Result<Option<usize>> {
+    if !input.peek(syn::token::Bracket) {
+        return Ok(None);
+    }
+    let content;
+    bracketed!(content in input);
+    let n: LitInt = content.parse()?;
+    match n.base10_parse()? {
+        0 => Err(Error::new(n.span(), "Array size must be positive.")),
+        n => Ok(Some(n)),
+    }
+}
+
+fn parse_type(input: ParseStream) -> Result<(Type, Ident)> {
+    let ty: Ident = input.parse()?;
+    let name: Ident = input.parse()?;
+    let size = parse_array_size(input)?;
+    match (ty.to_string().as_str(), size) {
+        ("tryte", None) => Ok((Type::Tryte, name)),
+        ("tryte", Some(n)) => Ok((Type::NTrytes(n), name)),
+        ("trytes", None) => Ok((Type::Trytes, name)),
+        ("size", None) => Ok((Type::Size, name)),
+        ("trytes", Some(_)) | ("size", Some(_)) => Err(Error::new(ty.span(), format!("`{}` can't be an array.", ty))),
+        _ => Err(Error::new(
+            ty.span(),
+            format!("Unsupported type `{}`, expected `tryte`, `trytes` or `size`.", ty),
+        )),
+    }
+}
+
+impl Parse for Statement {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let command: Ident = input.parse()?;
+        let statement = match command.to_string().as_str() {
+            "join" => {
+                parse_keyword(input, "link")?;
+                Statement::Join(input.parse()?)
+            }
+            "absorb" => {
+                let (ty, name) = parse_type(input)?;
+                Statement::Field(Command::Absorb, ty, name)
+            }
+            "mask" => {
+                let (ty, name) = parse_type(input)?;
+                Statement::Field(Command::Mask, ty, name)
+            }
+            "skip" => {
+                let (ty, name) = parse_type(input)?;
+                Statement::Field(Command::Skip, ty, name)
+            }
+            "commit" => Statement::Commit,
+            "squeeze" => match parse_type(input)? {
+                (Type::NTrytes(n), name) => Statement::Squeeze(name, n),
+                (_, name) => return Err(Error::new(name.span(), "Only `tryte` arrays can be squeezed.")),
+            },
+            _ => {
+                return Err(Error::new(
+                    command.span(),
+                    format!("Unsupported command `{}`.", command),
+                ))
+            }
+        };
+        input.parse::<Token![;]>()?;
+        Ok(statement)
+    }
+}
+
+impl Parse for Message {
+    fn parse(input: ParseStream) -> Result<Self> {
+        parse_keyword(input, "message")?;
+        let name: Ident = input.parse()?;
+        let content;
+        braced!(content in input);
+        let mut statements: Vec<Statement> = Vec::new();
+        let mut fields: Vec<Ident> = Vec::new();
+        while !content.is_empty() {
+            let statement: Statement = content.parse()?;
+            match &statement {
+                Statement::Join(field) | Statement::Field(_, _, field) => {
+                    if fields.contains(field) {
+                        return Err(Error::new(field.span(), format!("Duplicate field `{}`.", field)));
+                    }
+                    fields.push(field.clone());
+                }
+                Statement::Commit | Statement::Squeeze(_, _) => {}
+            }
+            statements.push(statement);
+        }
+        Ok(Self { name, statements })
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Tryte | Type::NTrytes(_) => write!(f, "tryte"),
+            Type::Trytes => write!(f, "trytes"),
+            Type::Size => write!(f, "size"),
+        }
+    }
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Statement::Join(name) => write!(f, "join link {};", name),
+            Statement::Field(command, Type::NTrytes(n), name) => {
+                write!(f, "{} tryte {}[{}];", command.as_str(), name, n)
+            }
+            Statement::Field(command, ty, name) => write!(f, "{} {} {};", command.as_str(), ty, name),
+            Statement::Commit => write!(f, "commit;"),
+            Statement::Squeeze(name, n) => write!(f, "squeeze tryte {}[{}];", name, n),
+        }
+    }
+}
+
+/// Message definition in PB3 syntax.
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "message {} {{", self.name)?;
+        for statement in &self.statements {
+            writeln!(f, "    {}", statement)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_message() {
+        let src = "message TaggedPacket { join link msgid; absorb trytes public_payload; mask tryte key[81]; \
+                   skip size n; absorb tryte version; commit; squeeze tryte mac[27]; }";
+        let msg: Message = syn::parse_str(src).unwrap();
+        assert_eq!(msg.name, "TaggedPacket");
+        assert!(msg.has_link());
+        assert_eq!(
+            msg.to_string(),
+            "message TaggedPacket {
+    join link msgid;
+    absorb trytes public_payload;
+    mask tryte key[81];
+    skip size n;
+    absorb tryte version;
+    commit;
+    squeeze tryte mac[27];
+}"
+        );
+    }
+
+    #[test]
+    fn reject_bad_message() {
+        for src in &[
+            "msg M { commit; }",
+            "message M { commit }",
+            "message M { absorb u8 x; }",
+            "message M { absorb trytes x[3]; }",
+            "message M { absorb tryte x[0]; }",
+            "message M { squeeze trytes mac; }",
+            "message M { join msgid; }",
+            "message M { fork; }",
+            "message M { absorb size n; mask trytes n; }",
+        ] {
+            assert!(syn::parse_str::<Message>(src).is_err(), "{}", src);
+        }
+    }
+}
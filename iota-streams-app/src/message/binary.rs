@@ -0,0 +1,158 @@
+//! Compact byte encoding of Tbinary messages for non-trinary transports.
+//!
+//! Encoded message is `link || body_size || body`, where `body_size` is the number of body trits
+//! as 4-byte big-endian integer and `body` trits are packed 5 trits per byte (`B1T5` words),
+//! which is about 1.7 times shorter than the tryte string representation.
+
+use failure::{
+    bail,
+    ensure,
+    Fallible,
+};
+use std::convert::TryInto;
+
+use iota_streams_core::tbits::{
+    trinary::{
+        trits_from_b1t5,
+        trits_to_b1t5,
+        TritWord,
+    },
+    Tbits,
+};
+
+use super::TbinaryMessage;
+
+/// Size of the encoded number of trits in bytes.
+const SIZE_BYTES: usize = 4;
+
+/// Link with compact byte encoding.
+pub trait BinaryLink: Sized {
+    /// Append encoded link to `buf`, fails if the link can't be encoded.
+    fn encode_link(&self, buf: &mut Vec<u8>) -> Fallible<()>;
+
+    /// Decode link from the beginning of `bytes`, return the link and the number of bytes read.
+    fn decode_link(bytes: &[u8]) -> Fallible<(Self, usize)>;
+}
+
+/// Append `ts` packed 5 trits per byte to `buf`.
+pub fn encode_trits<TW>(ts: &Tbits<TW>, buf: &mut Vec<u8>)
+where
+    TW: TritWord,
+{
+    buf.extend(trits_to_b1t5(ts.slice()));
+}
+
+/// Decode `n` trits packed 5 trits per byte from the beginning of `bytes`, return the trits
+/// and the number of bytes read.
+pub fn decode_trits<TW>(n: usize, bytes: &[u8]) -> Fallible<(Tbits<TW>, usize)>
+where
+    TW: TritWord,
+{
+    let size = (n + 4) / 5;
+    ensure!(size <= bytes.len(), "Not enough bytes to decode {} trits.", n);
+    match trits_from_b1t5(n, &bytes[..size]) {
+        Some(ts) => Ok((ts, size)),
+        None => bail!("Invalid encoding of {} trits.", n),
+    }
+}
+
+/// Append number of trits in `ts` as 4-byte big-endian integer followed by `ts` packed 5 trits per byte to `buf`.
+pub fn encode_sized_trits<TW>(ts: &Tbits<TW>, buf: &mut Vec<u8>)
+where
+    TW: TritWord,
+{
+    buf.extend_from_slice(&(ts.size() as u32).to_be_bytes());
+    encode_trits(ts, buf);
+}
+
+/// Decode trits encoded with `encode_sized_trits` from the beginning of `bytes`, return the trits
+/// and the number of bytes read.
+pub fn decode_sized_trits<TW>(bytes: &[u8]) -> Fallible<(Tbits<TW>, usize)>
+where
+    TW: TritWord,
+{
+    ensure!(SIZE_BYTES <= bytes.len(), "Number of trits is missing.");
+    let n = u32::from_be_bytes(bytes[..SIZE_BYTES].try_into().unwrap()) as usize;
+    let (ts, size) = decode_trits(n, &bytes[SIZE_BYTES..])?;
+    Ok((ts, SIZE_BYTES + size))
+}
+
+impl<TW, F, Link> TbinaryMessage<TW, F, Link>
+where
+    TW: TritWord,
+    Link: BinaryLink,
+{
+    /// Encode link and body of the message into bytes.
+    pub fn to_bytes(&self) -> Fallible<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.link.encode_link(&mut buf)?;
+        encode_sized_trits(&self.body, &mut buf);
+        Ok(buf)
+    }
+
+    /// Decode message encoded with `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Fallible<Self> {
+        let (link, link_size) = Link::decode_link(bytes)?;
+        let (body, body_size) = decode_sized_trits(&bytes[link_size..])?;
+        let trailing = bytes.len() - link_size - body_size;
+        ensure!(trailing == 0, "{} trailing bytes after message body.", trailing);
+        Ok(Self::new(link, body))
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_encode_decode_trits() {
+    use iota_streams_core::tbits::trinary::Trit;
+
+    for n in &[0, 1, 4, 5, 6, 243] {
+        let ts = Tbits::<Trit>::cycle_str(*n, "9ANZM");
+        let mut buf = vec![];
+        encode_sized_trits(&ts, &mut buf);
+        assert_eq!(SIZE_BYTES + (n + 4) / 5, buf.len());
+        // Trailing bytes are not read.
+        buf.push(0);
+        let (ts2, size) = decode_sized_trits::<Trit>(&buf).unwrap();
+        assert!(ts == ts2 && size + 1 == buf.len());
+        assert!(decode_sized_trits::<Trit>(&buf[..size - 1]).is_err());
+    }
+    assert!(decode_sized_trits::<Trit>(&[0, 0, 1]).is_err());
+    // 243 is not a valid B1T5 byte.
+    assert!(decode_trits::<Trit>(5, &[243]).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_message_to_from_bytes() {
+    use crate::transport::tangle::{
+        AppInst,
+        MsgId,
+        TangleAddress,
+        APPINST_SIZE,
+        MSGID_SIZE,
+    };
+    use iota_streams_core::{
+        sponge::prp::troika::Troika,
+        tbits::trinary::Trit,
+    };
+    use iota_streams_protobuf3::types::NTrytes;
+
+    let link = TangleAddress::<Trit>::new(
+        AppInst {
+            id: NTrytes(Tbits::cycle_str(APPINST_SIZE, "A")),
+        },
+        MsgId {
+            id: NTrytes(Tbits::cycle_str(MSGID_SIZE, "M")),
+        },
+    );
+    let msg = TbinaryMessage::<Trit, Troika, TangleAddress<Trit>>::new(link, Tbits::cycle_str(6561, "BODY"));
+    let mut bytes = msg.to_bytes().unwrap();
+    assert_eq!((APPINST_SIZE + MSGID_SIZE + 4) / 5 + SIZE_BYTES + (6561 + 4) / 5, bytes.len());
+
+    let msg2 = TbinaryMessage::<Trit, Troika, TangleAddress<Trit>>::from_bytes(&bytes).unwrap();
+    assert!(msg.link == msg2.link && msg.body == msg2.body);
+
+    bytes.push(0);
+    assert!(TbinaryMessage::<Trit, Troika, TangleAddress<Trit>>::from_bytes(&bytes).is_err());
+    assert!(TbinaryMessage::<Trit, Troika, TangleAddress<Trit>>::from_bytes(&bytes[..100]).is_err());
+}
@@ -9,6 +9,9 @@ use iota_streams_protobuf3::{
     io,
 };
 
+/// Generate `ContentWrap` and `ContentUnwrap` types of a message from its PB3 definition.
+pub use iota_streams_app_macros::pb3_message;
+
 /// Type of "absolute" links. For http it's the absolute URL.
 pub trait HasLink: Sized {
     /// Type of "base" links. For http it's domain name.
@@ -69,6 +72,8 @@ mod wrapped;
 pub use wrapped::*;
 mod tbinary;
 pub use tbinary::*;
+mod binary;
+pub use binary::*;
 mod preparsed;
 pub use preparsed::*;
 mod unwrapped;
@@ -56,12 +56,10 @@ where
         let mut buf = Tbits::<TW>::zero(buf_size);
 
         let spongos = {
-            let mut ctx = wrap::Context::new(buf.slice_mut());
-            self.header.wrap(&*self.store, &mut ctx)?;
-            self.content.wrap(&*self.store, &mut ctx)?;
-            ensure!(ctx.stream.is_empty(), "OStream has not been exhausted.");
+            let wrapped = self.wrap_stream(buf.slice_mut())?;
+            ensure!(wrapped.stream.is_empty(), "OStream has not been exhausted.");
 
-            ctx.spongos
+            wrapped.spongos
         };
 
         Ok(WrappedMessage {
@@ -73,4 +71,27 @@ where
             },
         })
     }
+
+    /// Wrap header and content into `stream`, eg. `io::ChunkedOStream` writing fragments
+    /// of transaction size, without allocating a buffer for the whole message.
+    ///
+    /// The stream is returned by `WrappedStream::commit` once the link has been committed
+    /// into the link store the same way as `WrappedMessage::commit` does.
+    pub fn wrap_stream<OS: io::OStream<TW>>(&self, stream: OS) -> Fallible<WrappedStream<TW, F, Link, OS>>
+    where
+        Link: Clone,
+        Store: 'a,
+        Header<TW, Link>: ContentWrap<TW, F, Store>,
+        Content: ContentWrap<TW, F, Store>,
+    {
+        let mut ctx = wrap::Context::new(stream);
+        self.header.wrap(&*self.store, &mut ctx)?;
+        self.content.wrap(&*self.store, &mut ctx)?;
+        ctx.stream.commit();
+        Ok(WrappedStream {
+            link: self.header.link.clone(),
+            stream: ctx.stream,
+            spongos: ctx.spongos,
+        })
+    }
 }
@@ -2,21 +2,58 @@ use failure::Fallible;
 use std::string::ToString;
 
 use super::*;
-use iota_streams_core::tbits::{
-    word::StringTbitWord,
-    TbitSlice,
+use iota_streams_core::{
+    sponge::prp::PRP,
+    tbits::{
+        trinary,
+        word::{
+            SpongosTbitWord,
+            StringTbitWord,
+        },
+        TbitSlice,
+    },
+};
+use iota_streams_protobuf3::{
+    command::unwrap,
+    types::*,
 };
-use iota_streams_protobuf3::command::unwrap;
 
 /// Message context preparsed for unwrapping.
-pub struct PreparsedMessage<'a, TW, F, Link> {
+///
+/// The rest of the message is read from `IS`, the message body slice by default.
+pub struct PreparsedMessage<'a, TW, F, Link, IS = TbitSlice<'a, TW>> {
     pub header: Header<TW, Link>,
-    pub(crate) ctx: unwrap::Context<TW, F, TbitSlice<'a, TW>>,
+    pub(crate) ctx: unwrap::Context<TW, F, IS>,
+    pub(crate) _phantom: std::marker::PhantomData<&'a ()>,
+}
+
+impl<'a, TW, F, Link, IS> PreparsedMessage<'a, TW, F, Link, IS>
+where
+    TW: SpongosTbitWord + StringTbitWord + trinary::TritWord,
+    F: PRP<TW> + Default,
+    Link: Clone + AbsorbExternalFallback<TW, F>,
+    IS: io::IStream<TW>,
+{
+    /// Unwrap header of the message with `link` from `stream`, eg. `io::ChunkedIStream`
+    /// reading fragments of bundle transactions.
+    pub fn parse_header(link: Link, stream: IS) -> Fallible<Self> {
+        let mut ctx = unwrap::Context::new(stream);
+        let mut header = Header::<TW, Link>::new(link);
+        let store = EmptyLinkStore::<TW, F, Link, ()>::default();
+        header.unwrap(&store, &mut ctx)?;
+
+        Ok(Self {
+            header,
+            ctx,
+            _phantom: std::marker::PhantomData,
+        })
+    }
 }
 
-impl<'a, TW, F, Link> PreparsedMessage<'a, TW, F, Link>
+impl<'a, TW, F, Link, IS> PreparsedMessage<'a, TW, F, Link, IS>
 where
     TW: StringTbitWord,
+    IS: io::IStream<TW>,
 {
     pub fn check_content_type(&self, content_type: &str) -> bool {
         (self.header.content_type.0).eq_str(content_type)
@@ -44,16 +81,18 @@ where
     }
 }
 
-impl<'a, TW, F, Link> Clone for PreparsedMessage<'a, TW, F, Link>
+impl<'a, TW, F, Link, IS> Clone for PreparsedMessage<'a, TW, F, Link, IS>
 where
     TW: Clone,
     F: Clone,
     Link: Clone,
+    IS: Clone,
 {
     fn clone(&self) -> Self {
         Self {
             header: self.header.clone(),
             ctx: self.ctx.clone(),
+            _phantom: std::marker::PhantomData,
         }
     }
 }
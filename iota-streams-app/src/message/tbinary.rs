@@ -14,7 +14,10 @@ use iota_streams_core::{
     },
 };
 use iota_streams_protobuf3::{
-    command::unwrap,
+    command::unwrap::{
+        self,
+        TraceFormat,
+    },
     types::*,
 };
 
@@ -73,14 +76,36 @@ where
     Link: Clone + AbsorbExternalFallback<TW, F>,
 {
     pub fn parse_header<'a>(&'a self) -> Fallible<PreparsedMessage<'a, TW, F, Link>> {
-        let mut ctx = unwrap::Context::new(self.body.slice());
+        PreparsedMessage::parse_header(self.link().clone(), self.body.slice())
+    }
+
+    /// Unwrap header and `content` and write a trace of every unwrapped field into `w`,
+    /// see `unwrap::Trace`. The trace is written even if unwrapping fails, eg. due to a bad MAC,
+    /// the error is then included in the trace and returned.
+    pub fn dump<Store, Content, W>(
+        &self,
+        store: &Store,
+        content: &mut Content,
+        format: TraceFormat,
+        w: &mut W,
+    ) -> Fallible<()>
+    where
+        Content: ContentUnwrap<TW, F, Store>,
+        W: fmt::Write,
+    {
+        let mut ctx = unwrap::Context::new_with_trace(self.body.slice());
         let mut header = Header::<TW, Link>::new(self.link().clone());
-        let store = EmptyLinkStore::<TW, F, Link, ()>::default();
-        header.unwrap(&store, &mut ctx)?;
+        let header_store = EmptyLinkStore::<TW, F, Link, ()>::default();
+        let r = header
+            .unwrap(&header_store, &mut ctx)
+            .and_then(|ctx| content.unwrap(store, ctx))
+            .map(|_| ());
 
-        Ok(PreparsedMessage {
-            header: header,
-            ctx: ctx,
-        })
+        let mut trace = ctx.trace.take().unwrap_or_default();
+        if let Err(e) = &r {
+            trace.error = Some(e.to_string());
+        }
+        trace.write(format, w)?;
+        r
     }
 }
@@ -37,3 +37,33 @@ where
         Ok(self.message)
     }
 }
+
+/// Result of wrapping the message into a stream, see `PreparedMessage::wrap_stream`.
+///
+/// The stream is only accessible after the link has been committed into the store.
+pub struct WrappedStream<TW, F, Link, OS> {
+    pub(crate) link: Link,
+    pub(crate) stream: OS,
+    pub(crate) spongos: Spongos<TW, F>,
+}
+
+impl<TW, F, Link, OS> WrappedStream<TW, F, Link, OS>
+where
+    TW: SpongosTbitWord,
+    F: PRP<TW>,
+    Link: HasLink,
+{
+    /// Save link for the current wrapped message and associated info into the store, return the stream.
+    pub fn commit<Store>(
+        mut self,
+        mut store: RefMut<Store>,
+        info: <Store as LinkStore<TW, F, <Link as HasLink>::Rel>>::Info,
+    ) -> Fallible<OS>
+    where
+        Store: LinkStore<TW, F, <Link as HasLink>::Rel>,
+    {
+        self.spongos.commit();
+        store.update(self.link.rel(), self.spongos, info)?;
+        Ok(self.stream)
+    }
+}
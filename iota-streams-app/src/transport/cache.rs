@@ -0,0 +1,151 @@
+//! Transport caching received messages.
+
+use failure::Fallible;
+use std::{
+    collections::HashMap,
+    hash,
+};
+
+use crate::{
+    message::TbinaryMessage,
+    transport::{
+        MessageWithMetadata,
+        Transport,
+    },
+};
+
+/// Read-through cache of messages and their metadata received from the inner transport indexed by link.
+///
+/// Only non-empty results are cached, receive options are not part of the key. Empty results
+/// and errors, eg. `LinkNotFound`, are returned as is and the next receive asks the inner transport
+/// again. Cached entry is dropped when a message is sent to its link, so the next receive fetches
+/// all messages again.
+pub struct CachingTransport<T, TW, F, Link> {
+    inner: T,
+    cache: HashMap<Link, Vec<MessageWithMetadata<TW, F, Link>>>,
+}
+
+impl<T, TW, F, Link> CachingTransport<T, TW, F, Link>
+where
+    Link: Eq + hash::Hash,
+{
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Check whether messages with link `link` are cached.
+    pub fn is_cached(&self, link: &Link) -> bool {
+        self.cache.contains_key(link)
+    }
+
+    /// Drop cached messages with link `link`.
+    pub fn invalidate(&mut self, link: &Link) {
+        self.cache.remove(link);
+    }
+
+    /// Drop all cached messages.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Inner transport.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Mutable inner transport, cache is not updated by requests made directly to the inner transport.
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Unwrap the inner transport.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T, TW, F, Link> Transport<TW, F, Link> for CachingTransport<T, TW, F, Link>
+where
+    T: Transport<TW, F, Link>,
+    TW: Clone,
+    Link: Eq + hash::Hash + Clone,
+{
+    type SendOptions = T::SendOptions;
+
+    fn send_message_with_options(&mut self, msg: &TbinaryMessage<TW, F, Link>, opt: Self::SendOptions) -> Fallible<()> {
+        self.invalidate(msg.link());
+        self.inner.send_message_with_options(msg, opt)
+    }
+
+    type RecvOptions = T::RecvOptions;
+
+    fn recv_messages_with_options(
+        &mut self,
+        link: &Link,
+        opt: Self::RecvOptions,
+    ) -> Fallible<Vec<TbinaryMessage<TW, F, Link>>> {
+        Ok(self
+            .recv_messages_with_metadata_and_options(link, opt)?
+            .into_iter()
+            .map(|(msg, _)| msg)
+            .collect())
+    }
+
+    fn recv_messages_with_metadata_and_options(
+        &mut self,
+        link: &Link,
+        opt: Self::RecvOptions,
+    ) -> Fallible<Vec<MessageWithMetadata<TW, F, Link>>> {
+        if let Some(msgs) = self.cache.get(link) {
+            return Ok(msgs.clone());
+        }
+        let msgs = self.inner.recv_messages_with_metadata_and_options(link, opt)?;
+        if !msgs.is_empty() {
+            self.cache.insert(link.clone(), msgs.clone());
+        }
+        Ok(msgs)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_caching_transport() {
+    use super::{
+        is_link_not_found,
+        BucketTransport,
+    };
+    use iota_streams_core::{
+        sponge::prp::troika::Troika,
+        tbits::{
+            trinary::Trit,
+            Tbits,
+        },
+    };
+
+    let mut transport = CachingTransport::new(BucketTransport::<Trit, Troika, String>::new());
+    let link = "LINK".to_string();
+    let msg = TbinaryMessage::new(link.clone(), Tbits::zero(6));
+
+    // Errors are not cached.
+    let e = Transport::recv_messages(&mut transport, &link).err().unwrap();
+    assert!(is_link_not_found(&e));
+    assert!(!transport.is_cached(&link));
+
+    Transport::send_message(&mut transport, &msg).unwrap();
+    assert_eq!(1, Transport::recv_messages(&mut transport, &link).unwrap().len());
+    assert!(transport.is_cached(&link));
+
+    // Messages sent directly to the inner transport are not seen until the entry is dropped.
+    Transport::send_message(transport.inner_mut(), &msg).unwrap();
+    assert_eq!(1, Transport::recv_messages(&mut transport, &link).unwrap().len());
+    transport.invalidate(&link);
+    assert_eq!(2, Transport::recv_messages(&mut transport, &link).unwrap().len());
+
+    // Sending drops the entry.
+    Transport::send_message(&mut transport, &msg).unwrap();
+    assert!(!transport.is_cached(&link));
+    assert_eq!(3, Transport::recv_messages(&mut transport, &link).unwrap().len());
+}
@@ -0,0 +1,236 @@
+//! Filesystem-backed transport.
+
+use failure::{
+    bail,
+    Fallible,
+};
+use std::{
+    fs,
+    io,
+    path::{
+        Path,
+        PathBuf,
+    },
+    process,
+    str::FromStr,
+    sync::atomic::{
+        AtomicUsize,
+        Ordering,
+    },
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use iota_streams_core::tbits::{
+    word::StringTbitWord,
+    Tbits,
+};
+
+use crate::{
+    message::TbinaryMessage,
+    transport::{
+        tangle::TangleAddress,
+        LinkNotFound,
+        MessageMetadata,
+        MessageWithMetadata,
+        Transport,
+    },
+};
+
+/// Extension of message files.
+const MSG_EXT: &str = "trytes";
+
+/// Counter of messages sent by the current process, makes message file names unique.
+static MSG_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Transport storing messages in a directory.
+///
+/// Each message is stored as a tryte string in a separate file `<dir>/<appinst>/<msgid>/<name>.trytes`,
+/// where `name` is made of send time, process id and message counter. Several messages can be stored
+/// under the same link, they are received in the order of file names, ie. in the order of sending.
+///
+/// Message files are first written under a temporary name starting with `.` and then renamed, so
+/// a directory can be shared between processes or synchronized with tools like `rsync` which use
+/// the same convention: files with names starting with `.` are ignored when receiving messages.
+///
+/// Message body is padded with zero trits to the whole number of trytes, the same way
+/// messages sent over the Tangle are.
+pub struct FileTransport<TW, F> {
+    dir: PathBuf,
+    _phantom: std::marker::PhantomData<(TW, F)>,
+}
+
+impl<TW, F> FileTransport<TW, F> {
+    /// Transport storing messages in directory `dir`, the directory is created when the first message is sent.
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Directory containing messages.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl<TW, F> FileTransport<TW, F>
+where
+    TW: StringTbitWord,
+{
+    /// Directory containing messages with link `link`.
+    fn link_dir(&self, link: &TangleAddress<TW>) -> PathBuf {
+        self.dir.join(link.appinst.to_string()).join(link.msgid.to_string())
+    }
+}
+
+fn msg_file_name() -> Fallible<String> {
+    let time = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    Ok(format!(
+        "{:020}-{}-{}",
+        time.as_nanos(),
+        process::id(),
+        MSG_COUNTER.fetch_add(1, Ordering::SeqCst)
+    ))
+}
+
+/// Metadata of message file `path`: send time is taken from the file name.
+fn msg_file_metadata(path: &Path) -> MessageMetadata {
+    let nanos = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.split('-').next())
+        .and_then(|time| time.parse::<i64>().ok());
+    MessageMetadata {
+        timestamp: nanos.map(|nanos| nanos / 1_000_000_000),
+        source: Some(path.display().to_string()),
+        ..MessageMetadata::default()
+    }
+}
+
+fn is_msg_file(path: &Path) -> bool {
+    let visible = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map_or(false, |name| !name.starts_with('.'));
+    visible && path.extension().map_or(false, |ext| ext == MSG_EXT)
+}
+
+impl<TW, F> Transport<TW, F, TangleAddress<TW>> for FileTransport<TW, F>
+where
+    TW: StringTbitWord,
+{
+    type SendOptions = ();
+
+    /// Store a message in a new file.
+    fn send_message_with_options(&mut self, msg: &TbinaryMessage<TW, F, TangleAddress<TW>>, _opt: ()) -> Fallible<()> {
+        let dir = self.link_dir(msg.link());
+        fs::create_dir_all(&dir)?;
+        let name = msg_file_name()?;
+        let tmp_path = dir.join(format!(".{}.tmp", name));
+        fs::write(&tmp_path, msg.body.to_string())?;
+        fs::rename(&tmp_path, dir.join(format!("{}.{}", name, MSG_EXT)))?;
+        Ok(())
+    }
+
+    type RecvOptions = ();
+
+    /// Load all messages stored under `link`.
+    fn recv_messages_with_options(
+        &mut self,
+        link: &TangleAddress<TW>,
+        opt: (),
+    ) -> Fallible<Vec<TbinaryMessage<TW, F, TangleAddress<TW>>>> {
+        Ok(self
+            .recv_messages_with_metadata_and_options(link, opt)?
+            .into_iter()
+            .map(|(msg, _)| msg)
+            .collect())
+    }
+
+    /// Load all messages stored under `link` together with send time and file path.
+    fn recv_messages_with_metadata_and_options(
+        &mut self,
+        link: &TangleAddress<TW>,
+        _opt: (),
+    ) -> Fallible<Vec<MessageWithMetadata<TW, F, TangleAddress<TW>>>> {
+        let entries = match fs::read_dir(self.link_dir(link)) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(LinkNotFound.into()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut paths = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if is_msg_file(&path) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut msgs = Vec::with_capacity(paths.len());
+        for path in paths {
+            let trytes = fs::read_to_string(&path)?;
+            match Tbits::<TW>::from_str(trytes.trim()) {
+                Ok(body) => msgs.push((TbinaryMessage::new(link.clone(), body), msg_file_metadata(&path))),
+                Err(()) => bail!("Bad message file: '{}'.", path.display()),
+            }
+        }
+        Ok(msgs)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_send_recv() {
+    use super::is_link_not_found;
+    use crate::transport::tangle::{
+        AppInst,
+        MsgId,
+        APPINST_SIZE,
+        MSGID_SIZE,
+    };
+    use iota_streams_core::{
+        sponge::prp::troika::Troika,
+        tbits::trinary::Trit,
+    };
+    use iota_streams_protobuf3::types::NTrytes;
+
+    let dir = std::env::temp_dir().join(format!("iota-streams-file-test-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let mut transport = FileTransport::<Trit, Troika>::new(&dir);
+
+    let link = TangleAddress::<Trit>::new(
+        AppInst {
+            id: NTrytes(Tbits::cycle_str(APPINST_SIZE, "A")),
+        },
+        MsgId {
+            id: NTrytes(Tbits::cycle_str(MSGID_SIZE, "M")),
+        },
+    );
+    assert!(transport.recv_messages(&link).err().map_or(false, |e| is_link_not_found(&e)));
+
+    // Bodies are padded to whole trytes.
+    let m1 = TbinaryMessage::new(link.clone(), Tbits::cycle_str(6, "B"));
+    let m2 = TbinaryMessage::new(link.clone(), &Tbits::cycle_str(6, "C") + &Tbits::zero(1));
+    transport.send_message(&m1).unwrap();
+    transport.send_message(&m2).unwrap();
+
+    // Hidden and foreign files are ignored.
+    fs::write(transport.link_dir(&link).join(".hidden.trytes"), "D").unwrap();
+    fs::write(transport.link_dir(&link).join("readme.txt"), "E").unwrap();
+
+    let msgs = transport.recv_messages_with_metadata(&link).unwrap();
+    assert_eq!(2, msgs.len());
+    assert!(msgs[0].0.body.eq_str("BB"));
+    assert!(msgs[1].0.body.eq_str("CC9"));
+    assert!(msgs.iter().all(|(_, meta)| meta.timestamp.is_some() && meta.source.is_some()));
+
+    fs::write(transport.link_dir(&link).join("bad.trytes"), "bad").unwrap();
+    assert!(transport.recv_messages(&link).is_err());
+
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,213 @@
+//! Transport over Streams HTTP API.
+//!
+//! - `POST {url}/{appinst}/{msgid}` stores the message body sent as the request body.
+//! - `GET {url}/{appinst}/{msgid}` returns bodies of all messages stored under the link in the order
+//!   of storing, or `404 Not Found` if there are none.
+//!
+//! Each body is encoded as the number of body trits (4-byte big-endian integer) followed by the trits
+//! packed 5 per byte, see `message::encode_sized_trits`. Content type is `application/octet-stream`.
+
+use failure::{
+    bail,
+    Fallible,
+};
+use std::io::Read;
+
+use reqwest::{
+    header::CONTENT_TYPE,
+    StatusCode,
+};
+
+use iota_streams_core::tbits::{
+    trinary::TritWord,
+    word::StringTbitWord,
+    Tbits,
+};
+
+use crate::{
+    message::{
+        decode_sized_trits,
+        encode_sized_trits,
+        TbinaryMessage,
+    },
+    transport::{
+        http::HttpAddress,
+        LinkNotFound,
+        MessageMetadata,
+        MessageWithMetadata,
+        Transport,
+    },
+};
+
+/// Content type of message bodies.
+pub const CONTENT_TYPE_BODY: &str = "application/octet-stream";
+
+/// Encode message bodies as HTTP request or response body.
+pub fn encode_bodies<'a, TW, I>(bodies: I) -> Vec<u8>
+where
+    TW: TritWord + 'a,
+    I: IntoIterator<Item = &'a Tbits<TW>>,
+{
+    let mut buf = Vec::new();
+    for body in bodies {
+        encode_sized_trits(body, &mut buf);
+    }
+    buf
+}
+
+/// Decode message bodies encoded with `encode_bodies`.
+pub fn decode_bodies<TW>(mut bytes: &[u8]) -> Fallible<Vec<Tbits<TW>>>
+where
+    TW: TritWord,
+{
+    let mut bodies = Vec::new();
+    while !bytes.is_empty() {
+        let (body, size) = decode_sized_trits(bytes)?;
+        bodies.push(body);
+        bytes = &bytes[size..];
+    }
+    Ok(bodies)
+}
+
+/// Transport sending messages to the server found in the message link.
+pub struct HttpTransport<TW, F> {
+    client: reqwest::Client,
+    _phantom: std::marker::PhantomData<(TW, F)>,
+}
+
+impl<TW, F> HttpTransport<TW, F> {
+    pub fn new() -> Self {
+        Self::with_client(reqwest::Client::new())
+    }
+
+    /// Transport making requests with `client`, eg. configured with timeouts or proxy.
+    pub fn with_client(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<TW, F> Default for HttpTransport<TW, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<TW, F> Transport<TW, F, HttpAddress<TW>> for HttpTransport<TW, F>
+where
+    TW: StringTbitWord + TritWord,
+{
+    type SendOptions = ();
+
+    fn send_message_with_options(&mut self, msg: &TbinaryMessage<TW, F, HttpAddress<TW>>, _opt: ()) -> Fallible<()> {
+        let mut response = self
+            .client
+            .post(&msg.link.to_string())
+            .header(CONTENT_TYPE, CONTENT_TYPE_BODY)
+            .body(encode_bodies(Some(&msg.body)))
+            .send()?;
+        if !response.status().is_success() {
+            bail!("Message rejected by the server: {} {}", response.status(), response.text()?);
+        }
+        Ok(())
+    }
+
+    type RecvOptions = ();
+
+    fn recv_messages_with_options(
+        &mut self,
+        link: &HttpAddress<TW>,
+        opt: (),
+    ) -> Fallible<Vec<TbinaryMessage<TW, F, HttpAddress<TW>>>> {
+        Ok(self
+            .recv_messages_with_metadata_and_options(link, opt)?
+            .into_iter()
+            .map(|(msg, _)| msg)
+            .collect())
+    }
+
+    /// Receive messages together with their URL.
+    fn recv_messages_with_metadata_and_options(
+        &mut self,
+        link: &HttpAddress<TW>,
+        _opt: (),
+    ) -> Fallible<Vec<MessageWithMetadata<TW, F, HttpAddress<TW>>>> {
+        let url = link.to_string();
+        let mut response = self.client.get(&url).send()?;
+        match response.status() {
+            StatusCode::OK => {}
+            StatusCode::NOT_FOUND => return Err(LinkNotFound.into()),
+            status => bail!("Request failed: {} {}", status, response.text()?),
+        }
+        let mut bytes = Vec::new();
+        response.read_to_end(&mut bytes)?;
+        let metadata = MessageMetadata {
+            source: Some(url),
+            ..MessageMetadata::default()
+        };
+        Ok(decode_bodies(&bytes)?
+            .into_iter()
+            .map(|body| (TbinaryMessage::new(link.clone(), body), metadata.clone()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_encode_decode_bodies() {
+    use iota_streams_core::tbits::trinary::Trit;
+
+    let bodies = vec![
+        Tbits::<Trit>::zero(0),
+        Tbits::cycle_str(7, "A"),
+        Tbits::cycle_str(6561, "B"),
+    ];
+    let bytes = encode_bodies(&bodies);
+    assert!(decode_bodies::<Trit>(&bytes).unwrap() == bodies);
+    assert!(decode_bodies::<Trit>(&bytes[..bytes.len() - 1]).is_err());
+    assert!(decode_bodies::<Trit>(&[]).unwrap().is_empty());
+}
+
+#[cfg(test)]
+#[test]
+fn test_send_recv() {
+    use super::mock::MockServer;
+    use crate::transport::{
+        is_link_not_found,
+        tangle::{
+            AppInst,
+            MsgId,
+            APPINST_SIZE,
+            MSGID_SIZE,
+        },
+    };
+    use iota_streams_core::{
+        sponge::prp::troika::Troika,
+        tbits::trinary::Trit,
+    };
+    use iota_streams_protobuf3::types::NTrytes;
+
+    let server = MockServer::start().unwrap();
+    let mut transport = HttpTransport::<Trit, Troika>::new();
+    let link = HttpAddress::new(
+        server.url(),
+        AppInst {
+            id: NTrytes(Tbits::cycle_str(APPINST_SIZE, "A")),
+        },
+        MsgId {
+            id: NTrytes(Tbits::cycle_str(MSGID_SIZE, "M")),
+        },
+    );
+    assert!(transport.recv_messages(&link).err().map_or(false, |e| is_link_not_found(&e)));
+
+    let m1 = TbinaryMessage::new(link.clone(), Tbits::cycle_str(6, "B"));
+    let m2 = TbinaryMessage::new(link.clone(), Tbits::cycle_str(7, "C"));
+    transport.send_message(&m1).unwrap();
+    transport.send_message(&m2).unwrap();
+    let msgs = transport.recv_messages_with_metadata(&link).unwrap();
+    assert_eq!(2, msgs.len());
+    assert!(msgs[0].0.body == m1.body && msgs[1].0.body == m2.body);
+    assert_eq!(Some(link.to_string()), msgs[0].1.source);
+}
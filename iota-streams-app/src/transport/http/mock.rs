@@ -0,0 +1,224 @@
+//! Local in-process Streams HTTP server.
+//!
+//! Implements the API used by `HttpTransport`, see `http::client`. Messages are kept in memory
+//! and are lost when the server is stopped.
+
+use failure::{
+    ensure,
+    Fallible,
+};
+use std::{
+    collections::HashMap,
+    io::{
+        BufRead,
+        BufReader,
+        Write,
+    },
+    net::{
+        SocketAddr,
+        TcpListener,
+        TcpStream,
+    },
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        Arc,
+        Mutex,
+    },
+    thread,
+};
+
+use iota_streams_core::tbits::trinary::Trit;
+
+use super::client::{
+    decode_bodies,
+    CONTENT_TYPE_BODY,
+};
+
+/// Size of the application instance identifier in trytes.
+const APPINST_TRYTES: usize = 81;
+
+/// Size of the message identifier in trytes.
+const MSGID_TRYTES: usize = 27;
+
+/// Encoded message bodies indexed by link path `/{appinst}/{msgid}`.
+type Messages = HashMap<String, Vec<u8>>;
+
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn new(status: &'static str, content_type: &'static str, body: Vec<u8>) -> Self {
+        Self {
+            status,
+            content_type,
+            body,
+        }
+    }
+
+    fn error(status: &'static str, message: &str) -> Self {
+        Self::new(status, "text/plain", message.as_bytes().to_vec())
+    }
+}
+
+fn is_trytes(s: &str, size: usize) -> bool {
+    s.len() == size && s.chars().all(|c| c == '9' || c.is_ascii_uppercase())
+}
+
+/// Check that `path` is a link path `/{appinst}/{msgid}`.
+fn is_link_path(path: &str) -> bool {
+    let mut segments = path.split('/');
+    segments.next() == Some("")
+        && segments.next().map_or(false, |appinst| is_trytes(appinst, APPINST_TRYTES))
+        && segments.next().map_or(false, |msgid| is_trytes(msgid, MSGID_TRYTES))
+        && segments.next().is_none()
+}
+
+fn process_request(messages: &Mutex<Messages>, request: &Request) -> Response {
+    if !is_link_path(&request.path) {
+        return Response::error("404 Not Found", "Bad link.");
+    }
+    let mut messages = messages.lock().unwrap();
+    match request.method.as_str() {
+        "POST" => match decode_bodies::<Trit>(&request.body) {
+            Ok(bodies) if bodies.len() == 1 => {
+                messages
+                    .entry(request.path.clone())
+                    .or_default()
+                    .extend_from_slice(&request.body);
+                Response::new("201 Created", "text/plain", Vec::new())
+            }
+            Ok(_) => Response::error("400 Bad Request", "Expected exactly one message body."),
+            Err(e) => Response::error("400 Bad Request", &e.to_string()),
+        },
+        "GET" => match messages.get(&request.path) {
+            Some(bodies) => Response::new("200 OK", CONTENT_TYPE_BODY, bodies.clone()),
+            None => Response::error("404 Not Found", "Link not found."),
+        },
+        _ => Response::error("405 Method Not Allowed", "Only GET and POST are supported."),
+    }
+}
+
+/// Read HTTP request from `reader`, or `None` if the connection has been closed.
+fn read_request<R: BufRead>(reader: &mut R) -> Fallible<Option<Request>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let mut request_line = line.split_whitespace();
+    let method = request_line.next().unwrap_or("").to_string();
+    let path = request_line.next().unwrap_or("").to_string();
+    ensure!(!method.is_empty() && path.starts_with('/'), "Bad HTTP request: '{}'.", line.trim());
+
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        ensure!(reader.read_line(&mut line)? != 0, "Unexpected end of HTTP request.");
+        let header = line.trim();
+        if header.is_empty() {
+            break;
+        }
+        let mut parts = header.splitn(2, ':');
+        if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse()?;
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(Request { method, path, body }))
+}
+
+fn write_response<W: Write>(writer: &mut W, response: &Response) -> Fallible<()> {
+    write!(
+        writer,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    )?;
+    writer.write_all(&response.body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn serve_connection(stream: TcpStream, messages: &Mutex<Messages>) -> Fallible<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    while let Some(request) = read_request(&mut reader)? {
+        write_response(&mut writer, &process_request(messages, &request))?;
+    }
+    Ok(())
+}
+
+/// Local Streams HTTP server listening on a random localhost port.
+///
+/// The server stops listening when dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    url: String,
+    stop: Arc<AtomicBool>,
+    listener: Option<thread::JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Start server.
+    pub fn start() -> Fallible<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let messages = Arc::new(Mutex::new(Messages::new()));
+
+        let stop_listener = stop.clone();
+        let listener = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop_listener.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let messages = messages.clone();
+                    thread::spawn(move || {
+                        // Connection errors only affect the client of the connection.
+                        let _ = serve_connection(stream, &messages);
+                    });
+                }
+            }
+        });
+
+        Ok(Self {
+            addr,
+            url: format!("http://{}", addr),
+            stop,
+            listener: Some(listener),
+        })
+    }
+
+    /// URL of the server suitable for `HttpLinkGenerator::new`.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // Wake up the listener blocked in `accept`.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(listener) = self.listener.take() {
+            let _ = listener.join();
+        }
+    }
+}
@@ -0,0 +1,538 @@
+//! HTTP-specific transport definitions.
+//!
+//! Messages are stored on a Streams HTTP server under URL `{url}/{appinst}/{msgid}`, where `url` is
+//! the server URL, eg. `http://localhost:8080/streams`, and `appinst` and `msgid` are tryte strings
+//! of the application instance and message identifiers.
+
+use failure::{
+    bail,
+    ensure,
+    Fallible,
+};
+use std::{
+    convert::TryInto,
+    fmt,
+    hash,
+};
+
+use iota_streams_core::{
+    sponge::prp::PRP,
+    tbits::{
+        trinary::{
+            self,
+            Trit,
+            Tryte,
+        },
+        word::{
+            BasicTbitWord,
+            SpongosTbitWord,
+            StringTbitWord,
+        },
+        Tbits,
+    },
+};
+use iota_streams_core_mss::signature::mss;
+use iota_streams_protobuf3::{
+    command::*,
+    io,
+    types::*,
+};
+
+use crate::{
+    message::*,
+    transport::tangle::{
+        AppInst,
+        DefaultTangleLinkGenerator,
+        MsgId,
+        TangleAddress,
+    },
+};
+
+/// Server URL without trailing slashes.
+fn normalize_url(url: &str) -> String {
+    url.trim_end_matches('/').to_string()
+}
+
+/// Encode string as trytes, each byte is encoded with two trytes, least significant first.
+fn trytes_from_str<TW>(s: &str) -> Trytes<TW>
+where
+    TW: trinary::TritWord,
+{
+    let trits: Vec<Trit> = s
+        .bytes()
+        .flat_map(|b| vec![Tryte(b % 27), Tryte(b / 27)])
+        .flat_map(|t| <[Trit; 3]>::from(t).to_vec())
+        .collect();
+    Trytes(Tbits::from_tbits(&trits))
+}
+
+/// Decode string encoded with `trytes_from_str`.
+fn str_from_trytes<TW>(trytes: &Trytes<TW>) -> Fallible<String>
+where
+    TW: trinary::TritWord,
+{
+    let n = trytes.0.size();
+    ensure!(n % 6 == 0, "Bad string encoding: {} trits.", n);
+    let mut trits = vec![Trit(0); n];
+    trytes.0.slice().get_tbits(&mut trits);
+    let bytes = trits
+        .chunks(6)
+        .map(|ts| {
+            let lo: &[Trit; 3] = ts[..3].try_into().unwrap();
+            let hi: &[Trit; 3] = ts[3..].try_into().unwrap();
+            (Tryte::from(lo).0 as u16 + 27 * Tryte::from(hi).0 as u16).try_into()
+        })
+        .collect::<Result<Vec<u8>, _>>();
+    match bytes {
+        Ok(bytes) => Ok(String::from_utf8(bytes)?),
+        Err(_) => bail!("Bad string encoding: byte out of range."),
+    }
+}
+
+/// Channel location: server URL and application instance.
+#[derive(Clone)]
+pub struct HttpBase<TW> {
+    /// Server URL without trailing slashes.
+    pub url: String,
+
+    /// Application instance.
+    pub appinst: AppInst<TW>,
+}
+
+impl<TW> HttpBase<TW> {
+    pub fn new(url: &str, appinst: AppInst<TW>) -> Self {
+        Self {
+            url: normalize_url(url),
+            appinst,
+        }
+    }
+}
+
+impl<TW> fmt::Debug for HttpBase<TW>
+where
+    TW: BasicTbitWord,
+    TW::Tbit: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{url: {}, appinst: {:?}}}", self.url, self.appinst)
+    }
+}
+
+impl<TW> fmt::Display for HttpBase<TW>
+where
+    TW: StringTbitWord,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.url, self.appinst)
+    }
+}
+
+impl<TW> Default for HttpBase<TW>
+where
+    TW: BasicTbitWord,
+{
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            appinst: AppInst::default(),
+        }
+    }
+}
+
+impl<TW> PartialEq for HttpBase<TW>
+where
+    TW: BasicTbitWord,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.url == other.url && self.appinst == other.appinst
+    }
+}
+impl<TW> Eq for HttpBase<TW> where TW: BasicTbitWord {}
+
+impl<TW> hash::Hash for HttpBase<TW>
+where
+    TW: BasicTbitWord,
+    TW::Tbit: hash::Hash,
+{
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.url.hash(state);
+        self.appinst.hash(state);
+    }
+}
+
+/// Message URL: channel base and message identifier as the relative part.
+#[derive(Clone)]
+pub struct HttpAddress<TW> {
+    pub base: HttpBase<TW>,
+    pub msgid: MsgId<TW>,
+}
+
+impl<TW> HttpAddress<TW> {
+    pub fn new(url: &str, appinst: AppInst<TW>, msgid: MsgId<TW>) -> Self {
+        Self {
+            base: HttpBase::new(url, appinst),
+            msgid,
+        }
+    }
+
+    /// Address of the message with Tangle address `link` on server `url`.
+    pub fn from_tangle_address(url: &str, link: TangleAddress<TW>) -> Self {
+        Self::new(url, link.appinst, link.msgid)
+    }
+}
+
+impl<TW> HttpAddress<TW>
+where
+    TW: Clone,
+{
+    /// Tangle address of the message, it's the same for all servers.
+    pub fn to_tangle_address(&self) -> TangleAddress<TW> {
+        TangleAddress::new(self.base.appinst.clone(), self.msgid.clone())
+    }
+}
+
+impl<TW> fmt::Debug for HttpAddress<TW>
+where
+    TW: BasicTbitWord,
+    TW::Tbit: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{base: {:?}, msgid: {:?}}}", self.base, self.msgid)
+    }
+}
+
+/// Full URL of the message.
+impl<TW> fmt::Display for HttpAddress<TW>
+where
+    TW: StringTbitWord,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.base, self.msgid)
+    }
+}
+
+impl<TW> Default for HttpAddress<TW>
+where
+    TW: BasicTbitWord,
+{
+    fn default() -> Self {
+        Self {
+            base: HttpBase::default(),
+            msgid: MsgId::default(),
+        }
+    }
+}
+
+impl<TW> PartialEq for HttpAddress<TW>
+where
+    TW: BasicTbitWord,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.base == other.base && self.msgid == other.msgid
+    }
+}
+impl<TW> Eq for HttpAddress<TW> where TW: BasicTbitWord {}
+
+impl<TW> hash::Hash for HttpAddress<TW>
+where
+    TW: BasicTbitWord,
+    TW::Tbit: hash::Hash,
+{
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.base.hash(state);
+        self.msgid.hash(state);
+    }
+}
+
+impl<TW> HasLink for HttpAddress<TW>
+where
+    TW: BasicTbitWord,
+{
+    type Base = HttpBase<TW>;
+    fn base(&self) -> &HttpBase<TW> {
+        &self.base
+    }
+
+    type Rel = MsgId<TW>;
+    fn rel(&self) -> &MsgId<TW> {
+        &self.msgid
+    }
+
+    fn from_base_rel(base: &HttpBase<TW>, rel: &MsgId<TW>) -> Self {
+        Self {
+            base: base.clone(),
+            msgid: rel.clone(),
+        }
+    }
+}
+
+/// Server URL is only the location of the message and is not absorbed, ie. message is bound
+/// to appinst+msgid exactly as `TangleAddress`.
+impl<TW, F> AbsorbExternalFallback<TW, F> for HttpAddress<TW>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    fn sizeof_absorb_external(&self, ctx: &mut sizeof::Context<TW, F>) -> Fallible<()> {
+        ctx.absorb(External(&self.base.appinst.id))?
+            .absorb(External(&self.msgid.id))?;
+        Ok(())
+    }
+    fn wrap_absorb_external<OS: io::OStream<TW>>(&self, ctx: &mut wrap::Context<TW, F, OS>) -> Fallible<()> {
+        ctx.absorb(External(&self.base.appinst.id))?
+            .absorb(External(&self.msgid.id))?;
+        Ok(())
+    }
+    fn unwrap_absorb_external<IS: io::IStream<TW>>(&self, ctx: &mut unwrap::Context<TW, F, IS>) -> Fallible<()> {
+        ctx.absorb(External(&self.base.appinst.id))?
+            .absorb(External(&self.msgid.id))?;
+        Ok(())
+    }
+}
+
+/// Full address is `skip`ped when stored outside of messages, ie. when persisting state.
+///
+/// ```pb3
+/// message HttpAddress {
+///     skip trytes url;
+///     skip tryte appinst[81];
+///     skip tryte msgid[27];
+/// }
+/// ```
+impl<TW, F> SkipFallback<TW, F> for HttpAddress<TW>
+where
+    TW: BasicTbitWord + trinary::TritWord,
+{
+    fn sizeof_skip(&self, ctx: &mut sizeof::Context<TW, F>) -> Fallible<()> {
+        ctx.skip(&trytes_from_str(&self.base.url))?
+            .skip(&self.base.appinst.id)?
+            .skip(&self.msgid.id)?;
+        Ok(())
+    }
+    fn wrap_skip<OS: io::OStream<TW>>(&self, ctx: &mut wrap::Context<TW, F, OS>) -> Fallible<()> {
+        ctx.skip(&trytes_from_str(&self.base.url))?
+            .skip(&self.base.appinst.id)?
+            .skip(&self.msgid.id)?;
+        Ok(())
+    }
+    fn unwrap_skip<IS: io::IStream<TW>>(&mut self, ctx: &mut unwrap::Context<TW, F, IS>) -> Fallible<()> {
+        let mut url = Trytes::default();
+        ctx.skip(&mut url)?
+            .skip(&mut self.base.appinst.id)?
+            .skip(&mut self.msgid.id)?;
+        self.base.url = str_from_trytes(&url)?;
+        Ok(())
+    }
+}
+
+/// Server URL length as 2-byte big-endian integer and URL bytes followed by `TangleAddress` encoding.
+/// URLs longer than `u16::MAX` bytes can't be encoded.
+impl<TW> BinaryLink for HttpAddress<TW>
+where
+    TW: trinary::TritWord,
+{
+    fn encode_link(&self, buf: &mut Vec<u8>) -> Fallible<()> {
+        let url = self.base.url.as_bytes();
+        ensure!(
+            url.len() <= u16::MAX as usize,
+            "URL is too long to be encoded: {} bytes.",
+            url.len()
+        );
+        buf.extend_from_slice(&(url.len() as u16).to_be_bytes());
+        buf.extend_from_slice(url);
+        self.to_tangle_address().encode_link(buf)
+    }
+
+    fn decode_link(bytes: &[u8]) -> Fallible<(Self, usize)> {
+        ensure!(2 <= bytes.len(), "URL length is missing.");
+        let url_size = 2 + u16::from_be_bytes([bytes[0], bytes[1]]) as usize;
+        ensure!(url_size <= bytes.len(), "URL is truncated.");
+        let url = std::str::from_utf8(&bytes[2..url_size])?;
+        let (link, size) = TangleAddress::decode_link(&bytes[url_size..])?;
+        Ok((Self::from_tangle_address(url, link), url_size + size))
+    }
+}
+
+/// Link generator deriving message identifiers with `DefaultTangleLinkGenerator` and
+/// locating messages on the server with URL `url`.
+pub struct HttpLinkGenerator<TW, F> {
+    url: String,
+    inner: DefaultTangleLinkGenerator<TW, F>,
+}
+
+impl<TW, F> Default for HttpLinkGenerator<TW, F>
+where
+    TW: BasicTbitWord,
+{
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            inner: DefaultTangleLinkGenerator::default(),
+        }
+    }
+}
+
+impl<TW, F> HttpLinkGenerator<TW, F>
+where
+    TW: BasicTbitWord,
+{
+    /// Create link generator for messages stored on the server with URL `url`.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: normalize_url(url),
+            inner: DefaultTangleLinkGenerator::default(),
+        }
+    }
+
+    /// Create link generator with the initial message `counter`, see `DefaultTangleLinkGenerator::with_counter`.
+    pub fn with_counter(url: &str, counter: usize) -> Self {
+        Self {
+            url: normalize_url(url),
+            inner: DefaultTangleLinkGenerator::with_counter(counter),
+        }
+    }
+}
+
+impl<TW, F> HttpLinkGenerator<TW, F> {
+    /// Server URL.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Generate links of the channel instance `base`.
+    pub fn reset_base(&mut self, base: HttpBase<TW>) {
+        self.url = base.url;
+        self.inner.reset_appinst(base.appinst);
+    }
+
+    fn with_url(&self, link: TangleAddress<TW>) -> HttpAddress<TW> {
+        HttpAddress {
+            base: HttpBase {
+                url: self.url.clone(),
+                appinst: link.appinst,
+            },
+            msgid: link.msgid,
+        }
+    }
+}
+
+impl<TW, F> HttpLinkGenerator<TW, F>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW> + Default,
+{
+    /// Derive link of a message attached to `msgid` as if it was generated with `counter`.
+    pub fn link_with_counter(&self, msgid: &MsgId<TW>, counter: usize) -> HttpAddress<TW> {
+        self.with_url(self.inner.link_with_counter(msgid, counter))
+    }
+}
+
+impl<TW, F, P> LinkGenerator<TW, HttpAddress<TW>, mss::PublicKey<TW, P>> for HttpLinkGenerator<TW, F>
+where
+    TW: StringTbitWord + SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW> + Default,
+    P: mss::Parameters<TW>,
+{
+    fn link_from(&mut self, mss_pk: &mss::PublicKey<TW, P>) -> HttpAddress<TW> {
+        let link = self.inner.link_from(mss_pk);
+        self.with_url(link)
+    }
+
+    fn header_from(&mut self, arg: &mss::PublicKey<TW, P>, content_type: &str) -> header::Header<TW, HttpAddress<TW>> {
+        header::Header::new_with_type(self.link_from(arg), content_type)
+    }
+}
+
+impl<TW, F> LinkGenerator<TW, HttpAddress<TW>, MsgId<TW>> for HttpLinkGenerator<TW, F>
+where
+    TW: StringTbitWord + SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW> + Default,
+{
+    fn link_from(&mut self, msgid: &MsgId<TW>) -> HttpAddress<TW> {
+        let link = self.inner.link_from(msgid);
+        self.with_url(link)
+    }
+
+    fn header_from(&mut self, arg: &MsgId<TW>, content_type: &str) -> header::Header<TW, HttpAddress<TW>> {
+        header::Header::new_with_type(self.link_from(arg), content_type)
+    }
+}
+
+/// Link generator state is encoded so that the generator can be persisted and
+/// restored later without repeating already generated links.
+///
+/// ```pb3
+/// message HttpLinkGenerator {
+///     skip trytes url;
+///     skip DefaultTangleLinkGenerator inner;
+/// }
+/// ```
+impl<TW, F> SkipFallback<TW, F> for HttpLinkGenerator<TW, F>
+where
+    TW: BasicTbitWord + trinary::TritWord,
+{
+    fn sizeof_skip(&self, ctx: &mut sizeof::Context<TW, F>) -> Fallible<()> {
+        ctx.skip(&trytes_from_str(&self.url))?;
+        self.inner.sizeof_skip(ctx)
+    }
+    fn wrap_skip<OS: io::OStream<TW>>(&self, ctx: &mut wrap::Context<TW, F, OS>) -> Fallible<()> {
+        ctx.skip(&trytes_from_str(&self.url))?;
+        self.inner.wrap_skip(ctx)
+    }
+    fn unwrap_skip<IS: io::IStream<TW>>(&mut self, ctx: &mut unwrap::Context<TW, F, IS>) -> Fallible<()> {
+        let mut url = Trytes::default();
+        ctx.skip(&mut url)?;
+        self.url = str_from_trytes(&url)?;
+        self.inner.unwrap_skip(ctx)
+    }
+}
+
+/// Transport over Streams HTTP API.
+pub mod client;
+pub use client::HttpTransport;
+
+/// Local Streams HTTP server for testing `client` transport offline.
+pub mod mock;
+
+#[cfg(test)]
+#[test]
+fn test_str_trytes() {
+    for s in &["", "http://localhost:8080/streams", "http://ünïcödé"] {
+        let trytes = trytes_from_str::<Trit>(s);
+        assert_eq!(6 * s.len(), trytes.0.size());
+        assert_eq!(*s, str_from_trytes(&trytes).unwrap());
+    }
+    // Byte 270 is out of range.
+    assert!(str_from_trytes(&Trytes::<Trit>(Tbits::cycle_str(6, "9J"))).is_err());
+    assert!(str_from_trytes(&Trytes::<Trit>(Tbits::cycle_str(3, "A"))).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_http_address() {
+    use crate::transport::tangle::{
+        APPINST_SIZE,
+        MSGID_SIZE,
+    };
+
+    let appinst = AppInst {
+        id: NTrytes(Tbits::<Trit>::cycle_str(APPINST_SIZE, "A")),
+    };
+    let msgid = MsgId {
+        id: NTrytes(Tbits::<Trit>::cycle_str(MSGID_SIZE, "M")),
+    };
+    let link = HttpAddress::new("http://localhost/streams//", appinst.clone(), msgid);
+    assert_eq!(
+        format!("http://localhost/streams/{}/{}", "A".repeat(81), "M".repeat(27)),
+        link.to_string()
+    );
+
+    let mut bytes = Vec::new();
+    link.encode_link(&mut bytes).unwrap();
+    let (link2, size) = HttpAddress::<Trit>::decode_link(&bytes).unwrap();
+    assert!(link == link2 && size == bytes.len());
+    assert!(HttpAddress::<Trit>::decode_link(&bytes[..size - 1]).is_err());
+
+    let long = HttpAddress::new(&"x".repeat(u16::MAX as usize + 1), appinst, link.msgid.clone());
+    assert!(long.encode_link(&mut Vec::new()).is_err());
+}
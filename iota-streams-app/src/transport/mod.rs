@@ -1,15 +1,62 @@
 use failure::{
-    bail,
     ensure,
     Fallible,
 };
 use std::{
     collections::HashMap,
+    error,
+    fmt,
+    future::{
+        self,
+        Future,
+    },
     hash,
+    pin::Pin,
 };
 
 use crate::message::TbinaryMessage;
 
+/// Error returned by transports when there are no messages with the requested link.
+///
+/// Other receive errors, eg. network or node failures, don't mean that the link is empty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinkNotFound;
+
+impl fmt::Display for LinkNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Link not found.")
+    }
+}
+
+impl error::Error for LinkNotFound {}
+
+/// Check whether receive error `e` or any of its causes is `LinkNotFound`.
+pub fn is_link_not_found(e: &failure::Error) -> bool {
+    e.iter_chain().any(|cause| cause.downcast_ref::<LinkNotFound>().is_some())
+}
+
+/// Transport-specific information about a received message, fields unknown to a transport are empty.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MessageMetadata {
+    /// Time the message was sent in seconds since Unix epoch, eg. bundle timestamp.
+    pub timestamp: Option<i64>,
+
+    /// Time the message was attached to the Tangle in milliseconds since Unix epoch.
+    pub attachment_timestamp: Option<i64>,
+
+    /// Hash of the bundle carrying the message.
+    pub bundle_hash: Option<String>,
+
+    /// Hashes of transactions carrying the message in the bundle order.
+    pub tx_hashes: Vec<String>,
+
+    /// Node URI, file or other source the message has been received from.
+    pub source: Option<String>,
+}
+
+/// Message received together with its metadata.
+pub type MessageWithMetadata<TW, F, Link> = (TbinaryMessage<TW, F, Link>, MessageMetadata);
+
 /// Network transport abstraction.
 /// Parametrized by the type of message links.
 /// Message link is used to identify/locate a message (eg. like URL for HTTP).
@@ -30,6 +77,8 @@ pub trait Transport<TW, F, Link> /* where Link: HasLink */ {
     type RecvOptions;
 
     /// Receive messages with explicit options.
+    ///
+    /// A link without messages yields either an empty list or `LinkNotFound` error.
     fn recv_messages_with_options(
         &mut self,
         link: &Link,
@@ -47,7 +96,7 @@ pub trait Transport<TW, F, Link> /* where Link: HasLink */ {
             ensure!(msgs.is_empty(), "More than one message found.");
             Ok(msg)
         } else {
-            bail!("Message not found.");
+            Err(LinkNotFound.into())
         }
     }
 
@@ -66,6 +115,221 @@ pub trait Transport<TW, F, Link> /* where Link: HasLink */ {
     {
         self.recv_message_with_options(link, Self::RecvOptions::default())
     }
+
+    /// Receive messages together with their metadata with explicit options.
+    /// Transports which don't keep metadata return empty metadata.
+    fn recv_messages_with_metadata_and_options(
+        &mut self,
+        link: &Link,
+        opt: Self::RecvOptions,
+    ) -> Fallible<Vec<MessageWithMetadata<TW, F, Link>>> {
+        Ok(self
+            .recv_messages_with_options(link, opt)?
+            .into_iter()
+            .map(|msg| (msg, MessageMetadata::default()))
+            .collect())
+    }
+
+    /// Receive messages together with their metadata with default options.
+    fn recv_messages_with_metadata(&mut self, link: &Link) -> Fallible<Vec<MessageWithMetadata<TW, F, Link>>>
+    where
+        Self::RecvOptions: Default,
+    {
+        self.recv_messages_with_metadata_and_options(link, Self::RecvOptions::default())
+    }
+}
+
+/// Future returned by `AsyncTransport` methods.
+pub type TransportFuture<'a, T> = Pin<Box<dyn Future<Output = Fallible<T>> + Send + 'a>>;
+
+/// Asynchronous network transport abstraction.
+/// Same as `Transport` except that messages are sent and received with futures.
+pub trait AsyncTransport<TW, F, Link> {
+    type SendOptions;
+
+    /// Send a message with explicit options.
+    fn send_message_with_options<'a>(
+        &'a mut self,
+        msg: &'a TbinaryMessage<TW, F, Link>,
+        opt: Self::SendOptions,
+    ) -> TransportFuture<'a, ()>;
+
+    /// Send a message with default options.
+    fn send_message<'a>(&'a mut self, msg: &'a TbinaryMessage<TW, F, Link>) -> TransportFuture<'a, ()>
+    where
+        Self::SendOptions: Default,
+    {
+        self.send_message_with_options(msg, Self::SendOptions::default())
+    }
+
+    type RecvOptions;
+
+    /// Receive messages with explicit options.
+    fn recv_messages_with_options<'a>(
+        &'a mut self,
+        link: &'a Link,
+        opt: Self::RecvOptions,
+    ) -> TransportFuture<'a, Vec<TbinaryMessage<TW, F, Link>>>;
+
+    /// Receive a message with explicit options.
+    fn recv_message_with_options<'a>(
+        &'a mut self,
+        link: &'a Link,
+        opt: Self::RecvOptions,
+    ) -> TransportFuture<'a, TbinaryMessage<TW, F, Link>>
+    where
+        TW: Send + 'a,
+        F: Send + 'a,
+        Link: Send,
+    {
+        let msgs = self.recv_messages_with_options(link, opt);
+        Box::pin(async move {
+            let mut msgs = msgs.await?;
+            if let Some(msg) = msgs.pop() {
+                ensure!(msgs.is_empty(), "More than one message found.");
+                Ok(msg)
+            } else {
+                Err(LinkNotFound.into())
+            }
+        })
+    }
+
+    /// Receive messages with default options.
+    fn recv_messages<'a>(&'a mut self, link: &'a Link) -> TransportFuture<'a, Vec<TbinaryMessage<TW, F, Link>>>
+    where
+        Self::RecvOptions: Default,
+    {
+        self.recv_messages_with_options(link, Self::RecvOptions::default())
+    }
+
+    /// Receive a message with default options.
+    fn recv_message<'a>(&'a mut self, link: &'a Link) -> TransportFuture<'a, TbinaryMessage<TW, F, Link>>
+    where
+        Self::RecvOptions: Default,
+        TW: Send + 'a,
+        F: Send + 'a,
+        Link: Send,
+    {
+        self.recv_message_with_options(link, Self::RecvOptions::default())
+    }
+
+    /// Receive messages together with their metadata with explicit options.
+    /// Transports which don't keep metadata return empty metadata.
+    fn recv_messages_with_metadata_and_options<'a>(
+        &'a mut self,
+        link: &'a Link,
+        opt: Self::RecvOptions,
+    ) -> TransportFuture<'a, Vec<MessageWithMetadata<TW, F, Link>>>
+    where
+        TW: Send + 'a,
+        F: Send + 'a,
+        Link: Send + 'a,
+    {
+        let msgs = self.recv_messages_with_options(link, opt);
+        Box::pin(async move {
+            Ok(msgs
+                .await?
+                .into_iter()
+                .map(|msg| (msg, MessageMetadata::default()))
+                .collect())
+        })
+    }
+
+    /// Receive messages together with their metadata with default options.
+    fn recv_messages_with_metadata<'a>(
+        &'a mut self,
+        link: &'a Link,
+    ) -> TransportFuture<'a, Vec<MessageWithMetadata<TW, F, Link>>>
+    where
+        Self::RecvOptions: Default,
+        TW: Send + 'a,
+        F: Send + 'a,
+        Link: Send + 'a,
+    {
+        self.recv_messages_with_metadata_and_options(link, Self::RecvOptions::default())
+    }
+}
+
+/// Asynchronous adapter for a blocking transport.
+/// Returned futures are ready, ie. the blocking transport is called before the future is polled.
+pub struct AsyncAdapter<T>(pub T);
+
+impl<T, TW, F, Link> AsyncTransport<TW, F, Link> for AsyncAdapter<T>
+where
+    T: Transport<TW, F, Link>,
+    TW: Send + 'static,
+    F: Send + 'static,
+    Link: Send,
+{
+    type SendOptions = T::SendOptions;
+
+    fn send_message_with_options<'a>(
+        &'a mut self,
+        msg: &'a TbinaryMessage<TW, F, Link>,
+        opt: Self::SendOptions,
+    ) -> TransportFuture<'a, ()> {
+        Box::pin(future::ready(self.0.send_message_with_options(msg, opt)))
+    }
+
+    type RecvOptions = T::RecvOptions;
+
+    fn recv_messages_with_options<'a>(
+        &'a mut self,
+        link: &'a Link,
+        opt: Self::RecvOptions,
+    ) -> TransportFuture<'a, Vec<TbinaryMessage<TW, F, Link>>> {
+        Box::pin(future::ready(self.0.recv_messages_with_options(link, opt)))
+    }
+
+    fn recv_messages_with_metadata_and_options<'a>(
+        &'a mut self,
+        link: &'a Link,
+        opt: Self::RecvOptions,
+    ) -> TransportFuture<'a, Vec<MessageWithMetadata<TW, F, Link>>>
+    where
+        TW: Send + 'a,
+        F: Send + 'a,
+        Link: Send + 'a,
+    {
+        Box::pin(future::ready(self.0.recv_messages_with_metadata_and_options(link, opt)))
+    }
+}
+
+/// Blocking adapter for an asynchronous transport.
+/// Each call blocks the current thread until the future returned by the asynchronous transport completes,
+/// so it must not be used from within an asynchronous task.
+pub struct SyncAdapter<T>(pub T);
+
+impl<T, TW, F, Link> Transport<TW, F, Link> for SyncAdapter<T>
+where
+    T: AsyncTransport<TW, F, Link>,
+    TW: Send,
+    F: Send,
+    Link: Send,
+{
+    type SendOptions = T::SendOptions;
+
+    fn send_message_with_options(&mut self, msg: &TbinaryMessage<TW, F, Link>, opt: Self::SendOptions) -> Fallible<()> {
+        futures::executor::block_on(self.0.send_message_with_options(msg, opt))
+    }
+
+    type RecvOptions = T::RecvOptions;
+
+    fn recv_messages_with_options(
+        &mut self,
+        link: &Link,
+        opt: Self::RecvOptions,
+    ) -> Fallible<Vec<TbinaryMessage<TW, F, Link>>> {
+        futures::executor::block_on(self.0.recv_messages_with_options(link, opt))
+    }
+
+    fn recv_messages_with_metadata_and_options(
+        &mut self,
+        link: &Link,
+        opt: Self::RecvOptions,
+    ) -> Fallible<Vec<MessageWithMetadata<TW, F, Link>>> {
+        futures::executor::block_on(self.0.recv_messages_with_metadata_and_options(link, opt))
+    }
 }
 
 pub struct BucketTransport<TW, F, Link> {
@@ -104,9 +368,85 @@ where
         if let Some(msgs) = self.bucket.get(link) {
             Ok(msgs.clone())
         } else {
-            bail!("Link not found in the bucket.")
+            Err(LinkNotFound.into())
         }
     }
 }
 
+impl<TW, F, Link> AsyncTransport<TW, F, Link> for BucketTransport<TW, F, Link>
+where
+    TW: Clone + Send,
+    F: Send,
+    Link: Eq + hash::Hash + Clone + Send,
+{
+    type SendOptions = ();
+
+    fn send_message_with_options<'a>(
+        &'a mut self,
+        msg: &'a TbinaryMessage<TW, F, Link>,
+        opt: (),
+    ) -> TransportFuture<'a, ()> {
+        Box::pin(future::ready(Transport::send_message_with_options(self, msg, opt)))
+    }
+
+    type RecvOptions = ();
+
+    fn recv_messages_with_options<'a>(
+        &'a mut self,
+        link: &'a Link,
+        opt: (),
+    ) -> TransportFuture<'a, Vec<TbinaryMessage<TW, F, Link>>> {
+        Box::pin(future::ready(Transport::recv_messages_with_options(self, link, opt)))
+    }
+}
+
+/// Filesystem-backed transport.
+pub mod file;
+pub use file::FileTransport;
+
+/// Transport retrying failed requests.
+pub mod retry;
+pub use retry::{
+    RetryPolicy,
+    RetryTransport,
+};
+
+/// Transport caching received messages.
+pub mod cache;
+pub use cache::CachingTransport;
+
+/// Transport publishing messages to several backends.
+pub mod multi;
+pub use multi::{
+    MultiRecvOptions,
+    MultiTransport,
+    WithOptions,
+};
+
 pub mod tangle;
+
+/// HTTP links and transport.
+pub mod http;
+
+#[cfg(test)]
+#[test]
+fn test_link_not_found() {
+    use iota_streams_core::{
+        sponge::prp::troika::Troika,
+        tbits::{
+            trinary::Trit,
+            Tbits,
+        },
+    };
+
+    let mut transport = BucketTransport::<Trit, Troika, String>::new();
+    let link = "LINK".to_string();
+    let e = Transport::recv_messages(&mut transport, &link).err().unwrap();
+    assert!(is_link_not_found(&e));
+    assert!(is_link_not_found(&e.context("Sync failed.").into()));
+    assert!(is_link_not_found(&Transport::recv_message(&mut transport, &link).err().unwrap()));
+    assert!(!is_link_not_found(&failure::err_msg("Node failed.")));
+
+    Transport::send_message(&mut transport, &TbinaryMessage::new(link.clone(), Tbits::zero(6))).unwrap();
+    assert_eq!(1, Transport::recv_messages(&mut transport, &link).unwrap().len());
+}
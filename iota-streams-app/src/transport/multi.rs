@@ -0,0 +1,306 @@
+//! Transport publishing messages to several backends.
+
+use failure::{
+    bail,
+    ensure,
+    Fallible,
+};
+
+use iota_streams_core::tbits::word::BasicTbitWord;
+
+use crate::{
+    message::TbinaryMessage,
+    transport::{
+        is_link_not_found,
+        LinkNotFound,
+        MessageWithMetadata,
+        Transport,
+    },
+};
+
+/// Transport with fixed send and receive options, used to put transports with different options
+/// into `MultiTransport`.
+pub struct WithOptions<T, SendOptions, RecvOptions> {
+    inner: T,
+    send_opt: SendOptions,
+    recv_opt: RecvOptions,
+}
+
+impl<T, SendOptions, RecvOptions> WithOptions<T, SendOptions, RecvOptions> {
+    pub fn new(inner: T, send_opt: SendOptions, recv_opt: RecvOptions) -> Self {
+        Self {
+            inner,
+            send_opt,
+            recv_opt,
+        }
+    }
+
+    /// Inner transport.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Mutable inner transport.
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T, TW, F, Link> Transport<TW, F, Link> for WithOptions<T, T::SendOptions, T::RecvOptions>
+where
+    T: Transport<TW, F, Link>,
+    T::SendOptions: Clone,
+    T::RecvOptions: Clone,
+{
+    type SendOptions = ();
+
+    fn send_message_with_options(&mut self, msg: &TbinaryMessage<TW, F, Link>, _opt: ()) -> Fallible<()> {
+        self.inner.send_message_with_options(msg, self.send_opt.clone())
+    }
+
+    type RecvOptions = ();
+
+    fn recv_messages_with_options(&mut self, link: &Link, _opt: ()) -> Fallible<Vec<TbinaryMessage<TW, F, Link>>> {
+        self.inner.recv_messages_with_options(link, self.recv_opt.clone())
+    }
+
+    fn recv_messages_with_metadata_and_options(
+        &mut self,
+        link: &Link,
+        _opt: (),
+    ) -> Fallible<Vec<MessageWithMetadata<TW, F, Link>>> {
+        self.inner.recv_messages_with_metadata_and_options(link, self.recv_opt.clone())
+    }
+}
+
+/// Backend of `MultiTransport`.
+pub type BoxedTransport<'a, TW, F, Link> = Box<dyn Transport<TW, F, Link, SendOptions = (), RecvOptions = ()> + 'a>;
+
+/// Where `MultiTransport` receives messages from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MultiRecvOptions {
+    /// Messages from the first backend, in the order of adding, which has any message with the link.
+    First,
+
+    /// Messages from all backends.
+    All,
+}
+
+impl Default for MultiRecvOptions {
+    fn default() -> Self {
+        MultiRecvOptions::First
+    }
+}
+
+/// Transport sending each message to all backends and receiving messages from the first or from all backends.
+///
+/// Sending succeeds if at least `quorum` backends accept the message. Received messages with
+/// identical bodies are returned once, eg. when the same message is found in several backends.
+/// If no messages are found, receiving fails with `LinkNotFound` unless some backend has failed otherwise.
+pub struct MultiTransport<'a, TW, F, Link> {
+    backends: Vec<BoxedTransport<'a, TW, F, Link>>,
+    quorum: usize,
+}
+
+impl<'a, TW, F, Link> MultiTransport<'a, TW, F, Link> {
+    /// Transport without backends, sending succeeds if at least `quorum` backends accept the message.
+    pub fn new(quorum: usize) -> Self {
+        Self {
+            backends: Vec::new(),
+            quorum,
+        }
+    }
+
+    /// Add backend with `()` send and receive options.
+    pub fn add<T>(&mut self, transport: T)
+    where
+        T: Transport<TW, F, Link, SendOptions = (), RecvOptions = ()> + 'a,
+    {
+        self.backends.push(Box::new(transport));
+    }
+
+    /// Add backend with fixed send and receive options.
+    pub fn add_with_options<T>(&mut self, transport: T, send_opt: T::SendOptions, recv_opt: T::RecvOptions)
+    where
+        T: Transport<TW, F, Link> + 'a,
+        T::SendOptions: Clone + 'a,
+        T::RecvOptions: Clone + 'a,
+    {
+        self.add(WithOptions::new(transport, send_opt, recv_opt));
+    }
+
+    /// Number of backends.
+    pub fn len(&self) -> usize {
+        self.backends.len()
+    }
+
+    /// Check whether there are no backends.
+    pub fn is_empty(&self) -> bool {
+        self.backends.is_empty()
+    }
+
+    /// Minimal number of backends which must accept a message.
+    pub fn quorum(&self) -> usize {
+        self.quorum
+    }
+
+    /// Change quorum.
+    pub fn set_quorum(&mut self, quorum: usize) {
+        self.quorum = quorum;
+    }
+}
+
+/// Append messages from `msgs` with bodies not in `unique`.
+fn append_unique<TW, F, Link>(
+    unique: &mut Vec<MessageWithMetadata<TW, F, Link>>,
+    msgs: Vec<MessageWithMetadata<TW, F, Link>>,
+) where
+    TW: BasicTbitWord,
+{
+    for msg in msgs {
+        if !unique.iter().any(|(m, _)| m.body == msg.0.body) {
+            unique.push(msg);
+        }
+    }
+}
+
+impl<'a, TW, F, Link> Transport<TW, F, Link> for MultiTransport<'a, TW, F, Link>
+where
+    TW: BasicTbitWord,
+{
+    type SendOptions = ();
+
+    /// Send message to all backends.
+    fn send_message_with_options(&mut self, msg: &TbinaryMessage<TW, F, Link>, _opt: ()) -> Fallible<()> {
+        ensure!(
+            self.quorum <= self.backends.len(),
+            "Quorum {} exceeds the number of backends {}.",
+            self.quorum,
+            self.backends.len()
+        );
+        let mut accepted = 0;
+        let mut errors = Vec::new();
+        for backend in self.backends.iter_mut() {
+            match backend.send_message(msg) {
+                Ok(()) => accepted += 1,
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+        ensure!(
+            self.quorum <= accepted,
+            "Message accepted by {} backends, quorum is {}: {}",
+            accepted,
+            self.quorum,
+            errors.join(" ")
+        );
+        Ok(())
+    }
+
+    type RecvOptions = MultiRecvOptions;
+
+    fn recv_messages_with_options(
+        &mut self,
+        link: &Link,
+        opt: MultiRecvOptions,
+    ) -> Fallible<Vec<TbinaryMessage<TW, F, Link>>> {
+        Ok(self
+            .recv_messages_with_metadata_and_options(link, opt)?
+            .into_iter()
+            .map(|(msg, _)| msg)
+            .collect())
+    }
+
+    /// Receive messages with metadata of the backend the message has been first found in.
+    fn recv_messages_with_metadata_and_options(
+        &mut self,
+        link: &Link,
+        opt: MultiRecvOptions,
+    ) -> Fallible<Vec<MessageWithMetadata<TW, F, Link>>> {
+        let mut msgs = Vec::new();
+        let mut errors = Vec::new();
+        let mut not_found = 0;
+        for backend in self.backends.iter_mut() {
+            match backend.recv_messages_with_metadata(link) {
+                Ok(found) => {
+                    append_unique(&mut msgs, found);
+                    if opt == MultiRecvOptions::First && !msgs.is_empty() {
+                        break;
+                    }
+                }
+                Err(e) if is_link_not_found(&e) => not_found += 1,
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+        if msgs.is_empty() {
+            // The link is not found only if no backend has failed.
+            if !errors.is_empty() {
+                bail!("Receive failed: {}", errors.join(" "));
+            }
+            if 0 < not_found {
+                return Err(LinkNotFound.into());
+            }
+        }
+        Ok(msgs)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_multi_transport() {
+    use super::BucketTransport;
+    use iota_streams_core::{
+        sponge::prp::troika::Troika,
+        tbits::{
+            trinary::Trit,
+            Tbits,
+        },
+    };
+
+    struct DeadTransport;
+
+    impl Transport<Trit, Troika, String> for DeadTransport {
+        type SendOptions = ();
+
+        fn send_message_with_options(&mut self, _msg: &TbinaryMessage<Trit, Troika, String>, _opt: ()) -> Fallible<()> {
+            bail!("Transport is dead.")
+        }
+
+        type RecvOptions = ();
+
+        fn recv_messages_with_options(
+            &mut self,
+            _link: &String,
+            _opt: (),
+        ) -> Fallible<Vec<TbinaryMessage<Trit, Troika, String>>> {
+            bail!("Transport is dead.")
+        }
+    }
+
+    let link = "LINK".to_string();
+    let m1 = TbinaryMessage::new(link.clone(), Tbits::cycle_str(6, "A"));
+    let m2 = TbinaryMessage::new(link.clone(), Tbits::cycle_str(6, "B"));
+
+    let mut bucket = BucketTransport::<Trit, Troika, String>::new();
+    Transport::send_message(&mut bucket, &m2).unwrap();
+    let mut transport = MultiTransport::new(2);
+    transport.add(BucketTransport::new());
+    transport.add(bucket);
+    assert!(is_link_not_found(&transport.recv_messages(&"NONE".to_string()).err().unwrap()));
+
+    // Both backends get `m1`, only the second one has `m2`.
+    transport.send_message(&m1).unwrap();
+    let msgs = transport.recv_messages(&link).unwrap();
+    assert!(msgs.len() == 1 && msgs[0].body == m1.body);
+    let msgs = transport.recv_messages_with_options(&link, MultiRecvOptions::All).unwrap();
+    assert!(msgs.len() == 2 && msgs[0].body == m1.body && msgs[1].body == m2.body);
+
+    // With a failed backend missing link is an error, messages found elsewhere are still received.
+    transport.add(DeadTransport);
+    assert!(!is_link_not_found(&transport.recv_messages(&"NONE".to_string()).err().unwrap()));
+    assert_eq!(2, transport.recv_messages_with_options(&link, MultiRecvOptions::All).unwrap().len());
+    transport.send_message(&m1).unwrap();
+    transport.set_quorum(3);
+    assert!(transport.send_message(&m1).is_err());
+    transport.set_quorum(4);
+    assert!(transport.send_message(&m1).is_err());
+}
@@ -0,0 +1,258 @@
+//! Transport retrying failed requests.
+
+use failure::Fallible;
+use std::{
+    thread,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use crate::{
+    message::TbinaryMessage,
+    transport::{
+        LinkNotFound,
+        MessageWithMetadata,
+        Transport,
+    },
+};
+
+/// Retry policy: a request is repeated with exponentially growing delay until it succeeds,
+/// `max_attempts` requests have been made or `deadline` since the first request has passed.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    /// Maximal number of requests, including the first one.
+    pub max_attempts: usize,
+
+    /// Delay before the second request, it is doubled after each retry.
+    pub initial_backoff: Duration,
+
+    /// Maximal delay between requests.
+    pub max_backoff: Duration,
+
+    /// Time since the first request after which no more retries are made.
+    /// Requests in progress are not interrupted, so the total time may exceed the deadline.
+    pub deadline: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+            deadline: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Repeat `request` until it succeeds. Result is successful if `done` returns true,
+    /// otherwise the last unsuccessful result is treated as `LinkNotFound` error.
+    fn run<R>(&self, mut request: impl FnMut() -> Fallible<R>, done: impl Fn(&R) -> bool) -> Fallible<R> {
+        let start = Instant::now();
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            let result = request();
+            if result.as_ref().map_or(false, &done) {
+                return result;
+            }
+            let elapsed = start.elapsed();
+            if self.max_attempts <= attempt || self.deadline <= elapsed + backoff {
+                return match result {
+                    Err(e) => Err(e.context(format!("Request failed after {} attempts.", attempt)).into()),
+                    Ok(_) => Err(LinkNotFound.into()),
+                };
+            }
+            thread::sleep(backoff);
+            backoff = std::cmp::min(backoff * 2, self.max_backoff);
+            attempt += 1;
+        }
+    }
+}
+
+/// Transport retrying requests to the inner transport according to `RetryPolicy`.
+///
+/// Failed sends are retried. Failed receives and receives with no messages are retried, which is
+/// useful for eventually-consistent nodes where a message may become available with a delay.
+/// If no messages are found after all retries, receiving fails with `LinkNotFound`.
+///
+/// Note, each probe of a link that has not been published yet, eg. in `Subscriber::sync_state`,
+/// takes all the retries, so a policy with a short deadline is preferable for synchronisation.
+pub struct RetryTransport<T> {
+    inner: T,
+    policy: RetryPolicy,
+}
+
+impl<T> RetryTransport<T> {
+    pub fn new(inner: T, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    /// Retry policy.
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Change retry policy.
+    pub fn set_policy(&mut self, policy: RetryPolicy) {
+        self.policy = policy;
+    }
+
+    /// Inner transport.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Mutable inner transport.
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Unwrap the inner transport.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T, TW, F, Link> Transport<TW, F, Link> for RetryTransport<T>
+where
+    T: Transport<TW, F, Link>,
+    T::SendOptions: Clone,
+    T::RecvOptions: Clone,
+{
+    type SendOptions = T::SendOptions;
+
+    fn send_message_with_options(&mut self, msg: &TbinaryMessage<TW, F, Link>, opt: Self::SendOptions) -> Fallible<()> {
+        let inner = &mut self.inner;
+        self.policy.run(|| inner.send_message_with_options(msg, opt.clone()), |_| true)
+    }
+
+    type RecvOptions = T::RecvOptions;
+
+    fn recv_messages_with_options(
+        &mut self,
+        link: &Link,
+        opt: Self::RecvOptions,
+    ) -> Fallible<Vec<TbinaryMessage<TW, F, Link>>> {
+        let inner = &mut self.inner;
+        self.policy.run(
+            || inner.recv_messages_with_options(link, opt.clone()),
+            |msgs| !msgs.is_empty(),
+        )
+    }
+
+    fn recv_messages_with_metadata_and_options(
+        &mut self,
+        link: &Link,
+        opt: Self::RecvOptions,
+    ) -> Fallible<Vec<MessageWithMetadata<TW, F, Link>>> {
+        let inner = &mut self.inner;
+        self.policy.run(
+            || inner.recv_messages_with_metadata_and_options(link, opt.clone()),
+            |msgs| !msgs.is_empty(),
+        )
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_retry_policy() {
+    use super::is_link_not_found;
+    use failure::bail;
+
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(2),
+        deadline: Duration::from_secs(10),
+    };
+
+    // Succeeds on the last attempt.
+    let mut attempts = 0;
+    let result = policy.run(
+        || {
+            attempts += 1;
+            if attempts < 3 {
+                bail!("Node is not synced.")
+            }
+            Ok(attempts)
+        },
+        |_| true,
+    );
+    assert_eq!(3, result.unwrap());
+
+    // Empty result after all attempts is `LinkNotFound`.
+    let mut attempts = 0;
+    let result = policy.run(
+        || {
+            attempts += 1;
+            Ok(Vec::<u8>::new())
+        },
+        |v| !v.is_empty(),
+    );
+    assert_eq!(3, attempts);
+    assert!(result.err().map_or(false, |e| is_link_not_found(&e)));
+
+    // The last error is returned with context.
+    let mut attempts = 0;
+    let result: Fallible<()> = policy.run(
+        || {
+            attempts += 1;
+            bail!("Attempt {} failed.", attempts)
+        },
+        |_| true,
+    );
+    let e = result.unwrap_err();
+    assert_eq!("Request failed after 3 attempts.", e.to_string());
+    assert_eq!("Attempt 3 failed.", e.iter_chain().last().unwrap().to_string());
+
+    // No retries are made after the deadline.
+    let policy = RetryPolicy {
+        deadline: Duration::from_millis(1),
+        ..policy
+    };
+    let mut attempts = 0;
+    assert!(policy
+        .run(
+            || {
+                attempts += 1;
+                Ok(())
+            },
+            |_| false,
+        )
+        .is_err());
+    assert_eq!(1, attempts);
+}
+
+#[cfg(test)]
+#[test]
+fn test_retry_transport() {
+    use super::{
+        is_link_not_found,
+        BucketTransport,
+    };
+    use iota_streams_core::{
+        sponge::prp::troika::Troika,
+        tbits::{
+            trinary::Trit,
+            Tbits,
+        },
+    };
+
+    let policy = RetryPolicy {
+        max_attempts: 2,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(1),
+        deadline: Duration::from_secs(10),
+    };
+    let mut transport = RetryTransport::new(BucketTransport::<Trit, Troika, String>::new(), policy);
+    let link = "LINK".to_string();
+    let e = Transport::recv_messages(&mut transport, &link).err().unwrap();
+    assert!(is_link_not_found(&e));
+
+    Transport::send_message(&mut transport, &TbinaryMessage::new(link.clone(), Tbits::zero(6))).unwrap();
+    assert_eq!(1, Transport::recv_messages(&mut transport, &link).unwrap().len());
+}
@@ -1,12 +1,16 @@
 use chrono::Utc;
 use failure::{
     bail,
+    ensure,
     Fallible,
 };
+use futures::channel::oneshot;
 use std::{
     convert::TryInto,
     str::FromStr,
     string::ToString,
+    sync::mpsc,
+    thread,
 };
 
 use iota_constants::HASH_TRINARY_SIZE as HASH_LENGTH;
@@ -108,13 +112,15 @@ where
     }
 }
 
+/// Size of message fragment carried by a transaction, 2187 trytes.
+pub const MSG_FRAGMENT_SIZE: usize = 6561;
+
 fn make_txs<TW>(address: &Tbits<TW>, tag: &Tbits<TW>, msg: &Tbits<TW>, timestamp: i64) -> Vec<iota_model::Transaction>
 where
     TW: StringTbitWord,
 {
     debug_assert_eq!(243, address.size());
     debug_assert_eq!(81, tag.size());
-    let msg_part_size = 6561;
 
     let mut txs = Vec::new();
     let mut m = msg.slice();
@@ -122,10 +128,10 @@ where
         txs.push(make_tx(
             address,
             tag,
-            &pad_trits(msg_part_size, m.take_min(msg_part_size)),
+            &pad_trits(MSG_FRAGMENT_SIZE, m.take_min(MSG_FRAGMENT_SIZE)),
             timestamp,
         ));
-        m = m.drop_min(msg_part_size);
+        m = m.drop_min(MSG_FRAGMENT_SIZE);
     }
     txs
 }
@@ -176,23 +182,35 @@ fn calc_bundle_hash(bundle: &iota_model::Bundle) -> Fallible<String> {
     hash.trytes()
 }
 
+/// Check bundle structure and hash: transactions must be complete with consecutive indices,
+/// have the same address and tag, carry no value and be bound by the bundle hash.
+///
 /// This functions is missing from iota-lib-rs for some reason.
-fn check_bundle_hash(bundle: &iota_model::Bundle) -> Fallible<()> {
-    failure::ensure!(!bundle.is_empty());
+fn check_bundle(bundle: &iota_model::Bundle) -> Fallible<()> {
+    ensure!(!bundle.is_empty(), "Bundle is empty.");
     let hash = calc_bundle_hash(bundle)?;
-    let mut current_index = 0usize;
     let last_index = bundle.len() - 1;
-    for tx in bundle.iter() {
-        failure::ensure!(tx.current_index == current_index);
-        failure::ensure!(tx.last_index == last_index);
-        failure::ensure!(tx.bundle == hash);
-        current_index += 1;
+    for (current_index, tx) in bundle.iter().enumerate() {
+        ensure!(
+            tx.current_index == current_index && tx.last_index == last_index,
+            "Bundle is incomplete or has bad transaction indices."
+        );
+        ensure!(
+            tx.address == bundle[0].address && tx.tag == bundle[0].tag,
+            "Bundle transactions have different addresses or tags."
+        );
+        ensure!(tx.value == 0, "Bundle transaction carries value.");
+        ensure!(tx.bundle == hash, "Bad bundle hash.");
     }
     Ok(())
 }
 
 /// Reconstruct valid bundles from trytes (returned by client's `get_trytes` method)
-/// taking into account `addtess`, `tag` and `bundle` fields.
+/// taking into account `addtess`, `tag` and `bundle` fields. Malformed and incomplete
+/// bundles are dropped, see `check_bundle`.
+///
+/// Bundles are ordered by timestamp, earliest first. As anyone can attach a bundle to
+/// any address and tag, the earliest bundle is not necessarily a valid Streams message.
 pub fn bundles_from_trytes(trytes: &Vec<iota_conversion::Trytes>) -> Vec<iota_model::Bundle> {
     let mut txs = trytes
         .into_iter()
@@ -228,17 +246,19 @@ pub fn bundles_from_trytes(trytes: &Vec<iota_conversion::Trytes>) -> Vec<iota_mo
         }
     }
 
-    bundles
+    let mut bundles = bundles
         .into_iter()
         .filter_map(|txs| {
             let bundle = iota_model::Bundle::new(txs);
-            if check_bundle_hash(&bundle).is_ok() {
+            if check_bundle(&bundle).is_ok() {
                 Some(bundle)
             } else {
                 None
             }
         })
-        .collect()
+        .collect::<Vec<iota_model::Bundle>>();
+    bundles.sort_by(|x, y| x[0].timestamp.cmp(&y[0].timestamp).then(x[0].bundle.cmp(&y[0].bundle)));
+    bundles
 }
 
 /// As Streams Message are packed into a bundle, and different bundles can have the same hash
@@ -265,9 +285,59 @@ where
     bundle
 }
 
-/// Reconstruct STREAMS Message from bundle. The input bundle is not checked (for validity of
-/// the hash, consistency of indices, etc.). Checked bundles are returned by `bundles_from_trytes`.
-pub fn msg_from_bundle<TW, F>(bundle: &iota_model::Bundle) -> TbinaryMessage<TW, F, TangleAddress<TW>>
+/// Pack STREAMS Message into a bundle attached to the Tangle approving `trunk` and `branch`
+/// transactions and do PoW locally, see `pow::attach_bundle`. This doesn't require a node,
+/// so messages can be prepared offline and later published with client's `store_and_broadcast`.
+pub fn attach_msg<TW, F>(
+    msg: &TbinaryMessage<TW, F, TangleAddress<TW>>,
+    timestamp: i64,
+    trunk: &str,
+    branch: &str,
+    opt: &pow::PowOptions,
+) -> Fallible<Vec<iota_conversion::Trytes>>
+where
+    TW: StringTbitWord,
+{
+    let mut bundle = msg_to_bundle(msg, timestamp);
+    pow::attach_bundle(&mut bundle, trunk, branch, opt)?;
+    Ok(bundle_to_trytes(&bundle))
+}
+
+/// Pack message fragments into a bundle, one transaction per fragment. Fragments are usually
+/// written by `io::ChunkedOStream` of `MSG_FRAGMENT_SIZE`, see `PreparedMessage::wrap_stream`,
+/// so that the message body is never copied into a single buffer.
+pub fn fragments_to_bundle<TW>(
+    link: &TangleAddress<TW>,
+    fragments: &[Tbits<TW>],
+    timestamp: i64,
+) -> Fallible<iota_model::Bundle>
+where
+    TW: StringTbitWord,
+{
+    let mut txs = Vec::with_capacity(fragments.len());
+    for fragment in fragments {
+        ensure!(
+            fragment.size() <= MSG_FRAGMENT_SIZE,
+            "Message fragment is too long: {} trits.",
+            fragment.size()
+        );
+        txs.push(make_tx(
+            link.appinst.tbits(),
+            link.msgid.tbits(),
+            &pad_trits(MSG_FRAGMENT_SIZE, fragment.slice()),
+            timestamp,
+        ));
+    }
+    let mut bundle = iota_model::Bundle::new(txs);
+    bundle.reset_indexes();
+    bundle.finalize()?;
+    Ok(bundle)
+}
+
+/// Reconstruct message link and fragments from bundle, one fragment per transaction.
+/// Fragments can be unwrapped with `io::ChunkedIStream`, see `PreparsedMessage::parse_header`.
+/// The input bundle is not checked, the same as in `msg_from_bundle`.
+pub fn fragments_from_bundle<TW>(bundle: &iota_model::Bundle) -> (TangleAddress<TW>, Vec<Tbits<TW>>)
 where
     TW: StringTbitWord,
 {
@@ -278,11 +348,33 @@ where
     let msgid = MsgId {
         id: NTrytes(Tbits::<TW>::from_str(&tx.tag).unwrap()),
     };
+    let fragments = bundle
+        .iter()
+        .map(|tx| Tbits::<TW>::from_str(&tx.signature_fragments).unwrap())
+        .collect();
+    (TangleAddress::<TW> { appinst, msgid }, fragments)
+}
+
+/// Reconstruct STREAMS Message from bundle. The input bundle is not checked (for validity of
+/// the hash, consistency of indices, etc.). Checked bundles are returned by `bundles_from_trytes`.
+pub fn msg_from_bundle<TW, F>(bundle: &iota_model::Bundle) -> TbinaryMessage<TW, F, TangleAddress<TW>>
+where
+    TW: StringTbitWord,
+{
+    let (link, fragments) = fragments_from_bundle(bundle);
     let mut body = Tbits::<TW>::zero(0);
-    for tx in bundle.iter() {
-        body += &Tbits::<TW>::from_str(&tx.signature_fragments).unwrap();
+    for fragment in &fragments {
+        body += fragment;
     }
-    TbinaryMessage::new(TangleAddress::<TW> { appinst, msgid }, body)
+    TbinaryMessage::new(link, body)
+}
+
+/// Reconstruct STREAMS Message together with the bundle timestamp.
+pub fn tangle_msg_from_bundle<TW, F>(bundle: &iota_model::Bundle) -> TangleMessage<TW, F>
+where
+    TW: StringTbitWord,
+{
+    TangleMessage::with_timestamp(msg_from_bundle(bundle), bundle[0].timestamp)
 }
 
 #[cfg(test)]
@@ -360,6 +452,68 @@ fn test_bundle_from_to_trytes() {
     bundle_from_to_trytes::<Trit, Troika>();
 }
 
+#[cfg(test)]
+fn bundle_validation<TW, F>()
+where
+    TW: StringTbitWord,
+{
+    let link = TangleAddress::<TW>::new(
+        AppInst {
+            id: NTrytes(Tbits::<TW>::cycle_str(APPINST_SIZE, "A")),
+        },
+        MsgId {
+            id: NTrytes(Tbits::<TW>::cycle_str(MSGID_SIZE, "M")),
+        },
+    );
+    // Two messages attached to the same link.
+    let m1 = TbinaryMessage::<TW, F, TangleAddress<TW>>::new(link.clone(), Tbits::<TW>::cycle_str(6561, "D"));
+    let m2 = TbinaryMessage::<TW, F, TangleAddress<TW>>::new(
+        link.clone(),
+        &Tbits::<TW>::cycle_str(6561, "E") + &Tbits::<TW>::cycle_str(6561, "F"),
+    );
+
+    // Bundles are ordered by timestamp.
+    {
+        let mut trytes = bundle_to_trytes(&msg_to_bundle(&m1, 2));
+        trytes.extend(bundle_to_trytes(&msg_to_bundle(&m2, 1)));
+        let bundles = bundles_from_trytes(&trytes);
+        assert_eq!(bundles.len(), 2);
+        let n = tangle_msg_from_bundle::<TW, F>(&bundles[0]);
+        assert_eq!(n.timestamp, 1);
+        assert_eq!(n.tbinary_message.body, m2.body);
+        assert_eq!(msg_from_bundle::<TW, F>(&bundles[1]).body, m1.body);
+    }
+
+    // Incomplete bundle is dropped.
+    {
+        let mut trytes = bundle_to_trytes(&msg_to_bundle(&m1, 0));
+        trytes.push(bundle_to_trytes(&msg_to_bundle(&m2, 0))[1].clone());
+        let bundles = bundles_from_trytes(&trytes);
+        assert_eq!(bundles.len(), 1);
+        assert_eq!(msg_from_bundle::<TW, F>(&bundles[0]).body, m1.body);
+    }
+
+    // Bundle carrying value is dropped even though its hash is valid.
+    {
+        let mut bundle = msg_to_bundle(&m2, 0);
+        bundle[0].value = 1;
+        bundle[1].value = -1;
+        assert!(bundle.finalize().is_ok());
+        assert!(calc_bundle_hash(&bundle).map_or(false, |hash| hash == bundle[0].bundle));
+        assert!(bundles_from_trytes(&bundle_to_trytes(&bundle)).is_empty());
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_bundle_validation() {
+    use iota_streams_core::{
+        sponge::prp::troika::Troika,
+        tbits::trinary::Trit,
+    };
+    bundle_validation::<Trit, Troika>();
+}
+
 /// Stripped version of `iota_client::options::SendTrytesOptions<'a>` due to lifetime parameter.
 #[derive(Clone, Copy)]
 pub struct SendTrytesOptions {
@@ -386,59 +540,213 @@ where
 {
     type SendOptions = SendTrytesOptions;
 
-    /// Send a Streams message over the Tangle with the current timestamp and default SendTrytesOptions.
+    /// Send a Streams message over the Tangle with the current timestamp.
+    ///
+    /// With `local_pow` the bundle is attached with `pow::attach_bundle` using `threads` threads,
+    /// otherwise PoW is done by the node.
     fn send_message_with_options(
         &mut self,
         msg: &TbinaryMessage<TW, F, TangleAddress<TW>>,
         opt: Self::SendOptions,
     ) -> Fallible<()> {
-        let opt = iota_client::options::SendTrytesOptions {
-            depth: opt.depth,
-            min_weight_magnitude: opt.min_weight_magnitude,
-            local_pow: opt.local_pow,
-            threads: opt.threads,
-            reference: None,
-        };
         let timestamp = Utc::now().timestamp();
-        let bundle = msg_to_bundle(msg, timestamp);
-        let trytes = bundle_to_trytes(&bundle);
-        // Ignore PoWed transactions.
-        let _txs = self.send_trytes(&trytes, opt)?;
+        if opt.local_pow {
+            let to_approve = self.get_transactions_to_approve(iota_client::options::GetTransactionsToApproveOptions {
+                depth: opt.depth,
+                reference: None,
+            })?;
+            let (trunk, branch) = match (to_approve.trunk_transaction(), to_approve.branch_transaction()) {
+                (Some(trunk), Some(branch)) => (trunk, branch),
+                _ => bail!("Get transactions to approve contains no transactions."),
+            };
+            let pow_opt = pow::PowOptions {
+                min_weight_magnitude: opt.min_weight_magnitude,
+                threads: opt.threads,
+            };
+            let trytes = attach_msg(msg, timestamp, trunk, branch, &pow_opt)?;
+            self.store_and_broadcast(&trytes)?;
+        } else {
+            let opt = iota_client::options::SendTrytesOptions {
+                depth: opt.depth,
+                min_weight_magnitude: opt.min_weight_magnitude,
+                local_pow: false,
+                threads: opt.threads,
+                reference: None,
+            };
+            let trytes = bundle_to_trytes(&msg_to_bundle(msg, timestamp));
+            // Ignore PoWed transactions.
+            let _txs = self.send_trytes(&trytes, opt)?;
+        }
         Ok(())
     }
 
     type RecvOptions = ();
 
-    /// Receive a message.
+    /// Receive messages from valid bundles, earliest first.
     fn recv_messages_with_options(
         &mut self,
         link: &TangleAddress<TW>,
         _opt: Self::RecvOptions,
     ) -> Fallible<Vec<TbinaryMessage<TW, F, TangleAddress<TW>>>> {
-        let find_opt = iota_client::options::FindTransactionsOptions {
-            bundles: Vec::new(),
-            addresses: vec![link.appinst.to_string()],
-            tags: vec![link.msgid.to_string()],
-            approvees: Vec::new(),
-        };
+        Ok(recv_bundles(self, link)?.iter().map(|bundle| msg_from_bundle(bundle)).collect())
+    }
 
-        let find_resp = self.find_transactions(find_opt)?;
-        if let Some(e) = find_resp.error() {
-            bail!("Find transactions failed with: {}.", e)
-        } else if let Some(hashes) = find_resp.take_hashes() {
-            let get_resp = self.get_trytes(&hashes)?;
-            if let Some(e) = get_resp.error() {
-                bail!("Get trytes failed with: {}.", e)
-            } else if let Some(trytes) = get_resp.take_trytes() {
-                return Ok(bundles_from_trytes(&trytes)
-                    .into_iter()
-                    .map(|bundle| msg_from_bundle(&bundle))
-                    .collect());
-            } else {
-                bail!("Get trytes contains no trytes.")
-            }
+    /// Receive messages from valid bundles together with bundle timestamps and hashes, earliest first.
+    fn recv_messages_with_metadata_and_options(
+        &mut self,
+        link: &TangleAddress<TW>,
+        _opt: Self::RecvOptions,
+    ) -> Fallible<Vec<MessageWithMetadata<TW, F, TangleAddress<TW>>>> {
+        Ok(recv_bundles(self, link)?
+            .iter()
+            .map(|bundle| (msg_from_bundle(bundle), metadata_from_bundle(bundle, self.uri)))
+            .collect())
+    }
+}
+
+/// Metadata of the message carried by `bundle` received from node `uri`.
+pub fn metadata_from_bundle(bundle: &iota_model::Bundle, uri: &str) -> MessageMetadata {
+    MessageMetadata {
+        timestamp: Some(bundle[0].timestamp),
+        attachment_timestamp: Some(bundle[0].attachment_timestamp),
+        bundle_hash: Some(bundle[0].bundle.clone()),
+        tx_hashes: bundle.iter().map(|tx| tx.hash.clone()).collect(),
+        source: Some(uri.to_string()),
+    }
+}
+
+/// Receive valid bundles attached to `link`, earliest first.
+fn recv_bundles<TW>(client: &mut iota_client::Client<'_>, link: &TangleAddress<TW>) -> Fallible<Vec<iota_model::Bundle>>
+where
+    TW: StringTbitWord,
+{
+    let find_opt = iota_client::options::FindTransactionsOptions {
+        bundles: Vec::new(),
+        addresses: vec![link.appinst.to_string()],
+        tags: vec![link.msgid.to_string()],
+        approvees: Vec::new(),
+    };
+
+    let find_resp = client.find_transactions(find_opt)?;
+    if let Some(e) = find_resp.error() {
+        bail!("Find transactions failed with: {}.", e)
+    } else if let Some(hashes) = find_resp.take_hashes() {
+        if hashes.is_empty() {
+            return Err(LinkNotFound.into());
+        }
+        let get_resp = client.get_trytes(&hashes)?;
+        if let Some(e) = get_resp.error() {
+            bail!("Get trytes failed with: {}.", e)
+        } else if let Some(trytes) = get_resp.take_trytes() {
+            Ok(bundles_from_trytes(&trytes))
         } else {
-            bail!("Find transactions contains no hashes.")
+            bail!("Get trytes contains no trytes.")
         }
+    } else {
+        bail!("Find transactions contains no hashes.")
+    }
+}
+
+/// Receive messages with bundle timestamps from valid bundles attached to `link`, earliest first.
+pub fn recv_tangle_messages<TW, F>(
+    client: &mut iota_client::Client<'_>,
+    link: &TangleAddress<TW>,
+) -> Fallible<Vec<TangleMessage<TW, F>>>
+where
+    TW: StringTbitWord,
+{
+    Ok(recv_bundles(client, link)?
+        .iter()
+        .map(|bundle| tangle_msg_from_bundle(bundle))
+        .collect())
+}
+
+type ClientJob = Box<dyn for<'c> FnOnce(&mut iota_client::Client<'c>) + Send>;
+
+/// Asynchronous Tangle client.
+///
+/// `iota_client::Client` is blocking, so requests are executed one by one by a client
+/// running in a background thread; futures complete when the corresponding request is done.
+/// The thread is stopped when `AsyncClient` is dropped.
+pub struct AsyncClient {
+    jobs: mpsc::Sender<ClientJob>,
+}
+
+impl AsyncClient {
+    /// Start client connected to IRI node with URI `uri`.
+    pub fn new(uri: &str) -> Self {
+        let uri = uri.to_string();
+        let (jobs, recv_jobs) = mpsc::channel::<ClientJob>();
+        thread::spawn(move || {
+            let mut client = iota_client::Client::new(&uri);
+            for job in recv_jobs {
+                job(&mut client);
+            }
+        });
+        Self { jobs }
+    }
+
+    fn run<T, J>(&self, job: J) -> TransportFuture<'static, T>
+    where
+        T: Send + 'static,
+        J: for<'c> FnOnce(&mut iota_client::Client<'c>) -> Fallible<T> + Send + 'static,
+    {
+        let (send_result, recv_result) = oneshot::channel();
+        let sent = self.jobs.send(Box::new(move |client| {
+            // Result is dropped if the future has been dropped.
+            let _ = send_result.send(job(client));
+        }));
+        Box::pin(async move {
+            ensure!(sent.is_ok(), "Tangle client thread has stopped.");
+            match recv_result.await {
+                Ok(result) => result,
+                Err(_) => bail!("Tangle client thread has stopped."),
+            }
+        })
+    }
+}
+
+impl<TW, F> AsyncTransport<TW, F, TangleAddress<TW>> for AsyncClient
+where
+    TW: StringTbitWord + Send + 'static,
+    F: Send + 'static,
+{
+    type SendOptions = SendTrytesOptions;
+
+    /// Send a Streams message over the Tangle with the current timestamp.
+    fn send_message_with_options<'a>(
+        &'a mut self,
+        msg: &'a TbinaryMessage<TW, F, TangleAddress<TW>>,
+        opt: Self::SendOptions,
+    ) -> TransportFuture<'a, ()> {
+        let msg = msg.clone();
+        self.run(move |client| Transport::send_message_with_options(client, &msg, opt))
+    }
+
+    type RecvOptions = ();
+
+    /// Receive a message.
+    fn recv_messages_with_options<'a>(
+        &'a mut self,
+        link: &'a TangleAddress<TW>,
+        opt: Self::RecvOptions,
+    ) -> TransportFuture<'a, Vec<TbinaryMessage<TW, F, TangleAddress<TW>>>> {
+        let link = link.clone();
+        self.run(move |client| Transport::recv_messages_with_options(client, &link, opt))
+    }
+
+    /// Receive messages together with bundle timestamps and hashes.
+    fn recv_messages_with_metadata_and_options<'a>(
+        &'a mut self,
+        link: &'a TangleAddress<TW>,
+        opt: Self::RecvOptions,
+    ) -> TransportFuture<'a, Vec<MessageWithMetadata<TW, F, TangleAddress<TW>>>>
+    where
+        TW: Send + 'a,
+        F: Send + 'a,
+        TangleAddress<TW>: Send + 'a,
+    {
+        let link = link.clone();
+        self.run(move |client| Transport::recv_messages_with_metadata_and_options(client, &link, opt))
     }
 }
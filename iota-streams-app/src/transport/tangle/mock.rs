@@ -0,0 +1,313 @@
+//! Local in-process stand-in for an IRI node.
+//!
+//! Only the endpoints used by `iota_client::Client` transport are implemented:
+//! `getTransactionsToApprove`, `attachToTangle`, `storeTransactions`, `broadcastTransactions`,
+//! `findTransactions` and `getTrytes`. Transactions are kept in memory and are lost when
+//! the node is stopped.
+//!
+//! Note that `iota_client::Client::send_trytes` ignores `min_weight_magnitude` option and always
+//! requests PoW with difficulty 14, so the node does PoW with its own, possibly lower, difficulty.
+
+use failure::{
+    bail,
+    ensure,
+    Fallible,
+};
+use serde_json::{
+    json,
+    Value,
+};
+use std::{
+    collections::HashMap,
+    io::{
+        BufRead,
+        BufReader,
+        Write,
+    },
+    net::{
+        SocketAddr,
+        TcpListener,
+        TcpStream,
+    },
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        Arc,
+        Mutex,
+    },
+    thread,
+};
+
+use iota_lib_rs::prelude::iota_model;
+
+use super::pow;
+
+/// Size of a transaction hash in trytes.
+const HASH_TRYTES: usize = 81;
+
+/// Size of a transaction in trytes.
+const TRANSACTION_TRYTES: usize = 2673;
+
+/// Transactions stored by the node.
+#[derive(Default)]
+struct Tangle {
+    /// Transactions and their tryte encodings indexed by transaction hash.
+    txs: HashMap<String, (iota_model::Transaction, String)>,
+
+    /// Hashes of stored transactions in the order of storing.
+    hashes: Vec<String>,
+}
+
+impl Tangle {
+    fn store(&mut self, trytes: &str, min_weight_magnitude: usize) -> Fallible<()> {
+        let tx: iota_model::Transaction = trytes.parse()?;
+        ensure!(
+            pow::check_pow(&tx, min_weight_magnitude),
+            "Invalid transaction hash: insufficient proof of work."
+        );
+        if !self.txs.contains_key(&tx.hash) {
+            self.hashes.push(tx.hash.clone());
+            self.txs.insert(tx.hash.clone(), (tx, trytes.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Hashes of transactions matching all non-empty lists of `bundles`, `addresses`, `tags` and `approvees`.
+    fn find(&self, bundles: &[String], addresses: &[String], tags: &[String], approvees: &[String]) -> Vec<String> {
+        fn matches(values: &[String], value: &str) -> bool {
+            values.is_empty() || values.iter().any(|v| v == value)
+        }
+        let addresses = addresses
+            .iter()
+            .map(|a| a.chars().take(HASH_TRYTES).collect())
+            .collect::<Vec<String>>();
+        self.hashes
+            .iter()
+            .filter(|hash| {
+                let tx = &self.txs[*hash].0;
+                matches(bundles, &tx.bundle)
+                    && matches(&addresses, &tx.address)
+                    && matches(tags, &tx.tag)
+                    && (matches(approvees, &tx.trunk_transaction) || matches(approvees, &tx.branch_transaction))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Tryte encoding of transaction `hash` or all-9 string if the transaction is unknown.
+    fn trytes(&self, hash: &str) -> String {
+        match self.txs.get(hash) {
+            Some((_, trytes)) => trytes.clone(),
+            None => "9".repeat(TRANSACTION_TRYTES),
+        }
+    }
+
+    /// Last two stored transactions or the null hash.
+    fn tips(&self) -> (String, String) {
+        let null_hash = "9".repeat(HASH_TRYTES);
+        let mut tips = self.hashes.iter().rev();
+        let trunk = tips.next().cloned().unwrap_or(null_hash);
+        let branch = tips.next().cloned().unwrap_or_else(|| trunk.clone());
+        (trunk, branch)
+    }
+}
+
+fn string_list(request: &Value, field: &str) -> Fallible<Vec<String>> {
+    match request.get(field) {
+        None => Ok(Vec::new()),
+        Some(Value::Array(values)) => values
+            .iter()
+            .map(|v| match v.as_str() {
+                Some(s) => Ok(s.to_string()),
+                None => bail!("Invalid '{}' parameter.", field),
+            })
+            .collect(),
+        Some(_) => bail!("Invalid '{}' parameter.", field),
+    }
+}
+
+fn process_command(tangle: &Mutex<Tangle>, min_weight_magnitude: usize, request: &Value) -> Fallible<Value> {
+    let mut tangle = tangle.lock().unwrap();
+    match request.get("command").and_then(|c| c.as_str()) {
+        Some("getTransactionsToApprove") => {
+            let (trunk, branch) = tangle.tips();
+            Ok(json!({
+                "trunkTransaction": trunk,
+                "branchTransaction": branch,
+                "duration": 0,
+            }))
+        }
+        Some("attachToTangle") => {
+            let trunk = request.get("trunkTransaction").and_then(|t| t.as_str()).unwrap_or("");
+            let branch = request.get("branchTransaction").and_then(|t| t.as_str()).unwrap_or("");
+            // Requested `minWeightMagnitude` is ignored: `iota_client::Client::send_trytes` always requests 14
+            // which is too slow for tests, node's own min weight magnitude is used instead.
+            let trytes = string_list(request, "trytes")?;
+            let opt = pow::PowOptions {
+                min_weight_magnitude,
+                threads: 1,
+            };
+            Ok(json!({
+                "trytes": pow::attach_trytes(&trytes, trunk, branch, &opt)?,
+                "duration": 0,
+            }))
+        }
+        Some("storeTransactions") => {
+            for trytes in string_list(request, "trytes")? {
+                tangle.store(&trytes, min_weight_magnitude)?;
+            }
+            Ok(json!({ "duration": 0 }))
+        }
+        Some("broadcastTransactions") => {
+            // There are no neighbors, transactions are only checked.
+            for trytes in string_list(request, "trytes")? {
+                let _tx: iota_model::Transaction = trytes.parse()?;
+            }
+            Ok(json!({ "duration": 0 }))
+        }
+        Some("findTransactions") => {
+            let bundles = string_list(request, "bundles")?;
+            let addresses = string_list(request, "addresses")?;
+            let tags = string_list(request, "tags")?;
+            let approvees = string_list(request, "approvees")?;
+            ensure!(
+                !bundles.is_empty() || !addresses.is_empty() || !tags.is_empty() || !approvees.is_empty(),
+                "Invalid params: no search criteria."
+            );
+            Ok(json!({
+                "hashes": tangle.find(&bundles, &addresses, &tags, &approvees),
+                "duration": 0,
+            }))
+        }
+        Some("getTrytes") => {
+            let trytes = string_list(request, "hashes")?
+                .iter()
+                .map(|hash| tangle.trytes(hash))
+                .collect::<Vec<String>>();
+            Ok(json!({
+                "trytes": trytes,
+                "duration": 0,
+            }))
+        }
+        Some(command) => bail!("Command [{}] is unknown.", command),
+        None => bail!("COMMAND parameter has not been specified in the request."),
+    }
+}
+
+/// Read HTTP request from `reader` and return its body, or `None` if the connection has been closed.
+fn read_request<R: BufRead>(reader: &mut R) -> Fallible<Option<Vec<u8>>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    ensure!(line.starts_with("POST "), "Unsupported HTTP request: '{}'.", line.trim());
+
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        ensure!(reader.read_line(&mut line)? != 0, "Unexpected end of HTTP request.");
+        let header = line.trim();
+        if header.is_empty() {
+            break;
+        }
+        let mut parts = header.splitn(2, ':');
+        if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse()?;
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+fn write_response<W: Write>(writer: &mut W, status: &str, body: &Value) -> Fallible<()> {
+    let body = body.to_string();
+    write!(
+        writer,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn serve_connection(stream: TcpStream, tangle: &Mutex<Tangle>, min_weight_magnitude: usize) -> Fallible<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    while let Some(body) = read_request(&mut reader)? {
+        let response = serde_json::from_slice(&body)
+            .map_err(failure::Error::from)
+            .and_then(|request| process_command(tangle, min_weight_magnitude, &request));
+        match response {
+            Ok(response) => write_response(&mut writer, "200 OK", &response)?,
+            Err(e) => write_response(&mut writer, "400 Bad Request", &json!({ "error": e.to_string() }))?,
+        }
+    }
+    Ok(())
+}
+
+/// Local IRI node listening on a random localhost port.
+///
+/// The node stops listening when dropped.
+pub struct MockNode {
+    addr: SocketAddr,
+    uri: String,
+    stop: Arc<AtomicBool>,
+    listener: Option<thread::JoinHandle<()>>,
+}
+
+impl MockNode {
+    /// Start node accepting transactions with at least `min_weight_magnitude` trailing zero trits in hash.
+    pub fn start(min_weight_magnitude: usize) -> Fallible<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let tangle = Arc::new(Mutex::new(Tangle::default()));
+
+        let stop_listener = stop.clone();
+        let listener = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop_listener.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let tangle = tangle.clone();
+                    thread::spawn(move || {
+                        // Connection errors only affect the client of the connection.
+                        let _ = serve_connection(stream, &tangle, min_weight_magnitude);
+                    });
+                }
+            }
+        });
+
+        Ok(Self {
+            addr,
+            uri: format!("http://{}", addr),
+            stop,
+            listener: Some(listener),
+        })
+    }
+
+    /// URI of the node suitable for `iota_client::Client::new`.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+}
+
+impl Drop for MockNode {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // Wake up the listener blocked in `accept`.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(listener) = self.listener.take() {
+            let _ = listener.join();
+        }
+    }
+}
@@ -131,6 +131,31 @@ where
     }
 }
 
+/// Application instance and message identifier trits packed together, 65 bytes.
+impl<TW> BinaryLink for TangleAddress<TW>
+where
+    TW: trinary::TritWord,
+{
+    fn encode_link(&self, buf: &mut Vec<u8>) -> Fallible<()> {
+        encode_trits(&Tbits::from_slices(&[self.appinst.tbits().slice(), self.msgid.tbits().slice()]), buf);
+        Ok(())
+    }
+
+    fn decode_link(bytes: &[u8]) -> Fallible<(Self, usize)> {
+        let (ts, size) = decode_trits::<TW>(APPINST_SIZE + MSGID_SIZE, bytes)?;
+        let (appinst, msgid) = ts.slice().split_at(APPINST_SIZE);
+        let link = Self {
+            appinst: AppInst {
+                id: NTrytes(Tbits::from_slice(appinst)),
+            },
+            msgid: MsgId {
+                id: NTrytes(Tbits::from_slice(msgid)),
+            },
+        };
+        Ok((link, size))
+    }
+}
+
 impl<TW> HasLink for TangleAddress<TW>
 where
     TW: BasicTbitWord,
@@ -173,6 +198,22 @@ where
     }
 }
 
+impl<TW, F> DefaultTangleLinkGenerator<TW, F>
+where
+    TW: BasicTbitWord,
+{
+    /// Create link generator with the initial message `counter`. Different parties of
+    /// the same channel instance should use distinct counters, otherwise their messages
+    /// attached to the same message get the same link.
+    pub fn with_counter(counter: usize) -> Self {
+        Self {
+            appinst: AppInst::<TW>::default(),
+            counter,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
 impl<TW, F> DefaultTangleLinkGenerator<TW, F> {
     pub fn reset_appinst(&mut self, appinst: AppInst<TW>) {
         self.appinst = appinst;
@@ -184,18 +225,28 @@ where
     TW: SpongosTbitWord + trinary::TritWord,
     F: PRP<TW> + Default,
 {
-    fn try_gen_msgid(&self, msgid: &MsgId<TW>) -> Fallible<MsgId<TW>> {
+    fn try_gen_msgid(&self, msgid: &MsgId<TW>, counter: usize) -> Fallible<MsgId<TW>> {
         let mut new = MsgId::default();
         wrap::Context::<TW, F, io::NoOStream>::new(io::NoOStream)
             .absorb(External(&self.appinst.id))?
             .absorb(External(&msgid.id))?
-            .absorb(External(Size(self.counter)))?
+            .absorb(External(Size(counter)))?
             .commit()?
             .squeeze(External(&mut new.id))?;
         Ok(new)
     }
     fn gen_msgid(&self, msgid: &MsgId<TW>) -> MsgId<TW> {
-        self.try_gen_msgid(msgid).map_or(MsgId::<TW>::default(), |x| x)
+        self.try_gen_msgid(msgid, self.counter).map_or(MsgId::<TW>::default(), |x| x)
+    }
+
+    /// Derive link of a message attached to `msgid` as if it was generated with `counter`.
+    /// Generator state is not changed. Links of messages published by other parties of the
+    /// channel instance can be predicted this way.
+    pub fn link_with_counter(&self, msgid: &MsgId<TW>, counter: usize) -> TangleAddress<TW> {
+        TangleAddress {
+            appinst: self.appinst.clone(),
+            msgid: self.try_gen_msgid(msgid, counter).map_or(MsgId::<TW>::default(), |x| x),
+        }
     }
 }
 
@@ -242,6 +293,35 @@ where
     }
 }
 
+/// Link generator state is encoded so that the generator can be persisted and
+/// restored later without repeating already generated links.
+///
+/// ```pb3
+/// message DefaultTangleLinkGenerator {
+///     skip tryte appinst[81];
+///     skip size counter;
+/// }
+/// ```
+impl<TW, F> SkipFallback<TW, F> for DefaultTangleLinkGenerator<TW, F>
+where
+    TW: BasicTbitWord + trinary::TritWord,
+{
+    fn sizeof_skip(&self, ctx: &mut sizeof::Context<TW, F>) -> Fallible<()> {
+        ctx.skip(&self.appinst.id)?.skip(Size(self.counter))?;
+        Ok(())
+    }
+    fn wrap_skip<OS: io::OStream<TW>>(&self, ctx: &mut wrap::Context<TW, F, OS>) -> Fallible<()> {
+        ctx.skip(&self.appinst.id)?.skip(Size(self.counter))?;
+        Ok(())
+    }
+    fn unwrap_skip<IS: io::IStream<TW>>(&mut self, ctx: &mut unwrap::Context<TW, F, IS>) -> Fallible<()> {
+        let mut counter = Size(0);
+        ctx.skip(&mut self.appinst.id)?.skip(&mut counter)?;
+        self.counter = counter.0;
+        Ok(())
+    }
+}
+
 pub const APPINST_SIZE: usize = 243;
 
 /// Application instance identifier.
@@ -363,6 +443,25 @@ where
     }
 }
 
+/// Full address is `skip`ped when stored outside of messages, ie. when persisting state.
+impl<TW, F> SkipFallback<TW, F> for TangleAddress<TW>
+where
+    TW: BasicTbitWord + trinary::TritWord,
+{
+    fn sizeof_skip(&self, ctx: &mut sizeof::Context<TW, F>) -> Fallible<()> {
+        ctx.skip(&self.appinst.id)?.skip(&self.msgid.id)?;
+        Ok(())
+    }
+    fn wrap_skip<OS: io::OStream<TW>>(&self, ctx: &mut wrap::Context<TW, F, OS>) -> Fallible<()> {
+        ctx.skip(&self.appinst.id)?.skip(&self.msgid.id)?;
+        Ok(())
+    }
+    fn unwrap_skip<IS: io::IStream<TW>>(&mut self, ctx: &mut unwrap::Context<TW, F, IS>) -> Fallible<()> {
+        ctx.skip(&mut self.appinst.id)?.skip(&mut self.msgid.id)?;
+        Ok(())
+    }
+}
+
 pub const MSGID_SIZE: usize = 81;
 
 /// Message identifier unique within application instance.
@@ -481,3 +580,10 @@ where
 
 //#[cfg(feature = "tangle")]
 pub mod client;
+
+/// Local IRI node for testing `client` transport offline.
+#[cfg(feature = "mock_node")]
+pub mod mock;
+
+/// Local proof of work for Tangle transactions.
+pub mod pow;
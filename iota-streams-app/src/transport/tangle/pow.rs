@@ -0,0 +1,403 @@
+//! Proof of work for Tangle transactions.
+//!
+//! Nonce search runs Curl-P-81 on 64 candidate nonces at once: each trit of the Curl state is
+//! represented by a pair of `u64` words, bit `i` of the pair is the trit of the `i`-th candidate.
+//! Several threads search disjoint parts of the nonce space until one of them finds a nonce.
+
+use chrono::Utc;
+use failure::{
+    bail,
+    ensure,
+    Fallible,
+};
+use std::{
+    convert::TryInto,
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        Arc,
+    },
+    thread,
+};
+
+use iota_conversion::Trinary;
+use iota_crypto::Sponge;
+use iota_lib_rs::prelude::{
+    iota_conversion,
+    iota_crypto,
+    iota_model,
+};
+
+/// Size of Curl hash and rate in trits.
+const HASH_LENGTH: usize = 243;
+
+/// Size of Curl state in trits.
+const STATE_LENGTH: usize = 3 * HASH_LENGTH;
+
+/// Size of a transaction in trits.
+const TRANSACTION_LENGTH: usize = 8019;
+
+/// Size of transaction nonce in trits, the nonce takes the last trits of the transaction.
+const NONCE_LENGTH: usize = 81;
+
+/// Offset of the nonce within the last absorbed block.
+const NONCE_OFFSET: usize = HASH_LENGTH - NONCE_LENGTH;
+
+/// Nonce trits enumerating the 64 candidates searched at once.
+const LANE_TRITS: usize = 4;
+
+/// Nonce trits enumerating threads.
+const THREAD_TRITS: usize = 27;
+
+/// Nonce trits enumerated by each thread, the last trits of the nonce.
+const COUNTER_TRITS: usize = 27;
+
+/// Maximal attachment timestamp, `(3^27 - 1) / 2`.
+const MAX_TIMESTAMP: i64 = 3_812_798_742_493;
+
+/// Proof of work options.
+#[derive(Clone, Copy, Debug)]
+pub struct PowOptions {
+    /// Minimal number of trailing zero trits in transaction hash.
+    pub min_weight_magnitude: usize,
+
+    /// Number of searching threads, at least one thread is used.
+    pub threads: usize,
+}
+
+impl Default for PowOptions {
+    fn default() -> Self {
+        Self {
+            min_weight_magnitude: 14,
+            threads: num_cpus::get(),
+        }
+    }
+}
+
+/// Curl state of 64 candidates, a trit is encoded as `(lo, hi)` bits:
+/// `(1, 1)` is 0, `(0, 1)` is 1 and `(1, 0)` is -1.
+#[derive(Clone)]
+struct Lanes {
+    lo: [u64; STATE_LENGTH],
+    hi: [u64; STATE_LENGTH],
+}
+
+impl Lanes {
+    /// All candidates with the same state `trits`.
+    fn from_trits(trits: &[i8]) -> Self {
+        let mut lanes = Self {
+            lo: [!0; STATE_LENGTH],
+            hi: [!0; STATE_LENGTH],
+        };
+        for (i, t) in trits.iter().enumerate() {
+            lanes.set(i, *t);
+        }
+        lanes
+    }
+
+    /// Set trit `i` of all candidates.
+    fn set(&mut self, i: usize, trit: i8) {
+        let (lo, hi) = match trit {
+            1 => (0, !0),
+            -1 => (!0, 0),
+            _ => (!0, !0),
+        };
+        self.lo[i] = lo;
+        self.hi[i] = hi;
+    }
+
+    /// Trit `i` of candidate `lane`.
+    fn get(&self, i: usize, lane: u32) -> i8 {
+        if (self.lo[i] >> lane) & 1 == 0 {
+            1
+        } else if (self.hi[i] >> lane) & 1 == 0 {
+            -1
+        } else {
+            0
+        }
+    }
+
+    /// Curl-P-81 transform, the same as `iota_crypto::Curl` does.
+    fn transform(&mut self, scratchpad: &mut Self) {
+        for _ in 0..81 {
+            scratchpad.clone_from(self);
+            let mut j = 0;
+            for i in 0..STATE_LENGTH {
+                let alpha = scratchpad.lo[j];
+                let beta = scratchpad.hi[j];
+                j = if j < 365 { j + 364 } else { j - 365 };
+                let gamma = scratchpad.hi[j];
+                let delta = (alpha | !gamma) & (scratchpad.lo[j] ^ beta);
+                self.lo[i] = !delta;
+                self.hi[i] = (alpha ^ gamma) | delta;
+            }
+        }
+    }
+
+    /// Mask of candidates with at least `min_weight_magnitude` trailing zero trits in hash.
+    fn zero_mask(&self, min_weight_magnitude: usize) -> u64 {
+        (HASH_LENGTH - min_weight_magnitude..HASH_LENGTH).fold(!0, |mask, i| mask & !(self.lo[i] ^ self.hi[i]))
+    }
+}
+
+/// Trits of balanced ternary representation of `n`, least significant first.
+fn to_trits(mut n: usize, trits: &mut [i8]) {
+    for t in trits.iter_mut() {
+        *t = match n % 3 {
+            0 => 0,
+            1 => 1,
+            _ => -1,
+        };
+        n = (n + 1) / 3;
+    }
+}
+
+/// Increment balanced ternary number `trits`, returns false on overflow.
+fn increment(trits: &mut [i8]) -> bool {
+    for t in trits.iter_mut() {
+        if *t < 1 {
+            *t += 1;
+            return true;
+        }
+        *t = -1;
+    }
+    false
+}
+
+/// Search nonces with thread trits set to `thread` until a nonce is found by any thread.
+fn search_thread(
+    mid_state: &Lanes,
+    thread: usize,
+    min_weight_magnitude: usize,
+    found: &AtomicBool,
+) -> Option<[i8; NONCE_LENGTH]> {
+    let mut nonce = [0; NONCE_LENGTH];
+    to_trits(thread, &mut nonce[LANE_TRITS..LANE_TRITS + THREAD_TRITS]);
+
+    let mut block = mid_state.clone();
+    for i in NONCE_OFFSET..NONCE_OFFSET + LANE_TRITS {
+        block.lo[i] = 0;
+        block.hi[i] = 0;
+    }
+    for lane in 0..64 {
+        let mut trits = [0; LANE_TRITS];
+        to_trits(lane, &mut trits);
+        for (i, t) in trits.iter().enumerate() {
+            let bit = 1 << lane;
+            block.lo[NONCE_OFFSET + i] |= if *t == 1 { 0 } else { bit };
+            block.hi[NONCE_OFFSET + i] |= if *t == -1 { 0 } else { bit };
+        }
+    }
+
+    let mut state = block.clone();
+    let mut scratchpad = block.clone();
+    loop {
+        if found.load(Ordering::Relaxed) {
+            return None;
+        }
+        for (i, t) in nonce.iter().enumerate().skip(LANE_TRITS) {
+            block.set(NONCE_OFFSET + i, *t);
+        }
+        state.clone_from(&block);
+        state.transform(&mut scratchpad);
+
+        let mask = state.zero_mask(min_weight_magnitude);
+        if mask != 0 {
+            found.store(true, Ordering::Relaxed);
+            let lane = mask.trailing_zeros();
+            for (i, t) in nonce.iter_mut().enumerate().take(LANE_TRITS) {
+                *t = block.get(NONCE_OFFSET + i, lane);
+            }
+            return Some(nonce);
+        }
+
+        if !increment(&mut nonce[NONCE_LENGTH - COUNTER_TRITS..]) {
+            return None;
+        }
+    }
+}
+
+/// Find nonce for transaction `trits` such that its Curl-P-81 hash has at least
+/// `min_weight_magnitude` trailing zero trits. The nonce in `trits` is ignored.
+pub fn search_nonce(trits: &[i8], opt: &PowOptions) -> Fallible<[i8; NONCE_LENGTH]> {
+    ensure!(
+        trits.len() == TRANSACTION_LENGTH,
+        "Bad transaction size: {} trits, expected {}.",
+        trits.len(),
+        TRANSACTION_LENGTH
+    );
+    ensure!(
+        opt.min_weight_magnitude <= HASH_LENGTH,
+        "Min weight magnitude {} exceeds hash size.",
+        opt.min_weight_magnitude
+    );
+
+    // Blocks before the last one don't depend on nonce.
+    let mut curl = iota_crypto::Curl::default();
+    curl.absorb(&trits[..TRANSACTION_LENGTH - HASH_LENGTH])?;
+    let mut mid_state = Lanes::from_trits(curl.state());
+    for (i, t) in trits[TRANSACTION_LENGTH - HASH_LENGTH..].iter().enumerate() {
+        mid_state.set(i, *t);
+    }
+
+    let mid_state = Arc::new(mid_state);
+    let found = Arc::new(AtomicBool::new(false));
+    let min_weight_magnitude = opt.min_weight_magnitude;
+    let searches = (0..std::cmp::max(1, opt.threads))
+        .map(|t| {
+            let (mid_state, found) = (mid_state.clone(), found.clone());
+            thread::spawn(move || search_thread(&mid_state, t, min_weight_magnitude, &found))
+        })
+        .collect::<Vec<_>>();
+    // All threads are joined, they stop soon after a nonce is found.
+    let mut nonce = None;
+    for search in searches {
+        if let Ok(Some(n)) = search.join() {
+            nonce.get_or_insert(n);
+        }
+    }
+    match nonce {
+        Some(nonce) => Ok(nonce),
+        None => bail!("Nonce not found."),
+    }
+}
+
+/// Check whether hash of transaction `tx` has at least `min_weight_magnitude` trailing zero trits.
+pub fn check_pow(tx: &iota_model::Transaction, min_weight_magnitude: usize) -> bool {
+    let hash = tx.hash.trits();
+    hash.len() == HASH_LENGTH && hash.iter().rev().take_while(|t| **t == 0).count() >= min_weight_magnitude
+}
+
+/// Do proof of work for transaction `tx`: set its nonce and hash.
+pub fn pow_transaction(tx: &mut iota_model::Transaction, opt: &PowOptions) -> Fallible<()> {
+    let trytes: String = (&*tx).try_into()?;
+    let mut trits = trytes.trits();
+    let nonce = search_nonce(&trits, opt)?;
+    trits[TRANSACTION_LENGTH - NONCE_LENGTH..].copy_from_slice(&nonce);
+    *tx = trits.trytes()?.parse()?;
+    Ok(())
+}
+
+/// Attach transactions to the Tangle the same way IRI `attachToTangle` does: the first transaction
+/// approves `trunk` and `branch`, each next transaction approves the previous one and `trunk`.
+fn attach<'a>(
+    txs: impl Iterator<Item = &'a mut iota_model::Transaction>,
+    trunk: &str,
+    branch: &str,
+    opt: &PowOptions,
+) -> Fallible<()> {
+    let mut previous: Option<String> = None;
+    for tx in txs {
+        match previous {
+            None => {
+                tx.trunk_transaction = trunk.to_string();
+                tx.branch_transaction = branch.to_string();
+            }
+            Some(hash) => {
+                tx.trunk_transaction = hash;
+                tx.branch_transaction = trunk.to_string();
+            }
+        }
+        tx.attachment_timestamp = Utc::now().timestamp_millis();
+        tx.attachment_timestamp_lower_bound = 0;
+        tx.attachment_timestamp_upper_bound = MAX_TIMESTAMP;
+        pow_transaction(tx, opt)?;
+        previous = Some(tx.hash.clone());
+    }
+    Ok(())
+}
+
+/// Attach finalized bundle approving `trunk` and `branch` transactions and do proof of work.
+///
+/// The last transaction of the bundle approves `trunk` and `branch`, each other transaction
+/// approves the next transaction of the bundle. Bundle hash is not affected.
+pub fn attach_bundle(bundle: &mut iota_model::Bundle, trunk: &str, branch: &str, opt: &PowOptions) -> Fallible<()> {
+    attach(bundle.iter_mut().rev(), trunk, branch, opt)
+}
+
+/// Attach transactions `trytes` as IRI `attachToTangle` does, bundle transactions are expected
+/// to be given in reverse order. Attached transactions are returned in reverse order of `trytes`.
+pub fn attach_trytes(
+    trytes: &[iota_conversion::Trytes],
+    trunk: &str,
+    branch: &str,
+    opt: &PowOptions,
+) -> Fallible<Vec<iota_conversion::Trytes>> {
+    let mut txs = trytes
+        .iter()
+        .map(|t| t.parse())
+        .collect::<Result<Vec<iota_model::Transaction>, _>>()?;
+    attach(txs.iter_mut(), trunk, branch, opt)?;
+    txs.iter()
+        .rev()
+        .map(|tx| tx.try_into())
+        .collect::<Result<Vec<iota_conversion::Trytes>, _>>()
+}
+
+#[cfg(test)]
+#[test]
+fn test_search_nonce() {
+    // Signature fragment is followed by zero trits.
+    let trits = format!("{}{}", "POWTEST".repeat(2187 / 7), "9".repeat(2673 - 2187 / 7 * 7)).trits();
+    for (min_weight_magnitude, threads) in &[(0, 1), (5, 1), (8, 3)] {
+        let opt = PowOptions {
+            min_weight_magnitude: *min_weight_magnitude,
+            threads: *threads,
+        };
+        let mut tx_trits = trits.clone();
+        let nonce = search_nonce(&tx_trits, &opt).unwrap();
+        tx_trits[TRANSACTION_LENGTH - NONCE_LENGTH..].copy_from_slice(&nonce);
+        // Transaction hash is computed with `iota_crypto::Curl` when parsing.
+        let tx: iota_model::Transaction = tx_trits.trytes().unwrap().parse().unwrap();
+        assert!(check_pow(&tx, *min_weight_magnitude));
+    }
+    assert!(search_nonce(&trits[..TRANSACTION_LENGTH - 1], &PowOptions::default()).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_attach_bundle() {
+    use super::{
+        client::*,
+        *,
+    };
+    use crate::message::TbinaryMessage;
+    use iota_streams_core::{
+        sponge::prp::troika::Troika,
+        tbits::{
+            trinary::Trit,
+            Tbits,
+        },
+    };
+
+    let link = TangleAddress::<Trit>::new(
+        AppInst {
+            id: NTrytes(Tbits::cycle_str(APPINST_SIZE, "A")),
+        },
+        MsgId {
+            id: NTrytes(Tbits::cycle_str(MSGID_SIZE, "M")),
+        },
+    );
+    let msg = TbinaryMessage::<Trit, Troika, TangleAddress<Trit>>::new(link, Tbits::cycle_str(6561 * 2, "B"));
+    let mut bundle = msg_to_bundle(&msg, 0);
+    let bundle_hash = bundle[0].bundle.clone();
+    let trunk = "T".repeat(81);
+    let branch = "B".repeat(81);
+    let opt = PowOptions {
+        min_weight_magnitude: 6,
+        threads: 2,
+    };
+    attach_bundle(&mut bundle, &trunk, &branch, &opt).unwrap();
+
+    assert!(bundle.iter().all(|tx| check_pow(tx, 6) && tx.bundle == bundle_hash));
+    assert_eq!(bundle[0].trunk_transaction, bundle[1].hash);
+    assert_eq!(bundle[0].branch_transaction, trunk);
+    assert_eq!(bundle[1].trunk_transaction, trunk);
+    assert_eq!(bundle[1].branch_transaction, branch);
+
+    let bundles = bundles_from_trytes(&bundle_to_trytes(&bundle));
+    assert_eq!(bundles.len(), 1);
+    assert_eq!(msg_from_bundle::<Trit, Troika>(&bundles[0]).body, msg.body);
+}
@@ -174,6 +174,33 @@ where
         Self { sk, mt }
     }
 
+    /// Serialize Merkle tree: its height, the current WOTS key number and the stored nodes.
+    /// PRNG and nonce are not part of the result and must be kept separately.
+    pub fn store(&self) -> (usize, usize, Vec<Tbits<TW>>) {
+        self.mt.store()
+    }
+
+    /// Restore private key from `prng`, `nonce` and the Merkle tree serialized with `store`.
+    /// No leaves are regenerated, the tree is trusted to correspond to `prng` and `nonce`.
+    pub fn load(
+        prng: &Prng<TW, P::PrngG>,
+        nonce: TbitSlice<TW>,
+        height: usize,
+        skn: usize,
+        nodes: Vec<Tbits<TW>>,
+    ) -> Option<Self> {
+        if height > P::MAX_D || nodes.iter().any(|n| n.size() != P::MT_HASH_SIZE) {
+            return None;
+        }
+        let sk = SK {
+            prng: prng.clone(),
+            nonce: Tbits::<TW>::from_slice(nonce),
+            _phantom: std::marker::PhantomData,
+        };
+        let mt = P::MerkleTree::load(height, skn, nodes)?;
+        Some(Self { sk, mt })
+    }
+
     pub fn public_key<'a>(&'a self) -> &'a PublicKey<TW, P> {
         unsafe { std::mem::transmute::<&'a Tbits<TW>, &'a PublicKey<TW, P>>(self.mt.root()) }
     }
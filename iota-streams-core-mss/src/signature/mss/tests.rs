@@ -35,3 +35,29 @@ where
         }
     }
 }
+
+pub fn store_load<TW, P>()
+where
+    TW: SpongosTbitWord + IntTbitWord,
+    P: Parameters<TW>,
+{
+    let k = Tbits::zero(Prng::<TW, P::PrngG>::KEY_SIZE);
+    let prng = Prng::<TW, P::PrngG>::init(k);
+    let n = Tbits::<TW>::zero(33);
+
+    for d in 0..3 {
+        let mut sk = PrivateKey::<TW, P>::gen(&prng, n.slice(), d);
+
+        let h = Tbits::<TW>::zero(P::HASH_SIZE);
+        loop {
+            let (height, skn, nodes) = sk.store();
+            let loaded = PrivateKey::<TW, P>::load(&prng, n.slice(), height, skn, nodes).unwrap();
+            assert!(sk.public_key() == loaded.public_key());
+            assert_eq!(sk.skn(), loaded.skn());
+            assert!(sk.sign_tbits(&h) == loaded.sign_tbits(&h));
+            if !sk.next() {
+                break;
+            }
+        }
+    }
+}
@@ -69,5 +69,17 @@ fn sign_verify_d2_mttraversal() {
     use iota_streams_core::tbits::trinary::Trit;
     super::tests::sign_verify::<Trit, ParametersMtTraversal<Trit>>();
 }
+
+#[test]
+fn store_load_mtcomplete() {
+    use iota_streams_core::tbits::trinary::Trit;
+    super::tests::store_load::<Trit, ParametersMtComplete<Trit>>();
+}
+
+#[test]
+fn store_load_mttraversal() {
+    use iota_streams_core::tbits::trinary::Trit;
+    super::tests::store_load::<Trit, ParametersMtTraversal<Trit>>();
+}
 /*
  */
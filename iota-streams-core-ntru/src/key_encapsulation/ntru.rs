@@ -382,6 +382,22 @@ pub struct PublicKey<TW, F> {
     _phantom: std::marker::PhantomData<F>,
 }
 
+/// Default implementation for PrivateKey. Similar to `PublicKey`, this object is not valid
+/// and exists in order to simplify deserialization of private keys. Once private key trits
+/// have been deserialized the object must be `validate`d.
+impl<TW, F> Default for PrivateKey<TW, F>
+where
+    TW: BasicTbitWord,
+{
+    fn default() -> Self {
+        Self {
+            sk: Tbits::zero(SK_SIZE),
+            f: Poly::new(),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
 /// Default implementation for PublicKey. Note, this object is not valid and can't be
 /// used for encapsulating keys. This instance exists in order to simplify deserialization
 /// of public keys. Once public key trits have been deserialized the object must be `validate`d. If the `validate` method returns `false` then the object is invalid.
@@ -559,6 +575,68 @@ where
      */
 }
 
+impl<TW, F> PrivateKey<TW, F> {
+    /// Private key tbits.
+    pub fn tbits(&self) -> &Tbits<TW> {
+        &self.sk
+    }
+
+    /// Private key tbits, once private key has been modified it must be `validate`d.
+    pub fn tbits_mut(&mut self) -> &mut Tbits<TW> {
+        &mut self.sk
+    }
+}
+
+/// Compute `f = NTT(1+3sk)` and check for invertibility.
+fn sk_from_trits<TW>(sk: TbitSlice<TW>) -> Option<Poly>
+where
+    TW: TritWord,
+{
+    let mut f = Poly::new();
+    f.small_from_trits(sk);
+    f.small_mul3();
+    f.small3_add1();
+    f.ntt();
+    if f.has_inv() {
+        Some(f)
+    } else {
+        None
+    }
+}
+
+impl<TW, F> PrivateKey<TW, F>
+where
+    TW: TritWord,
+{
+    /// Try to create `PrivateKey` object from tbits `sk`. Fails in case `sk` has bad size
+    /// or corresponding polynomial is not invertible.
+    pub fn from_trits(sk: Tbits<TW>) -> Option<Self> {
+        if sk.size() == SK_SIZE {
+            let f = sk_from_trits(sk.slice())?;
+            Some(PrivateKey {
+                sk,
+                f,
+                _phantom: std::marker::PhantomData,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Precompute polynomial `f = NTT(1+3sk)` and check for invertibility.
+    pub fn validate(&mut self) -> bool {
+        if self.sk.size() != SK_SIZE {
+            return false;
+        }
+        if let Some(f) = sk_from_trits(self.sk.slice()) {
+            self.f = f;
+            true
+        } else {
+            false
+        }
+    }
+}
+
 impl<TW, F> PublicKey<TW, F> {
     /// Public polinomial tbits.
     pub fn tbits(&self) -> &Tbits<TW> {
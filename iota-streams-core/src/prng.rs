@@ -89,6 +89,11 @@ where
         }
     }
 
+    /// PRNG secret key, needed in order to persist the PRNG state.
+    pub fn secret_key(&self) -> &Tbits<TW> {
+        &self.secret_key
+    }
+
     fn gen_with_spongos<'a>(
         &self,
         s: &mut Spongos<TW, G>,
@@ -1,4 +1,8 @@
 //use std::convert::{AsMut, AsRef, From, TryFrom};
+use failure::{
+    ensure,
+    Fallible,
+};
 use std::fmt;
 //use std::hash;
 
@@ -387,6 +391,21 @@ where
         0 == self.outer.pos
     }
 
+    /// One-way digest of committed state: outer state hashed with a fresh `G` spongos.
+    /// Unlike `squeeze` the state is not changed and the digest reveals nothing of what would be
+    /// squeezed next. At most `G::CAPACITY` trits can be requested.
+    pub fn digest_tbits<G>(&self, n: usize) -> Fallible<Tbits<TW>>
+    where
+        G: PRP<TW> + Default,
+    {
+        ensure!(self.is_committed(), "Spongos state must be committed.");
+        ensure!(n <= G::CAPACITY, "Digest size {} exceeds capacity {}.", n, G::CAPACITY);
+        let mut s = Spongos::<TW, G>::init();
+        s.absorb(self.outer.tbits.slice());
+        s.commit();
+        Ok(s.squeeze_tbits(n))
+    }
+
     /// Join two Spongos objects.
     /// Joiner -- self -- object absorbs data squeezed from joinee.
     pub fn join(&mut self, joinee: &mut Self) {
@@ -1,7 +1,19 @@
 //! 5 trits per 1 byte.
 
-use super::defs::*;
-use crate::tbits::word::BasicTbitWord;
+use std::convert::TryInto;
+
+use super::{
+    defs::*,
+    word::TritWord,
+};
+use crate::tbits::{
+    word::BasicTbitWord,
+    TbitSlice,
+    Tbits,
+};
+
+/// Number of distinct values of a `B1T5` word, bytes `243..=255` are not valid words.
+pub const B1T5_VALUES: u16 = 243;
 
 /// 5 trits packed into a byte. It represents a "network" trinary word.
 ///
@@ -21,6 +33,55 @@ impl B1T5 {
     pub fn from_trits(ts: &[Trit; 5]) -> Self {
         unsafe { Self::word_from_tbits(ts.as_ptr()) }
     }
+
+    /// Byte representation of the word.
+    pub fn to_byte(self) -> u8 {
+        self.0
+    }
+
+    /// Word represented by byte `b`, `None` if `b` is not a valid representation, ie. `b >= 243`.
+    pub fn from_byte(b: u8) -> Option<Self> {
+        if (b as u16) < B1T5_VALUES {
+            Some(Self(b))
+        } else {
+            None
+        }
+    }
+}
+
+/// Pack trits into bytes, 5 trits per byte. The last byte is padded with zero trits.
+pub fn trits_to_b1t5<TW>(ts: TbitSlice<TW>) -> Vec<u8>
+where
+    TW: TritWord,
+{
+    let n = ts.size();
+    let mut trits = vec![Trit(0); (n + 4) / 5 * 5];
+    ts.get_tbits(&mut trits[..n]);
+    trits
+        .chunks(5)
+        .map(|t5| B1T5::from_trits(t5.try_into().unwrap()).to_byte())
+        .collect()
+}
+
+/// Unpack `n` trits from bytes packed with `trits_to_b1t5`.
+///
+/// Returns `None` if the number of bytes doesn't correspond to `n`, a byte is not a valid `B1T5` word
+/// or padding trits are not zero, so that each trit sequence has exactly one byte representation.
+pub fn trits_from_b1t5<TW>(n: usize, bytes: &[u8]) -> Option<Tbits<TW>>
+where
+    TW: TritWord,
+{
+    if bytes.len() != (n + 4) / 5 {
+        return None;
+    }
+    let mut trits = Vec::with_capacity(bytes.len() * 5);
+    for b in bytes {
+        trits.extend_from_slice(&B1T5::from_byte(*b)?.to_trits());
+    }
+    if trits[n..].iter().any(|t| *t != Trit(0)) {
+        return None;
+    }
+    Some(Tbits::from_tbits(&trits[..n]))
 }
 
 impl BasicTbitWord for B1T5 {
@@ -70,4 +131,21 @@ mod tests {
             assert_eq!(b, b2);
         }
     }
+
+    #[test]
+    fn b1t5_bytes() {
+        for n in 0_usize..20 {
+            let ts = Tbits::<Trit>::from_tbits(&(0..n).map(|i| Trit((i % 3) as u8)).collect::<Vec<_>>());
+            let bytes = trits_to_b1t5(ts.slice());
+            assert_eq!((n + 4) / 5, bytes.len());
+            assert_eq!(Some(ts), trits_from_b1t5::<Trit>(n, &bytes));
+        }
+
+        assert_eq!(None, trits_from_b1t5::<Trit>(5, &[243]));
+        assert_eq!(None, trits_from_b1t5::<Trit>(5, &[1, 0]));
+        assert_eq!(None, trits_from_b1t5::<Trit>(6, &[1]));
+        // Non-zero padding trit.
+        assert_eq!(None, trits_from_b1t5::<Trit>(4, &[81]));
+        assert!(trits_from_b1t5::<Trit>(4, &[80]).is_some());
+    }
 }
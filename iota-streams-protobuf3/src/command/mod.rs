@@ -23,6 +23,8 @@
 
 use failure::Fallible;
 
+use crate::types::OneofMode;
+
 /// Absorb command. Trinary representation of the field is absorbed into Spongos state.
 /// External fields are not encoded in the trinary stream. Non-trinary field is
 /// an input argument in Wrap command and an output argument in Unwrap command.
@@ -99,12 +101,26 @@ pub trait Repeated<I, F> {
     fn repeated(&mut self, values_iter: I, value_handle: F) -> Fallible<&mut Self>;
 }
 
+/// Oneof modifier. The discriminant `tag` of the chosen branch is encoded as `tryte`
+/// with the command selected by `mode`, then only the chosen branch is processed with
+/// `branch` which gets the index of the branch. In Unwrap operation the decoded
+/// discriminant is checked against the number of branches and is stored in `tag`.
+pub trait Oneof<Tag, C> {
+    fn oneof(&mut self, mode: OneofMode, tag: Tag, branch: C) -> Fallible<&mut Self>;
+}
+
+/// Optional modifier, same as `oneof { null none = 0; T some = 1; }`.
+/// The value is processed with `some` only if it is present.
+pub trait Optional<T, C> {
+    fn optional(&mut self, mode: OneofMode, value: T, some: C) -> Fallible<&mut Self>;
+}
+
 /// Condition guard.
 pub trait Guard {
     fn guard(&mut self, cond: bool, msg: &str) -> Fallible<&mut Self>;
 }
 
-/// Dump context info into stdout.
+/// Dump context info into stdout, unwrap context with trace records the label in the trace instead.
 /// Use it like this: `ctx.dump(format_args!("checkpoint"))`
 pub trait Dump {
     fn dump<'a>(&mut self, args: std::fmt::Arguments<'a>) -> Fallible<&mut Self> {
@@ -7,9 +7,13 @@ use super::Context;
 use crate::{
     command::Absorb,
     types::{
+        sizeof_bytes,
         sizeof_sizet,
         AbsorbFallback,
+        Bytes,
         Fallback,
+        Int,
+        NBytes,
         NTrytes,
         Size,
         Trint3,
@@ -100,6 +104,30 @@ where
     }
 }
 
+/// Integers are fixed-size and are encoded with `3 * T::TRYTES` trits.
+impl<'a, TW, F, T: Int> Absorb<&'a T> for Context<TW, F> {
+    fn absorb(&mut self, _int: &'a T) -> Fallible<&mut Self> {
+        self.size += 3 * T::TRYTES;
+        Ok(self)
+    }
+}
+
+/// `bytes` has variable size thus the size is encoded before the content bytes.
+impl<'a, TW, F> Absorb<&'a Bytes> for Context<TW, F> {
+    fn absorb(&mut self, bytes: &'a Bytes) -> Fallible<&mut Self> {
+        self.size += sizeof_sizet((bytes.0).len()) + sizeof_bytes((bytes.0).len());
+        Ok(self)
+    }
+}
+
+/// `byte [n]` is fixed-size.
+impl<'a, TW, F, const N: usize> Absorb<&'a NBytes<N>> for Context<TW, F> {
+    fn absorb(&mut self, _nbytes: &'a NBytes<N>) -> Fallible<&mut Self> {
+        self.size += sizeof_bytes(N);
+        Ok(self)
+    }
+}
+
 /// MSS public key has fixed size.
 impl<'a, TW, F, P> Absorb<&'a mss::PublicKey<TW, P>> for Context<TW, F>
 where
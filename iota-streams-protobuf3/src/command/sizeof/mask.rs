@@ -7,7 +7,11 @@ use super::Context;
 use crate::{
     command::Mask,
     types::{
+        sizeof_bytes,
         sizeof_sizet,
+        Bytes,
+        Int,
+        NBytes,
         NTrytes,
         Size,
         Trint3,
@@ -77,6 +81,30 @@ where
     }
 }
 
+/// Integers are fixed-size and are encoded with `3 * T::TRYTES` trits.
+impl<'a, TW, F, T: Int> Mask<&'a T> for Context<TW, F> {
+    fn mask(&mut self, _int: &'a T) -> Fallible<&mut Self> {
+        self.size += 3 * T::TRYTES;
+        Ok(self)
+    }
+}
+
+/// `bytes` has variable size thus the size is encoded before the content bytes.
+impl<'a, TW, F> Mask<&'a Bytes> for Context<TW, F> {
+    fn mask(&mut self, bytes: &'a Bytes) -> Fallible<&mut Self> {
+        self.size += sizeof_sizet((bytes.0).len()) + sizeof_bytes((bytes.0).len());
+        Ok(self)
+    }
+}
+
+/// `byte [n]` is fixed-size.
+impl<'a, TW, F, const N: usize> Mask<&'a NBytes<N>> for Context<TW, F> {
+    fn mask(&mut self, _nbytes: &'a NBytes<N>) -> Fallible<&mut Self> {
+        self.size += sizeof_bytes(N);
+        Ok(self)
+    }
+}
+
 impl<TW, F> Mask<&ntru::PublicKey<TW, F>> for Context<TW, F>
 where
     TW: BasicTbitWord,
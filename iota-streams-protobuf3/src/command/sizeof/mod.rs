@@ -29,6 +29,7 @@ mod dump;
 mod fork;
 mod join;
 mod mask;
+mod oneof;
 mod repeated;
 mod skip;
 mod squeeze;
@@ -0,0 +1,50 @@
+use failure::Fallible;
+
+use super::Context;
+use crate::{
+    command::{
+        Absorb,
+        Mask,
+        Oneof,
+        Optional,
+        Skip,
+    },
+    types::{
+        OneofMode,
+        OneofTag,
+    },
+};
+
+/// Size of the discriminant encoded with `mode` command.
+fn sizeof_oneof_tag<TW, F>(ctx: &mut Context<TW, F>, mode: OneofMode, tag: OneofTag) -> Fallible<&mut Context<TW, F>> {
+    let t = tag.to_trint3()?;
+    match mode {
+        OneofMode::Absorb => ctx.absorb(&t),
+        OneofMode::Mask => ctx.mask(&t),
+        OneofMode::Skip => ctx.skip(&t),
+    }
+}
+
+/// Only the chosen branch counts.
+impl<TW, F, C> Oneof<OneofTag, C> for Context<TW, F>
+where
+    C: for<'a> FnOnce(&'a mut Self, usize) -> Fallible<&'a mut Self>,
+{
+    fn oneof(&mut self, mode: OneofMode, tag: OneofTag, branch: C) -> Fallible<&mut Self> {
+        sizeof_oneof_tag(self, mode, tag)?;
+        branch(self, tag.index)
+    }
+}
+
+impl<TW, F, T, C> Optional<Option<T>, C> for Context<TW, F>
+where
+    C: for<'a> FnOnce(&'a mut Self, T) -> Fallible<&'a mut Self>,
+{
+    fn optional(&mut self, mode: OneofMode, value: Option<T>, some: C) -> Fallible<&mut Self> {
+        sizeof_oneof_tag(self, mode, OneofTag::new(value.is_some() as usize, 2))?;
+        match value {
+            Some(value) => some(self, value),
+            None => Ok(self),
+        }
+    }
+}
@@ -7,8 +7,13 @@ use super::Context;
 use crate::{
     command::Skip,
     types::{
+        sizeof_bytes,
         sizeof_sizet,
+        sizeof_sk_padding,
+        Bytes,
         Fallback,
+        Int,
+        NBytes,
         NTrytes,
         Size,
         SkipFallback,
@@ -17,6 +22,7 @@ use crate::{
     },
 };
 use iota_streams_core::tbits::word::BasicTbitWord;
+use iota_streams_core_ntru::key_encapsulation::ntru;
 
 /// Skipped values are just encoded.
 /// All Trint3 values are encoded with 3 trits.
@@ -99,9 +105,46 @@ where
     }
 }
 
+/// Integers are fixed-size and are encoded with `3 * T::TRYTES` trits.
+impl<'a, TW, F, T: Int> Skip<&'a T> for Context<TW, F> {
+    fn skip(&mut self, _int: &'a T) -> Fallible<&mut Self> {
+        self.size += 3 * T::TRYTES;
+        Ok(self)
+    }
+}
+
+/// `bytes` has variable size thus the size is encoded before the content bytes.
+impl<'a, TW, F> Skip<&'a Bytes> for Context<TW, F> {
+    fn skip(&mut self, bytes: &'a Bytes) -> Fallible<&mut Self> {
+        self.size += sizeof_sizet((bytes.0).len()) + sizeof_bytes((bytes.0).len());
+        Ok(self)
+    }
+}
+
+/// `byte [n]` is fixed-size.
+impl<'a, TW, F, const N: usize> Skip<&'a NBytes<N>> for Context<TW, F> {
+    fn skip(&mut self, _nbytes: &'a NBytes<N>) -> Fallible<&mut Self> {
+        self.size += sizeof_bytes(N);
+        Ok(self)
+    }
+}
+
 impl<'a, TW, F, T: 'a + SkipFallback<TW, F>> Skip<&'a Fallback<T>> for Context<TW, F> {
     fn skip(&mut self, val: &'a Fallback<T>) -> Fallible<&mut Self> {
         (val.0).sizeof_skip(self)?;
         Ok(self)
     }
 }
+
+/// NTRU private key trits are padded with zero trits up to a multiple of 3,
+/// so that it can be stored as trytes.
+impl<'a, TW, F> Skip<&'a ntru::PrivateKey<TW, F>> for Context<TW, F>
+where
+    TW: BasicTbitWord,
+{
+    fn skip(&mut self, sk: &'a ntru::PrivateKey<TW, F>) -> Fallible<&mut Self> {
+        ensure!(sk.tbits().size() == ntru::PRIVATE_KEY_SIZE);
+        self.size += ntru::PRIVATE_KEY_SIZE + sizeof_sk_padding();
+        Ok(self)
+    }
+}
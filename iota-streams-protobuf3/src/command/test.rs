@@ -15,6 +15,7 @@ use iota_streams_core::{
     },
     tbits::{
         trinary::{
+            trits_to_b1t5,
             Trit,
             TritWord,
             MAX_TRINT3,
@@ -35,6 +36,7 @@ use iota_streams_core_ntru::key_encapsulation::ntru;
 
 use crate::{
     command::*,
+    io,
     types::*,
 };
 
@@ -262,6 +264,413 @@ fn trytes() {
     assert!(dbg!(absorb_mask_squeeze_trytes_mac::<Trit, Troika>()).is_ok());
 }
 
+fn oneof_optional_branches<TW, F>() -> Fallible<()>
+where
+    TW: SpongosTbitWord + TritWord,
+    F: PRP<TW> + Default,
+{
+    let mac = Mac(81);
+    let modes = [OneofMode::Absorb, OneofMode::Mask, OneofMode::Skip];
+
+    for mode in modes.iter() {
+        for index in 0..3 {
+            let tag = OneofTag::new(index, 3);
+            let size = Size(index * 1000);
+            let t = Trint3(index as i8 - 13);
+            let opt = if index == 0 { None } else { Some(Trint3(index as i8)) };
+
+            let buf_size = sizeof::Context::<TW, F>::new()
+                .oneof(*mode, tag, |ctx, i| match i {
+                    0 => Ok(ctx),
+                    1 => ctx.absorb(&size),
+                    _ => ctx.mask(&t),
+                })?
+                .optional(*mode, opt.as_ref(), |ctx, t| ctx.absorb(t))?
+                .commit()?
+                .squeeze(&mac)?
+                .get_size();
+            let mut buf = Tbits::<TW>::zero(buf_size);
+
+            {
+                let mut ctx = wrap::Context::<TW, F, TbitSliceMut<TW>>::new(buf.slice_mut());
+                ctx.oneof(*mode, tag, |ctx, i| match i {
+                    0 => Ok(ctx),
+                    1 => ctx.absorb(&size),
+                    _ => ctx.mask(&t),
+                })?
+                .optional(*mode, opt.as_ref(), |ctx, t| ctx.absorb(t))?
+                .commit()?
+                .squeeze(&mac)?;
+                ensure!(ctx.stream.is_empty(), "Output stream is not exhausted.");
+            }
+
+            let mut tag2 = OneofTag::new(0, 3);
+            let mut size2 = Size::default();
+            let mut t2 = Trint3::default();
+            let mut opt2 = Some(MIN_TRINT3);
+            {
+                let mut ctx = unwrap::Context::<TW, F, TbitSlice<TW>>::new(buf.slice());
+                ctx.oneof(*mode, &mut tag2, |ctx, i| match i {
+                    0 => Ok(ctx),
+                    1 => ctx.absorb(&mut size2),
+                    _ => ctx.mask(&mut t2),
+                })?
+                .optional(*mode, &mut opt2, |ctx, t| ctx.absorb(t))?
+                .commit()?
+                .squeeze(&mac)?;
+                ensure!(ctx.stream.is_empty(), "Input stream is not exhausted.");
+            }
+
+            ensure!(tag == tag2, "Unwrapped bad oneof tag.");
+            ensure!(index != 1 || size == size2, "Unwrapped bad size branch.");
+            ensure!(index != 2 || t == t2, "Unwrapped bad trint3 branch.");
+            ensure!(opt == opt2, "Unwrapped bad optional value.");
+
+            // Unwrapping with fewer branches must fail for the last branch only.
+            let mut tag3 = OneofTag::new(0, 2);
+            let mut ctx = unwrap::Context::<TW, F, TbitSlice<TW>>::new(buf.slice());
+            let r = ctx.oneof(*mode, &mut tag3, |ctx, _| Ok(ctx)).map(|_| ());
+            ensure!(r.is_ok() == (index < 2), "Bad oneof tag is not detected.");
+        }
+
+        let bad_tag = OneofTag::new(3, 3);
+        ensure!(
+            sizeof::Context::<TW, F>::new().oneof(*mode, bad_tag, |ctx, _| Ok(ctx)).is_err(),
+            "Wrapped bad oneof tag."
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn oneof_optional() {
+    assert!(dbg!(oneof_optional_branches::<Trit, Troika>()).is_ok());
+}
+
+fn absorb_mask_skip_bytes_ints<TW, F>() -> Fallible<()>
+where
+    TW: SpongosTbitWord + TritWord,
+    F: PRP<TW> + Default,
+{
+    let mac = Mac(81);
+    let bytes = Bytes((0..=255).collect());
+    let nbytes = NBytes([0, 1, 127, 128, 255]);
+    let ints = (u8::MAX, u16::MAX, u32::MAX, u64::MAX, i64::MIN, i64::MAX);
+
+    let buf_size = sizeof::Context::<TW, F>::new()
+        .absorb(&bytes)?
+        .mask(&nbytes)?
+        .skip(&ints.0)?
+        .absorb(&ints.1)?
+        .mask(&ints.2)?
+        .skip(&ints.3)?
+        .absorb(&ints.4)?
+        .mask(&ints.5)?
+        .skip(&Bytes::default())?
+        .commit()?
+        .squeeze(&mac)?
+        .get_size();
+    let mut buf = Tbits::<TW>::zero(buf_size);
+
+    {
+        let mut ctx = wrap::Context::<TW, F, TbitSliceMut<TW>>::new(buf.slice_mut());
+        ctx.absorb(&bytes)?
+            .mask(&nbytes)?
+            .skip(&ints.0)?
+            .absorb(&ints.1)?
+            .mask(&ints.2)?
+            .skip(&ints.3)?
+            .absorb(&ints.4)?
+            .mask(&ints.5)?
+            .skip(&Bytes::default())?
+            .commit()?
+            .squeeze(&mac)?;
+        ensure!(ctx.stream.is_empty(), "Output stream is not exhausted.");
+    }
+
+    let mut bytes2 = Bytes::default();
+    let mut nbytes2 = NBytes::<5>::default();
+    let mut ints2 = (0_u8, 0_u16, 0_u32, 0_u64, 0_i64, 0_i64);
+    let mut empty2 = Bytes(vec![1, 2, 3]);
+    {
+        let mut ctx = unwrap::Context::<TW, F, TbitSlice<TW>>::new(buf.slice());
+        ctx.absorb(&mut bytes2)?
+            .mask(&mut nbytes2)?
+            .skip(&mut ints2.0)?
+            .absorb(&mut ints2.1)?
+            .mask(&mut ints2.2)?
+            .skip(&mut ints2.3)?
+            .absorb(&mut ints2.4)?
+            .mask(&mut ints2.5)?
+            .skip(&mut empty2)?
+            .commit()?
+            .squeeze(&mac)?;
+        ensure!(ctx.stream.is_empty(), "Input stream is not exhausted.");
+    }
+
+    ensure!(bytes == bytes2, "Unwrapped bad bytes.");
+    ensure!(nbytes == nbytes2, "Unwrapped bad nbytes.");
+    ensure!(ints == ints2, "Unwrapped bad integers.");
+    ensure!(empty2.0.is_empty(), "Unwrapped bad empty bytes.");
+
+    // Two trytes can hold values out of `u8` range.
+    let mut buf = Tbits::<TW>::zero(6);
+    wrap::Context::<TW, F, TbitSliceMut<TW>>::new(buf.slice_mut())
+        .absorb(MAX_TRINT3)?
+        .absorb(MAX_TRINT3)?;
+    let mut byte = 0_u8;
+    ensure!(
+        unwrap::Context::<TW, F, TbitSlice<TW>>::new(buf.slice())
+            .absorb(&mut byte)
+            .is_err(),
+        "Out of range byte is not detected."
+    );
+    Ok(())
+}
+
+#[test]
+fn bytes_ints() {
+    assert!(dbg!(absorb_mask_skip_bytes_ints::<Trit, Troika>()).is_ok());
+}
+
+fn unwrap_trace<TW, F>() -> Fallible<()>
+where
+    TW: SpongosTbitWord + StringTbitWord + TritWord,
+    F: PRP<TW> + Default,
+{
+    let mac = Mac(81);
+    let trytes = Trytes::<TW>(Tbits::from_str("TRACE").unwrap());
+    let int = 12345_u16;
+    let bytes = Bytes(vec![1, 2, 3]);
+
+    let buf_size = sizeof::Context::<TW, F>::new()
+        .absorb(&trytes)?
+        .mask(&int)?
+        .skip(&bytes)?
+        .commit()?
+        .squeeze(&mac)?
+        .get_size();
+    let mut buf = Tbits::<TW>::zero(buf_size);
+    {
+        let mut ctx = wrap::Context::<TW, F, TbitSliceMut<TW>>::new(buf.slice_mut());
+        ctx.absorb(&trytes)?
+            .mask(&int)?
+            .skip(&bytes)?
+            .commit()?
+            .squeeze(&mac)?;
+    }
+
+    let mut trytes2 = Trytes::<TW>::default();
+    let mut int2 = 0_u16;
+    let mut bytes2 = Bytes::default();
+    let mut ctx = unwrap::Context::<TW, F, TbitSlice<TW>>::new_with_trace(buf.slice());
+    ctx.absorb(&mut trytes2)?
+        .mask(&mut int2)?
+        .skip(&mut bytes2)?
+        .commit()?
+        .dump(format_args!("before \"mac\""))?
+        .squeeze(&mac)?;
+    let trace = ctx.trace.take().unwrap();
+
+    let fields: Vec<_> = trace
+        .entries
+        .iter()
+        .filter_map(|entry| match entry {
+            unwrap::TraceEntry::Field {
+                command,
+                offset,
+                trits,
+                plaintext,
+            } => Some((*command, *offset, trits.size(), plaintext.is_some())),
+            _ => None,
+        })
+        .collect();
+    ensure!(
+        fields
+            == vec![
+                (unwrap::TraceCommand::Absorb, 0, 6, false),
+                (unwrap::TraceCommand::Absorb, 6, 15, false),
+                (unwrap::TraceCommand::Mask, 21, 12, true),
+                (unwrap::TraceCommand::Skip, 33, 6, false),
+                (unwrap::TraceCommand::Skip, 39, 18, false),
+                (unwrap::TraceCommand::Squeeze, 57, 81, false),
+            ],
+        "Bad trace fields: {:?}.",
+        fields
+    );
+    ensure!(trace.offset() == buf_size, "Bad trace offset.");
+    match &trace.entries[trace.entries.len() - 3] {
+        unwrap::TraceEntry::Commit { offset, digest } => {
+            ensure!(*offset == 57 && digest.size() == unwrap::TRACE_DIGEST_SIZE, "Bad commit entry.");
+            match &trace.entries[trace.entries.len() - 1] {
+                unwrap::TraceEntry::Field { trits, .. } => {
+                    ensure!(digest != trits, "Commit digest reveals squeezed trits.")
+                }
+                _ => ensure!(false, "Squeeze is not traced."),
+            }
+        }
+        _ => ensure!(false, "Commit is not traced."),
+    }
+
+    let mut text = String::new();
+    trace.write(unwrap::TraceFormat::Text, &mut text)?;
+    ensure!(text.lines().count() == 8, "Bad text trace:\n{}", text);
+    ensure!(text.contains("     6     15 absorb   TRACE\n"), "Bad text trace:\n{}", text);
+    ensure!(text.contains("dump     before \"mac\"\n"), "Bad text trace:\n{}", text);
+
+    let mut json = String::new();
+    trace.write(unwrap::TraceFormat::Json, &mut json)?;
+    ensure!(
+        json.starts_with("{\"entries\":[{\"command\":\"absorb\",\"offset\":0,\"size\":6,")
+            && json.contains(",\"plaintext\":\"")
+            && json.contains("{\"command\":\"dump\",\"offset\":57,\"label\":\"before \\\"mac\\\"\"}")
+            && json.ends_with("],\"error\":null}"),
+        "Bad json trace: {}",
+        json
+    );
+
+    // Trace is recorded up to the bad MAC.
+    let t = buf.slice().drop(buf_size - 3).get3();
+    buf.slice_mut().drop(buf_size - 3).put3(Trint3((t.0 + 14) % 27 - 13));
+    let mut ctx = unwrap::Context::<TW, F, TbitSlice<TW>>::new_with_trace(buf.slice());
+    let r = ctx
+        .absorb(&mut trytes2)
+        .and_then(|ctx| ctx.mask(&mut int2))
+        .and_then(|ctx| ctx.skip(&mut bytes2))
+        .and_then(|ctx| ctx.commit())
+        .and_then(|ctx| ctx.squeeze(&mac))
+        .map(|_| ());
+    ensure!(r.is_err(), "Bad MAC is not detected.");
+    ensure!(ctx.trace.unwrap().entries.len() == 7, "Trace is not recorded up to the bad MAC.");
+    Ok(())
+}
+
+#[test]
+fn trace() {
+    assert!(dbg!(unwrap_trace::<Trit, Troika>()).is_ok());
+}
+
+fn wrap_chunked<TW, F, OS: io::OStream<TW>>(os: OS, payload: &Trytes<TW>, key: &NTrytes<TW>) -> Fallible<OS>
+where
+    TW: SpongosTbitWord + TritWord,
+    F: PRP<TW> + Default,
+{
+    let mut ctx = wrap::Context::<TW, F, OS>::new(os);
+    ctx.absorb(payload)?
+        .mask(key)?
+        .skip(&Size(payload.0.size()))?
+        .commit()?
+        .squeeze(&Mac(81))?;
+    ctx.stream.commit();
+    Ok(ctx.stream)
+}
+
+fn unwrap_chunked<TW, F, IS: io::IStream<TW>>(is: IS, payload: &Trytes<TW>, key: &NTrytes<TW>) -> Fallible<IS>
+where
+    TW: SpongosTbitWord + TritWord,
+    F: PRP<TW> + Default,
+{
+    let mut payload2 = Trytes::<TW>::default();
+    let mut key2 = NTrytes::<TW>(Tbits::zero(key.0.size()));
+    let mut size2 = Size(0);
+    let mut ctx = unwrap::Context::<TW, F, IS>::new(is);
+    ctx.absorb(&mut payload2)?
+        .mask(&mut key2)?
+        .skip(&mut size2)?
+        .commit()?
+        .squeeze(&Mac(81))?;
+    ensure!(payload == &payload2 && key == &key2, "Unwrapped bad fields.");
+    ensure!(size2 == Size(payload.0.size()), "Unwrapped bad size.");
+    Ok(ctx.stream)
+}
+
+fn chunked_wrap_unwrap<TW, F>() -> Fallible<()>
+where
+    TW: SpongosTbitWord + StringTbitWord + TritWord,
+    F: PRP<TW> + Default,
+{
+    let payload = Trytes::<TW>(Tbits::cycle_str(999, "PAYLOAD"));
+    let key = NTrytes::<TW>(Tbits::cycle_str(243, "KEY"));
+
+    let buf_size = sizeof::Context::<TW, F>::new()
+        .absorb(&payload)?
+        .mask(&key)?
+        .skip(&Size(payload.0.size()))?
+        .commit()?
+        .squeeze(&Mac(81))?
+        .get_size();
+    let mut buf = Tbits::<TW>::zero(buf_size);
+    wrap_chunked::<TW, F, _>(buf.slice_mut(), &payload, &key)?;
+
+    for chunk_size in [1, 2, 81, 100, buf_size, buf_size + 1].iter() {
+        let chunks = wrap_chunked::<TW, F, _>(io::ChunkedOStream::new(*chunk_size), &payload, &key)?.into_chunks();
+        ensure!(chunks.len() == (buf_size + chunk_size - 1) / chunk_size, "Bad number of fragments.");
+        ensure!(
+            chunks[..chunks.len() - 1].iter().all(|c| c.size() == *chunk_size),
+            "Bad fragment size."
+        );
+        ensure!(buf == Tbits::from_slices(&chunks.iter().map(Tbits::slice).collect::<Vec<_>>()));
+
+        let is = unwrap_chunked::<TW, F, _>(io::ChunkedIStream::new(&chunks), &payload, &key)?;
+        ensure!(is.is_empty(), "Input fragments are not exhausted.");
+    }
+
+    // Fragments of different sizes.
+    let mut slice = buf.slice();
+    let mut chunks = Vec::new();
+    for n in (1..).map(|i| i * 7 % 50) {
+        chunks.push(Tbits::from_slice(slice.advance_min(n)));
+        if slice.is_empty() {
+            break;
+        }
+    }
+    unwrap_chunked::<TW, F, _>(io::ChunkedIStream::new(&chunks), &payload, &key)?;
+    chunks.pop();
+    ensure!(
+        unwrap_chunked::<TW, F, _>(io::ChunkedIStream::new(&chunks), &payload, &key).is_err(),
+        "Truncated fragments unwrapped."
+    );
+    Ok(())
+}
+
+#[test]
+fn chunked() {
+    assert!(dbg!(chunked_wrap_unwrap::<Trit, Troika>()).is_ok());
+}
+
+fn b1t5_wrap_unwrap<TW, F>() -> Fallible<()>
+where
+    TW: SpongosTbitWord + StringTbitWord + TritWord,
+    F: PRP<TW> + Default,
+{
+    let payload = Trytes::<TW>(Tbits::cycle_str(999, "PAYLOAD"));
+    let key = NTrytes::<TW>(Tbits::cycle_str(243, "KEY"));
+
+    let chunks = wrap_chunked::<TW, F, _>(io::ChunkedOStream::new(6561), &payload, &key)?.into_chunks();
+    let bytes = wrap_chunked::<TW, F, _>(io::WriteOStream::new(Vec::new()), &payload, &key)?.finish()?;
+    ensure!(bytes == trits_to_b1t5(chunks[0].slice()), "Bad B1T5 encoding.");
+
+    let is = unwrap_chunked::<TW, F, _>(io::ReadIStream::new(&bytes[..]), &payload, &key)?;
+    ensure!(is.finish()?.is_empty(), "Input bytes are not exhausted.");
+
+    ensure!(
+        unwrap_chunked::<TW, F, _>(io::ReadIStream::new(&bytes[..bytes.len() - 1]), &payload, &key).is_err(),
+        "Truncated bytes unwrapped."
+    );
+    let mut bad = bytes.clone();
+    bad[0] = 255;
+    ensure!(
+        unwrap_chunked::<TW, F, _>(io::ReadIStream::new(&bad[..]), &payload, &key).is_err(),
+        "Invalid byte unwrapped."
+    );
+    Ok(())
+}
+
+#[test]
+fn b1t5() {
+    assert!(dbg!(b1t5_wrap_unwrap::<Trit, Troika>()).is_ok());
+}
+
 fn mssig_traverse<TW, F, P>() -> Fallible<()>
 where
     TW: StringTbitWord + IntTbitWord + SpongosTbitWord + TritWord,
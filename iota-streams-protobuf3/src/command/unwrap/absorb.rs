@@ -7,13 +7,18 @@ use std::mem;
 use super::{
     unwrap::*,
     Context,
+    Trace,
+    TraceCommand,
 };
 use crate::{
     command::Absorb,
     io,
     types::{
         AbsorbFallback,
+        Bytes,
         Fallback,
+        Int,
+        NBytes,
         NTrytes,
         Size,
         Trint3,
@@ -54,15 +59,24 @@ where
     fn unwrap3(&mut self, trint3: &mut Trint3) -> Fallible<&mut Self> {
         let slice = self.ctx.stream.try_advance(3)?;
         *trint3 = slice.get3();
+        if let Some(trace) = &mut self.ctx.trace {
+            trace.field(TraceCommand::Absorb, slice, None);
+        }
         self.ctx.spongos.absorb(slice);
         Ok(self)
     }
     fn unwrapn(&mut self, trits: TbitSliceMut<TW>) -> Fallible<&mut Self> {
         let slice = self.ctx.stream.try_advance(trits.size())?;
         slice.copy(&trits);
+        if let Some(trace) = &mut self.ctx.trace {
+            trace.field(TraceCommand::Absorb, slice, None);
+        }
         self.ctx.spongos.absorb(unsafe { trits.as_const() });
         Ok(self)
     }
+    fn trace(&mut self) -> Option<&mut Trace<TW>> {
+        self.ctx.trace.as_mut()
+    }
 }
 
 fn unwrap_absorb_trint3<'a, TW, F, IS: io::IStream<TW>>(
@@ -95,6 +109,37 @@ where
 {
     ctx.unwrapn(trits)
 }
+fn unwrap_absorb_int<'a, TW, F, IS: io::IStream<TW>, T: Int>(
+    ctx: &'a mut AbsorbContext<TW, F, IS>,
+    int: &mut T,
+) -> Fallible<&'a mut AbsorbContext<TW, F, IS>>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    unwrap_int(ctx, int)
+}
+fn unwrap_absorb_bytes<'a, TW, F, IS: io::IStream<TW>>(
+    ctx: &'a mut AbsorbContext<TW, F, IS>,
+    bytes: &mut [u8],
+) -> Fallible<&'a mut AbsorbContext<TW, F, IS>>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    unwrap_bytes(ctx, bytes)
+}
+fn unwrap_absorb_bytes_vec<'a, TW, F, IS: io::IStream<TW>>(
+    ctx: &'a mut AbsorbContext<TW, F, IS>,
+    n: usize,
+    bytes: &mut Vec<u8>,
+) -> Fallible<&'a mut AbsorbContext<TW, F, IS>>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    unwrap_bytes_vec(ctx, n, bytes)
+}
 
 impl<TW, F, IS: io::IStream<TW>> Absorb<&mut Trint3> for Context<TW, F, IS>
 where
@@ -139,6 +184,38 @@ where
     }
 }
 
+impl<'a, TW, F, IS: io::IStream<TW>, T: Int> Absorb<&'a mut T> for Context<TW, F, IS>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    fn absorb(&mut self, int: &'a mut T) -> Fallible<&mut Self> {
+        Ok(unwrap_absorb_int(self.as_mut(), int)?.as_mut())
+    }
+}
+
+impl<'a, TW, F, IS: io::IStream<TW>> Absorb<&'a mut Bytes> for Context<TW, F, IS>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    fn absorb(&mut self, bytes: &'a mut Bytes) -> Fallible<&mut Self> {
+        let mut size = Size(0);
+        self.absorb(&mut size)?;
+        Ok(unwrap_absorb_bytes_vec(self.as_mut(), size.0, &mut bytes.0)?.as_mut())
+    }
+}
+
+impl<'a, TW, F, IS: io::IStream<TW>, const N: usize> Absorb<&'a mut NBytes<N>> for Context<TW, F, IS>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    fn absorb(&mut self, nbytes: &'a mut NBytes<N>) -> Fallible<&mut Self> {
+        Ok(unwrap_absorb_bytes(self.as_mut(), &mut nbytes.0)?.as_mut())
+    }
+}
+
 impl<'a, TW, F, IS: io::IStream<TW>, P> Absorb<&'a mut mss::PublicKey<TW, P>> for Context<TW, F, IS>
 where
     TW: SpongosTbitWord + trinary::TritWord,
@@ -1,9 +1,15 @@
 use failure::Fallible;
 
-use super::Context;
+use super::{
+    Context,
+    TRACE_DIGEST_SIZE,
+};
 use crate::command::Commit;
 use iota_streams_core::{
-    sponge::prp::PRP,
+    sponge::prp::{
+        troika::Troika,
+        PRP,
+    },
     tbits::{
         trinary,
         word::SpongosTbitWord,
@@ -18,6 +24,9 @@ where
 {
     fn commit(&mut self) -> Fallible<&mut Self> {
         self.spongos.commit();
+        if let Some(trace) = &mut self.trace {
+            trace.commit(self.spongos.digest_tbits::<Troika>(TRACE_DIGEST_SIZE)?);
+        }
         Ok(self)
     }
 }
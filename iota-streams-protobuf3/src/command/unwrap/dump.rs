@@ -12,6 +12,11 @@ where
     TW: BasicTbitWord,
 {
     fn dump<'a>(&mut self, args: std::fmt::Arguments<'a>) -> Fallible<&mut Self> {
+        if let Some(trace) = &mut self.trace {
+            trace.dump(args.to_string());
+            return Ok(self);
+        }
+
         #[cfg(not(test))]
         println!("dump: {}", args,);
 
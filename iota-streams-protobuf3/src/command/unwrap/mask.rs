@@ -7,11 +7,16 @@ use std::mem;
 use super::{
     unwrap::*,
     Context,
+    Trace,
+    TraceCommand,
 };
 use crate::{
     command::Mask,
     io,
     types::{
+        Bytes,
+        Int,
+        NBytes,
         NTrytes,
         Size,
         Trint3,
@@ -64,6 +69,9 @@ where
         {
             let t3 = TbitSlice::<TW>::from_slice(3, &buf);
             *trint3 = t3.get3();
+            if let Some(trace) = &mut self.ctx.trace {
+                trace.field(TraceCommand::Mask, slice, Some(t3));
+            }
         }
         Ok(self)
     }
@@ -71,8 +79,14 @@ where
         let slice = self.ctx.stream.try_advance(trits.size())?;
         slice.copy(&trits);
         self.ctx.spongos.decrypt_mut(&mut trits);
+        if let Some(trace) = &mut self.ctx.trace {
+            trace.field(TraceCommand::Mask, slice, Some(unsafe { trits.as_const() }));
+        }
         Ok(self)
     }
+    fn trace(&mut self) -> Option<&mut Trace<TW>> {
+        self.ctx.trace.as_mut()
+    }
 }
 
 fn unwrap_mask_trint3<'a, TW, F, IS: io::IStream<TW>>(
@@ -105,6 +119,37 @@ where
 {
     ctx.unwrapn(trits)
 }
+fn unwrap_mask_int<'a, TW, F, IS: io::IStream<TW>, T: Int>(
+    ctx: &'a mut MaskContext<TW, F, IS>,
+    int: &mut T,
+) -> Fallible<&'a mut MaskContext<TW, F, IS>>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    unwrap_int(ctx, int)
+}
+fn unwrap_mask_bytes<'a, TW, F, IS: io::IStream<TW>>(
+    ctx: &'a mut MaskContext<TW, F, IS>,
+    bytes: &mut [u8],
+) -> Fallible<&'a mut MaskContext<TW, F, IS>>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    unwrap_bytes(ctx, bytes)
+}
+fn unwrap_mask_bytes_vec<'a, TW, F, IS: io::IStream<TW>>(
+    ctx: &'a mut MaskContext<TW, F, IS>,
+    n: usize,
+    bytes: &mut Vec<u8>,
+) -> Fallible<&'a mut MaskContext<TW, F, IS>>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    unwrap_bytes_vec(ctx, n, bytes)
+}
 
 impl<'a, TW, F, IS: io::IStream<TW>> Mask<&'a mut Trint3> for Context<TW, F, IS>
 where
@@ -149,6 +194,38 @@ where
     }
 }
 
+impl<'a, TW, F, IS: io::IStream<TW>, T: Int> Mask<&'a mut T> for Context<TW, F, IS>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    fn mask(&mut self, int: &'a mut T) -> Fallible<&mut Self> {
+        Ok(unwrap_mask_int(self.as_mut(), int)?.as_mut())
+    }
+}
+
+impl<'a, TW, F, IS: io::IStream<TW>> Mask<&'a mut Bytes> for Context<TW, F, IS>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    fn mask(&mut self, bytes: &'a mut Bytes) -> Fallible<&mut Self> {
+        let mut size = Size(0);
+        self.mask(&mut size)?;
+        Ok(unwrap_mask_bytes_vec(self.as_mut(), size.0, &mut bytes.0)?.as_mut())
+    }
+}
+
+impl<'a, TW, F, IS: io::IStream<TW>, const N: usize> Mask<&'a mut NBytes<N>> for Context<TW, F, IS>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    fn mask(&mut self, nbytes: &'a mut NBytes<N>) -> Fallible<&mut Self> {
+        Ok(unwrap_mask_bytes(self.as_mut(), &mut nbytes.0)?.as_mut())
+    }
+}
+
 impl<'a, TW, F, IS: io::IStream<TW>> Mask<&'a mut ntru::PublicKey<TW, F>> for Context<TW, F, IS>
 where
     TW: SpongosTbitWord + trinary::TritWord,
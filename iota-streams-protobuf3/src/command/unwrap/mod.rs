@@ -11,13 +11,18 @@ use iota_streams_core::{
         prp::PRP,
         spongos::*,
     },
-    tbits::word::SpongosTbitWord,
+    tbits::word::{
+        BasicTbitWord,
+        SpongosTbitWord,
+    },
 };
 
 //#[derive(Debug)]
 pub struct Context<TW, F, IS> {
     pub spongos: Spongos<TW, F>,
     pub stream: IS,
+    /// Trace of unwrapped fields, `None` unless created with `new_with_trace`.
+    pub trace: Option<Trace<TW>>,
 }
 
 impl<TW, F, IS> Context<TW, F, IS>
@@ -29,13 +34,29 @@ where
         Self {
             spongos: Spongos::<TW, F>::init(),
             stream: stream,
+            trace: None,
+        }
+    }
+
+    /// Create context recording a trace of unwrapped fields.
+    pub fn new_with_trace(stream: IS) -> Self {
+        Self {
+            spongos: Spongos::<TW, F>::init(),
+            stream,
+            trace: Some(Trace::new()),
         }
     }
 }
 
-impl<TW, F, IS: io::IStream<TW>> Context<TW, F, IS> {
+impl<TW, F, IS: io::IStream<TW>> Context<TW, F, IS>
+where
+    TW: BasicTbitWord,
+{
     pub fn drop(&mut self, n: Size) -> Fallible<&mut Self> {
-        self.stream.try_advance(n.0)?;
+        let slice = self.stream.try_advance(n.0)?;
+        if let Some(trace) = &mut self.trace {
+            trace.field(TraceCommand::Drop, slice, None);
+        }
         Ok(self)
         //<IS as io::IStream<TW>>::try_advance(&mut self.stream, n)
     }
@@ -51,6 +72,7 @@ where
         Self {
             spongos: self.spongos.clone(),
             stream: self.stream.clone(),
+            trace: self.trace.clone(),
         }
     }
 }
@@ -65,6 +87,7 @@ mod fork;
 mod guard;
 mod join;
 mod mask;
+mod oneof;
 mod repeated;
 mod skip;
 mod squeeze;
@@ -73,6 +96,8 @@ mod squeeze_external;
 mod mssig;
 mod ntrukem;
 
+mod trace;
+
 pub use absorb::*;
 pub use absorb_external::*;
 pub use commit::*;
@@ -88,3 +113,5 @@ pub use squeeze_external::*;
 
 pub use mssig::*;
 pub use ntrukem::*;
+
+pub use trace::*;
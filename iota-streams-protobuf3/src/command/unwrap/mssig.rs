@@ -3,7 +3,10 @@ use failure::{
     Fallible,
 };
 
-use super::Context;
+use super::{
+    Context,
+    TraceCommand,
+};
 use crate::{
     command::{
         Commit,
@@ -54,11 +57,19 @@ where
         );
 
         let skn_slice = self.stream.try_advance(P::SKN_SIZE)?;
+        if let Some(trace) = &mut self.trace {
+            trace.begin();
+            trace.field(TraceCommand::Mssig, skn_slice, None);
+        }
         let d_skn = mss::parse_skn::<TW, P>(skn_slice);
         ensure!(d_skn.is_some(), "Failed to parse MSS signature skn: {:?}.", skn_slice);
         let (d, skn) = d_skn.unwrap();
         let n = P::apath_size(d);
         let wotsig_apath_slice = self.stream.try_advance(P::WotsParameters::SIGNATURE_SIZE + n)?;
+        if let Some(trace) = &mut self.trace {
+            trace.field(TraceCommand::Mssig, wotsig_apath_slice, None);
+            trace.end();
+        }
         let (wotsig, apath) = wotsig_apath_slice.split_at(P::WotsParameters::SIGNATURE_SIZE);
         mss::recover_apk::<TW, P>(d, skn, ((hash.0).0).slice(), wotsig, apath, apk.tbits_mut().slice_mut());
         Ok(self)
@@ -3,7 +3,10 @@ use failure::{
     Fallible,
 };
 
-use super::Context;
+use super::{
+    Context,
+    TraceCommand,
+};
 use crate::{
     command::Ntrukem,
     io,
@@ -27,6 +30,9 @@ where
         //TODO: ensure!(ntru::KEY_SIZE == (secret.0).size(), "Trit size of `external tryte secret[n]` to be encapsulated with NTRU must be equal {} trits.", ntru::KEY_SIZE);
 
         let ekey_slice = self.stream.try_advance(ntru::EKEY_SIZE)?;
+        if let Some(trace) = &mut self.trace {
+            trace.field(TraceCommand::Ntrukem, ekey_slice, None);
+        }
         ensure!(
             sk.decrypt_with_spongos(&mut self.spongos, ekey_slice, (secret.0).slice_mut()),
             "Failed to decapsulate secret."
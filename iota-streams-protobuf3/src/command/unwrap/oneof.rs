@@ -0,0 +1,78 @@
+use failure::Fallible;
+
+use super::Context;
+use crate::{
+    command::{
+        Absorb,
+        Mask,
+        Oneof,
+        Optional,
+        Skip,
+    },
+    io,
+    types::{
+        OneofMode,
+        OneofTag,
+        Trint3,
+    },
+};
+use iota_streams_core::{
+    sponge::prp::PRP,
+    tbits::{
+        trinary,
+        word::SpongosTbitWord,
+    },
+};
+
+fn unwrap_oneof_tag<'c, TW, F, IS: io::IStream<TW>>(
+    ctx: &'c mut Context<TW, F, IS>,
+    mode: OneofMode,
+    tag: &mut OneofTag,
+) -> Fallible<&'c mut Context<TW, F, IS>>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    let mut t = Trint3(-1);
+    match mode {
+        OneofMode::Absorb => ctx.absorb(&mut t)?,
+        OneofMode::Mask => ctx.mask(&mut t)?,
+        OneofMode::Skip => ctx.skip(&mut t)?,
+    };
+    tag.set_trint3(t)?;
+    Ok(ctx)
+}
+
+impl<'b, TW, F, IS: io::IStream<TW>, C> Oneof<&'b mut OneofTag, C> for Context<TW, F, IS>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+    C: for<'a> FnOnce(&'a mut Self, usize) -> Fallible<&'a mut Self>,
+{
+    fn oneof(&mut self, mode: OneofMode, tag: &'b mut OneofTag, branch: C) -> Fallible<&mut Self> {
+        unwrap_oneof_tag(self, mode, tag)?;
+        branch(self, tag.index)
+    }
+}
+
+/// The value is replaced with `None` or with the unwrapped value starting from the default.
+impl<'b, TW, F, IS: io::IStream<TW>, T, C> Optional<&'b mut Option<T>, C> for Context<TW, F, IS>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+    T: Default,
+    C: for<'a, 'v> FnOnce(&'a mut Self, &'v mut T) -> Fallible<&'a mut Self>,
+{
+    fn optional(&mut self, mode: OneofMode, value: &'b mut Option<T>, some: C) -> Fallible<&mut Self> {
+        let mut tag = OneofTag::new(0, 2);
+        unwrap_oneof_tag(self, mode, &mut tag)?;
+        *value = if tag.index == 1 {
+            let mut v = T::default();
+            some(self, &mut v)?;
+            Some(v)
+        } else {
+            None
+        };
+        Ok(self)
+    }
+}
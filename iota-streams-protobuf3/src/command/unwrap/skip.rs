@@ -1,15 +1,24 @@
-use failure::Fallible;
+use failure::{
+    ensure,
+    Fallible,
+};
 use std::mem;
 
 use super::{
     unwrap::*,
     Context,
+    Trace,
+    TraceCommand,
 };
 use crate::{
     command::Skip,
     io,
     types::{
+        sizeof_sk_padding,
+        Bytes,
         Fallback,
+        Int,
+        NBytes,
         NTrytes,
         Size,
         SkipFallback,
@@ -23,6 +32,7 @@ use iota_streams_core::tbits::{
     TbitSliceMut,
     Tbits,
 };
+use iota_streams_core_ntru::key_encapsulation::ntru;
 
 struct SkipContext<TW, F, IS> {
     ctx: Context<TW, F, IS>,
@@ -45,13 +55,22 @@ where
     fn unwrap3(&mut self, trint3: &mut Trint3) -> Fallible<&mut Self> {
         let slice = self.ctx.stream.try_advance(3)?;
         *trint3 = slice.get3();
+        if let Some(trace) = &mut self.ctx.trace {
+            trace.field(TraceCommand::Skip, slice, None);
+        }
         Ok(self)
     }
     fn unwrapn(&mut self, trits: TbitSliceMut<TW>) -> Fallible<&mut Self> {
         let slice = self.ctx.stream.try_advance(trits.size())?;
         slice.copy(&trits);
+        if let Some(trace) = &mut self.ctx.trace {
+            trace.field(TraceCommand::Skip, slice, None);
+        }
         Ok(self)
     }
+    fn trace(&mut self) -> Option<&mut Trace<TW>> {
+        self.ctx.trace.as_mut()
+    }
 }
 
 fn unwrap_skip_trint3<'a, TW, F, IS: io::IStream<TW>>(
@@ -81,6 +100,34 @@ where
 {
     ctx.unwrapn(trits)
 }
+fn unwrap_skip_int<'a, TW, F, IS: io::IStream<TW>, T: Int>(
+    ctx: &'a mut SkipContext<TW, F, IS>,
+    int: &mut T,
+) -> Fallible<&'a mut SkipContext<TW, F, IS>>
+where
+    TW: BasicTbitWord + trinary::TritWord,
+{
+    unwrap_int(ctx, int)
+}
+fn unwrap_skip_bytes<'a, TW, F, IS: io::IStream<TW>>(
+    ctx: &'a mut SkipContext<TW, F, IS>,
+    bytes: &mut [u8],
+) -> Fallible<&'a mut SkipContext<TW, F, IS>>
+where
+    TW: BasicTbitWord + trinary::TritWord,
+{
+    unwrap_bytes(ctx, bytes)
+}
+fn unwrap_skip_bytes_vec<'a, TW, F, IS: io::IStream<TW>>(
+    ctx: &'a mut SkipContext<TW, F, IS>,
+    n: usize,
+    bytes: &mut Vec<u8>,
+) -> Fallible<&'a mut SkipContext<TW, F, IS>>
+where
+    TW: BasicTbitWord + trinary::TritWord,
+{
+    unwrap_bytes_vec(ctx, n, bytes)
+}
 
 impl<'a, TW, F, IS: io::IStream<TW>> Skip<&'a mut Trint3> for Context<TW, F, IS>
 where
@@ -121,9 +168,52 @@ where
     }
 }
 
+impl<'a, TW, F, IS: io::IStream<TW>, T: Int> Skip<&'a mut T> for Context<TW, F, IS>
+where
+    TW: BasicTbitWord + trinary::TritWord,
+{
+    fn skip(&mut self, int: &'a mut T) -> Fallible<&mut Self> {
+        Ok(unwrap_skip_int(self.as_mut(), int)?.as_mut())
+    }
+}
+
+impl<'a, TW, F, IS: io::IStream<TW>> Skip<&'a mut Bytes> for Context<TW, F, IS>
+where
+    TW: BasicTbitWord + trinary::TritWord,
+{
+    fn skip(&mut self, bytes: &'a mut Bytes) -> Fallible<&mut Self> {
+        let mut size = Size(0);
+        self.skip(&mut size)?;
+        Ok(unwrap_skip_bytes_vec(self.as_mut(), size.0, &mut bytes.0)?.as_mut())
+    }
+}
+
+impl<'a, TW, F, IS: io::IStream<TW>, const N: usize> Skip<&'a mut NBytes<N>> for Context<TW, F, IS>
+where
+    TW: BasicTbitWord + trinary::TritWord,
+{
+    fn skip(&mut self, nbytes: &'a mut NBytes<N>) -> Fallible<&mut Self> {
+        Ok(unwrap_skip_bytes(self.as_mut(), &mut nbytes.0)?.as_mut())
+    }
+}
+
 impl<'a, TW, F, T: 'a + SkipFallback<TW, F>, IS: io::IStream<TW>> Skip<&'a mut Fallback<T>> for Context<TW, F, IS> {
     fn skip(&mut self, val: &'a mut Fallback<T>) -> Fallible<&mut Self> {
         (val.0).unwrap_skip(self)?;
         Ok(self)
     }
 }
+
+impl<'a, TW, F, IS: io::IStream<TW>> Skip<&'a mut ntru::PrivateKey<TW, F>> for Context<TW, F, IS>
+where
+    TW: BasicTbitWord + trinary::TritWord,
+{
+    fn skip(&mut self, sk: &'a mut ntru::PrivateKey<TW, F>) -> Fallible<&mut Self> {
+        let mut padding = Tbits::<TW>::zero(sizeof_sk_padding());
+        unwrap_skip_trits(self.as_mut(), sk.tbits_mut().slice_mut())?;
+        unwrap_skip_trits(self.as_mut(), padding.slice_mut())?;
+        ensure!(padding == Tbits::zero(sizeof_sk_padding()), "Bad NTRU private key padding.");
+        ensure!(sk.validate(), "Bad NTRU private key.");
+        Ok(self)
+    }
+}
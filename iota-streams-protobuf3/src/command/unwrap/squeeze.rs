@@ -3,7 +3,10 @@ use failure::{
     Fallible,
 };
 
-use super::Context;
+use super::{
+    Context,
+    TraceCommand,
+};
 use crate::{
     command::Squeeze,
     io,
@@ -24,10 +27,11 @@ where
     F: PRP<TW>,
 {
     fn squeeze(&mut self, val: &'a Mac) -> Fallible<&mut Self> {
-        ensure!(
-            self.spongos.squeeze_eq(self.stream.try_advance(val.0)?),
-            "Integrity is violated, bad MAC."
-        );
+        let slice = self.stream.try_advance(val.0)?;
+        if let Some(trace) = &mut self.trace {
+            trace.field(TraceCommand::Squeeze, slice, None);
+        }
+        ensure!(self.spongos.squeeze_eq(slice), "Integrity is violated, bad MAC.");
         Ok(self)
     }
 }
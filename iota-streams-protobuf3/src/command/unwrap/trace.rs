@@ -0,0 +1,284 @@
+//! Trace of unwrapped fields for debugging.
+//!
+//! Unwrap context created with `Context::new_with_trace` records every field read from the input
+//! stream: its offset and size, the command and the unwrapped trits. Masked fields also record
+//! decrypted plaintext, commits record the spongos state digest and `dump` commands record their
+//! labels. The trace can then be written as text or JSON into any `fmt::Write`.
+
+use std::fmt;
+
+use iota_streams_core::tbits::{
+    word::{
+        BasicTbitWord,
+        StringTbitWord,
+    },
+    TbitSlice,
+    Tbits,
+};
+
+/// Size of spongos state digest recorded after commit.
+pub const TRACE_DIGEST_SIZE: usize = 81;
+
+/// Command reading a field from the input stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceCommand {
+    Absorb,
+    Mask,
+    Skip,
+    Squeeze,
+    Mssig,
+    Ntrukem,
+    Drop,
+}
+
+impl TraceCommand {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TraceCommand::Absorb => "absorb",
+            TraceCommand::Mask => "mask",
+            TraceCommand::Skip => "skip",
+            TraceCommand::Squeeze => "squeeze",
+            TraceCommand::Mssig => "mssig",
+            TraceCommand::Ntrukem => "ntrukem",
+            TraceCommand::Drop => "drop",
+        }
+    }
+}
+
+/// Trace entry, offsets are in trits from the start of the stream.
+#[derive(Clone)]
+pub enum TraceEntry<TW> {
+    /// Field read from the stream. `trits` are the stream trits, `plaintext` are decrypted trits of masked fields.
+    Field {
+        command: TraceCommand,
+        offset: usize,
+        trits: Tbits<TW>,
+        plaintext: Option<Tbits<TW>>,
+    },
+    /// Spongos state committed, `digest` is `TRACE_DIGEST_SIZE` trits of one-way digest of the committed state.
+    Commit { offset: usize, digest: Tbits<TW> },
+    /// Label of `dump` command.
+    Dump { offset: usize, label: String },
+}
+
+/// Output format of `Trace::write`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// One line per entry: offset, size, command and trytes.
+    Text,
+    /// Object `{"entries": [...], "error": ...}`.
+    Json,
+}
+
+/// Trace of unwrapped fields.
+#[derive(Clone)]
+pub struct Trace<TW> {
+    pub entries: Vec<TraceEntry<TW>>,
+    /// Error that stopped unwrapping, if any.
+    pub error: Option<String>,
+    /// Current offset in the stream.
+    offset: usize,
+    /// Depth of nested fields, trits of nested fields are appended to the outermost one.
+    depth: usize,
+    /// Whether the current outermost field has been started.
+    started: bool,
+}
+
+impl<TW> Default for Trace<TW> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            error: None,
+            offset: 0,
+            depth: 0,
+            started: false,
+        }
+    }
+}
+
+impl<TW> Trace<TW> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current offset in the stream.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Start a field consisting of several reads, eg. `size_t` or `bytes`.
+    pub(crate) fn begin(&mut self) {
+        if self.depth == 0 {
+            self.started = false;
+        }
+        self.depth += 1;
+    }
+
+    /// End field started with `begin`.
+    pub(crate) fn end(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth == 0 {
+            self.started = false;
+        }
+    }
+}
+
+impl<TW> Trace<TW>
+where
+    TW: BasicTbitWord,
+{
+    /// Record `trits` read from the stream.
+    pub(crate) fn field(&mut self, command: TraceCommand, trits: TbitSlice<TW>, plaintext: Option<TbitSlice<TW>>) {
+        self.offset += trits.size();
+        if self.started {
+            if let Some(TraceEntry::Field {
+                command: c,
+                trits: t,
+                plaintext: p,
+                ..
+            }) = self.entries.last_mut()
+            {
+                if *c == command {
+                    *t += &Tbits::from_slice(trits);
+                    if let (Some(p), Some(plaintext)) = (p, plaintext) {
+                        *p += &Tbits::from_slice(plaintext);
+                    }
+                    return;
+                }
+            }
+        }
+        self.started = self.depth > 0;
+        self.entries.push(TraceEntry::Field {
+            command,
+            offset: self.offset - trits.size(),
+            trits: Tbits::from_slice(trits),
+            plaintext: plaintext.map(Tbits::from_slice),
+        });
+    }
+
+    pub(crate) fn commit(&mut self, digest: Tbits<TW>) {
+        self.entries.push(TraceEntry::Commit {
+            offset: self.offset,
+            digest,
+        });
+    }
+
+    pub(crate) fn dump(&mut self, label: String) {
+        self.entries.push(TraceEntry::Dump {
+            offset: self.offset,
+            label,
+        });
+    }
+}
+
+fn write_json_str<W: fmt::Write>(w: &mut W, s: &str) -> fmt::Result {
+    w.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => w.write_str("\\\"")?,
+            '\\' => w.write_str("\\\\")?,
+            '\n' => w.write_str("\\n")?,
+            c if (c as u32) < 0x20 => write!(w, "\\u{:04x}", c as u32)?,
+            c => w.write_char(c)?,
+        }
+    }
+    w.write_char('"')
+}
+
+impl<TW> Trace<TW>
+where
+    TW: StringTbitWord,
+{
+    pub fn write<W: fmt::Write>(&self, format: TraceFormat, w: &mut W) -> fmt::Result {
+        match format {
+            TraceFormat::Text => self.write_text(w),
+            TraceFormat::Json => self.write_json(w),
+        }
+    }
+
+    pub fn write_text<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        for entry in &self.entries {
+            match entry {
+                TraceEntry::Field {
+                    command,
+                    offset,
+                    trits,
+                    plaintext,
+                } => {
+                    write!(w, "{:>6} {:>6} {:<8} {}", offset, trits.size(), command.as_str(), trits)?;
+                    if let Some(plaintext) = plaintext {
+                        write!(w, " -> {}", plaintext)?;
+                    }
+                    writeln!(w)?;
+                }
+                TraceEntry::Commit { offset, digest } => {
+                    writeln!(w, "{:>6} {:>6} {:<8} {}", offset, "", "commit", digest)?;
+                }
+                TraceEntry::Dump { offset, label } => {
+                    writeln!(w, "{:>6} {:>6} {:<8} {}", offset, "", "dump", label)?;
+                }
+            }
+        }
+        if let Some(error) = &self.error {
+            writeln!(w, "error: {}", error)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_json<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        w.write_str("{\"entries\":[")?;
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i != 0 {
+                w.write_char(',')?;
+            }
+            match entry {
+                TraceEntry::Field {
+                    command,
+                    offset,
+                    trits,
+                    plaintext,
+                } => {
+                    write!(
+                        w,
+                        "{{\"command\":\"{}\",\"offset\":{},\"size\":{},\"trits\":",
+                        command.as_str(),
+                        offset,
+                        trits.size()
+                    )?;
+                    write_json_str(w, &trits.to_string())?;
+                    if let Some(plaintext) = plaintext {
+                        w.write_str(",\"plaintext\":")?;
+                        write_json_str(w, &plaintext.to_string())?;
+                    }
+                    w.write_char('}')?;
+                }
+                TraceEntry::Commit { offset, digest } => {
+                    write!(w, "{{\"command\":\"commit\",\"offset\":{},\"digest\":", offset)?;
+                    write_json_str(w, &digest.to_string())?;
+                    w.write_char('}')?;
+                }
+                TraceEntry::Dump { offset, label } => {
+                    write!(w, "{{\"command\":\"dump\",\"offset\":{},\"label\":", offset)?;
+                    write_json_str(w, label)?;
+                    w.write_char('}')?;
+                }
+            }
+        }
+        w.write_str("],\"error\":")?;
+        match &self.error {
+            Some(error) => write_json_str(w, error)?,
+            None => w.write_str("null")?,
+        }
+        w.write_char('}')
+    }
+}
+
+/// Text format.
+impl<TW> fmt::Display for Trace<TW>
+where
+    TW: StringTbitWord,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_text(f)
+    }
+}
@@ -1,9 +1,12 @@
 use failure::{
+    bail,
     ensure,
     Fallible,
 };
 
+use super::Trace;
 use crate::types::{
+    Int,
     Size,
     Trint3,
     SIZE_MAX,
@@ -14,11 +17,26 @@ use iota_streams_core::tbits::TbitSliceMut;
 pub(crate) trait Unwrap<TW> {
     fn unwrap3(&mut self, trint3: &mut Trint3) -> Fallible<&mut Self>;
     fn unwrapn(&mut self, trits: TbitSliceMut<TW>) -> Fallible<&mut Self>;
+    fn trace(&mut self) -> Option<&mut Trace<TW>>;
+}
+
+/// Trace trint3s unwrapped until `end_field` as a single field.
+fn begin_field<TW, Ctx: Unwrap<TW>>(ctx: &mut Ctx) {
+    if let Some(trace) = ctx.trace() {
+        trace.begin();
+    }
+}
+
+fn end_field<TW, Ctx: Unwrap<TW>>(ctx: &mut Ctx) {
+    if let Some(trace) = ctx.trace() {
+        trace.end();
+    }
 }
 
 /// Helper function for unwrapping (decoding/absorbing) size values.
 pub(crate) fn unwrap_size<'a, TW, Ctx: Unwrap<TW>>(ctx: &'a mut Ctx, size: &mut Size) -> Fallible<&'a mut Ctx> where
 {
+    begin_field(ctx);
     let mut d = Trint3(0);
     ctx.unwrap3(&mut d)?;
     ensure!(Trint3(0) <= d && d <= Trint3(13), "Invalid size of `size_t`: {}.", d);
@@ -48,5 +66,56 @@ pub(crate) fn unwrap_size<'a, TW, Ctx: Unwrap<TW>>(ctx: &'a mut Ctx, size: &mut
     }
 
     size.0 = m as usize;
+    end_field(ctx);
+    Ok(ctx)
+}
+
+/// Helper function for unwrapping (decoding/absorbing) fixed-width integers.
+pub(crate) fn unwrap_int<'a, TW, Ctx: Unwrap<TW>, T: Int>(ctx: &'a mut Ctx, int: &mut T) -> Fallible<&'a mut Ctx> {
+    let mut n: i128 = 0;
+    let mut r: i128 = 1;
+    let mut t = Trint3(0);
+    begin_field(ctx);
+    for _ in 0..T::TRYTES {
+        ctx.unwrap3(&mut t)?;
+        n += r * t.0 as i128;
+        r *= 27;
+    }
+    end_field(ctx);
+    match T::from_i128(n) {
+        Some(v) => *int = v,
+        None => bail!("Integer value is out of range: {}.", n),
+    }
+    Ok(ctx)
+}
+
+/// Helper function for unwrapping (decoding/absorbing) bytes.
+///
+/// Bytes are decoded one by one as `u8`, see `wrap_bytes` for why `tbits::binary` conversion
+/// is not used.
+pub(crate) fn unwrap_bytes<'a, TW, Ctx: Unwrap<TW>>(ctx: &'a mut Ctx, bytes: &mut [u8]) -> Fallible<&'a mut Ctx> {
+    begin_field(ctx);
+    for byte in bytes.iter_mut() {
+        unwrap_int(ctx, byte)?;
+    }
+    end_field(ctx);
+    Ok(ctx)
+}
+
+/// Helper function for unwrapping (decoding/absorbing) `n` bytes into `bytes`. The buffer grows
+/// while bytes are decoded so that a bad `n` fails at the end of the stream instead of allocating.
+pub(crate) fn unwrap_bytes_vec<'a, TW, Ctx: Unwrap<TW>>(
+    ctx: &'a mut Ctx,
+    n: usize,
+    bytes: &mut Vec<u8>,
+) -> Fallible<&'a mut Ctx> {
+    bytes.clear();
+    begin_field(ctx);
+    for _ in 0..n {
+        let mut byte = 0_u8;
+        unwrap_int(ctx, &mut byte)?;
+        bytes.push(byte);
+    }
+    end_field(ctx);
     Ok(ctx)
 }
@@ -13,7 +13,10 @@ use crate::{
     io,
     types::{
         AbsorbFallback,
+        Bytes,
         Fallback,
+        Int,
+        NBytes,
         NTrytes,
         Size,
         Trint3,
@@ -94,6 +97,26 @@ where
 {
     ctx.wrapn(trits)
 }
+fn wrap_absorb_int<TW, F, OS: io::OStream<TW>, T: Int>(
+    ctx: &mut AbsorbContext<TW, F, OS>,
+    int: T,
+) -> Fallible<&mut AbsorbContext<TW, F, OS>>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    wrap_int(ctx, int)
+}
+fn wrap_absorb_bytes<'a, TW, F, OS: io::OStream<TW>>(
+    ctx: &'a mut AbsorbContext<TW, F, OS>,
+    bytes: &[u8],
+) -> Fallible<&'a mut AbsorbContext<TW, F, OS>>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    wrap_bytes(ctx, bytes)
+}
 
 impl<'a, TW, F, OS: io::OStream<TW>> Absorb<&'a Trint3> for Context<TW, F, OS>
 where
@@ -156,6 +179,37 @@ where
     }
 }
 
+impl<'a, TW, F, OS: io::OStream<TW>, T: Int> Absorb<&'a T> for Context<TW, F, OS>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    fn absorb(&mut self, int: &'a T) -> Fallible<&mut Self> {
+        Ok(wrap_absorb_int(self.as_mut(), *int)?.as_mut())
+    }
+}
+
+impl<'a, TW, F, OS: io::OStream<TW>> Absorb<&'a Bytes> for Context<TW, F, OS>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    fn absorb(&mut self, bytes: &'a Bytes) -> Fallible<&mut Self> {
+        self.absorb(&Size((bytes.0).len()))?;
+        Ok(wrap_absorb_bytes(self.as_mut(), &bytes.0)?.as_mut())
+    }
+}
+
+impl<'a, TW, F, OS: io::OStream<TW>, const N: usize> Absorb<&'a NBytes<N>> for Context<TW, F, OS>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    fn absorb(&mut self, nbytes: &'a NBytes<N>) -> Fallible<&mut Self> {
+        Ok(wrap_absorb_bytes(self.as_mut(), &nbytes.0)?.as_mut())
+    }
+}
+
 impl<'a, TW, F, OS: io::OStream<TW>, P> Absorb<&'a mss::PublicKey<TW, P>> for Context<TW, F, OS>
 where
     TW: SpongosTbitWord + trinary::TritWord,
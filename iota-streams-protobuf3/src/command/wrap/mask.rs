@@ -12,6 +12,9 @@ use crate::{
     command::Mask,
     io,
     types::{
+        Bytes,
+        Int,
+        NBytes,
         NTrytes,
         Size,
         Trint3,
@@ -91,6 +94,26 @@ where
 {
     ctx.wrapn(trits)
 }
+fn wrap_mask_int<TW, F, OS: io::OStream<TW>, T: Int>(
+    ctx: &mut MaskContext<TW, F, OS>,
+    int: T,
+) -> Fallible<&mut MaskContext<TW, F, OS>>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    wrap_int(ctx, int)
+}
+fn wrap_mask_bytes<'a, TW, F, OS: io::OStream<TW>>(
+    ctx: &'a mut MaskContext<TW, F, OS>,
+    bytes: &[u8],
+) -> Fallible<&'a mut MaskContext<TW, F, OS>>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    wrap_bytes(ctx, bytes)
+}
 
 impl<'a, TW, F, OS: io::OStream<TW>> Mask<&'a Trint3> for Context<TW, F, OS>
 where
@@ -139,6 +162,37 @@ where
     }
 }
 
+impl<'a, TW, F, OS: io::OStream<TW>, T: Int> Mask<&'a T> for Context<TW, F, OS>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    fn mask(&mut self, int: &'a T) -> Fallible<&mut Self> {
+        Ok(wrap_mask_int(self.as_mut(), *int)?.as_mut())
+    }
+}
+
+impl<'a, TW, F, OS: io::OStream<TW>> Mask<&'a Bytes> for Context<TW, F, OS>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    fn mask(&mut self, bytes: &'a Bytes) -> Fallible<&mut Self> {
+        self.mask(&Size((bytes.0).len()))?;
+        Ok(wrap_mask_bytes(self.as_mut(), &bytes.0)?.as_mut())
+    }
+}
+
+impl<'a, TW, F, OS: io::OStream<TW>, const N: usize> Mask<&'a NBytes<N>> for Context<TW, F, OS>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    fn mask(&mut self, nbytes: &'a NBytes<N>) -> Fallible<&mut Self> {
+        Ok(wrap_mask_bytes(self.as_mut(), &nbytes.0)?.as_mut())
+    }
+}
+
 impl<'a, TW, F, OS: io::OStream<TW>> Mask<&'a ntru::PublicKey<TW, F>> for Context<TW, F, OS>
 where
     TW: SpongosTbitWord + trinary::TritWord,
@@ -38,6 +38,7 @@ mod fork;
 mod guard;
 mod join;
 mod mask;
+mod oneof;
 mod repeated;
 mod skip;
 mod squeeze;
@@ -0,0 +1,68 @@
+use failure::Fallible;
+
+use super::Context;
+use crate::{
+    command::{
+        Absorb,
+        Mask,
+        Oneof,
+        Optional,
+        Skip,
+    },
+    io,
+    types::{
+        OneofMode,
+        OneofTag,
+    },
+};
+use iota_streams_core::{
+    sponge::prp::PRP,
+    tbits::{
+        trinary,
+        word::SpongosTbitWord,
+    },
+};
+
+fn wrap_oneof_tag<TW, F, OS: io::OStream<TW>>(
+    ctx: &mut Context<TW, F, OS>,
+    mode: OneofMode,
+    tag: OneofTag,
+) -> Fallible<&mut Context<TW, F, OS>>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+{
+    let t = tag.to_trint3()?;
+    match mode {
+        OneofMode::Absorb => ctx.absorb(&t),
+        OneofMode::Mask => ctx.mask(&t),
+        OneofMode::Skip => ctx.skip(&t),
+    }
+}
+
+impl<TW, F, OS: io::OStream<TW>, C> Oneof<OneofTag, C> for Context<TW, F, OS>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+    C: for<'a> FnOnce(&'a mut Self, usize) -> Fallible<&'a mut Self>,
+{
+    fn oneof(&mut self, mode: OneofMode, tag: OneofTag, branch: C) -> Fallible<&mut Self> {
+        wrap_oneof_tag(self, mode, tag)?;
+        branch(self, tag.index)
+    }
+}
+
+impl<TW, F, OS: io::OStream<TW>, T, C> Optional<Option<T>, C> for Context<TW, F, OS>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+    C: for<'a> FnOnce(&'a mut Self, T) -> Fallible<&'a mut Self>,
+{
+    fn optional(&mut self, mode: OneofMode, value: Option<T>, some: C) -> Fallible<&mut Self> {
+        wrap_oneof_tag(self, mode, OneofTag::new(value.is_some() as usize, 2))?;
+        match value {
+            Some(value) => some(self, value),
+            None => Ok(self),
+        }
+    }
+}
@@ -9,7 +9,11 @@ use crate::{
     command::Skip,
     io,
     types::{
+        sizeof_sk_padding,
+        Bytes,
         Fallback,
+        Int,
+        NBytes,
         NTrytes,
         Size,
         SkipFallback,
@@ -21,7 +25,9 @@ use iota_streams_core::tbits::{
     trinary,
     word::BasicTbitWord,
     TbitSlice,
+    Tbits,
 };
+use iota_streams_core_ntru::key_encapsulation::ntru;
 
 struct SkipContext<TW, F, OS> {
     ctx: Context<TW, F, OS>,
@@ -80,6 +86,24 @@ where
 {
     ctx.wrapn(trits)
 }
+fn wrap_skip_int<TW, F, OS: io::OStream<TW>, T: Int>(
+    ctx: &mut SkipContext<TW, F, OS>,
+    int: T,
+) -> Fallible<&mut SkipContext<TW, F, OS>>
+where
+    TW: BasicTbitWord + trinary::TritWord,
+{
+    wrap_int(ctx, int)
+}
+fn wrap_skip_bytes<'a, TW, F, OS: io::OStream<TW>>(
+    ctx: &'a mut SkipContext<TW, F, OS>,
+    bytes: &[u8],
+) -> Fallible<&'a mut SkipContext<TW, F, OS>>
+where
+    TW: BasicTbitWord + trinary::TritWord,
+{
+    wrap_bytes(ctx, bytes)
+}
 
 impl<'a, TW, F, OS: io::OStream<TW>> Skip<&'a Trint3> for Context<TW, F, OS>
 where
@@ -136,9 +160,48 @@ where
     }
 }
 
+impl<'a, TW, F, OS: io::OStream<TW>, T: Int> Skip<&'a T> for Context<TW, F, OS>
+where
+    TW: BasicTbitWord + trinary::TritWord,
+{
+    fn skip(&mut self, int: &'a T) -> Fallible<&mut Self> {
+        Ok(wrap_skip_int(self.as_mut(), *int)?.as_mut())
+    }
+}
+
+impl<'a, TW, F, OS: io::OStream<TW>> Skip<&'a Bytes> for Context<TW, F, OS>
+where
+    TW: BasicTbitWord + trinary::TritWord,
+{
+    fn skip(&mut self, bytes: &'a Bytes) -> Fallible<&mut Self> {
+        self.skip(&Size((bytes.0).len()))?;
+        Ok(wrap_skip_bytes(self.as_mut(), &bytes.0)?.as_mut())
+    }
+}
+
+impl<'a, TW, F, OS: io::OStream<TW>, const N: usize> Skip<&'a NBytes<N>> for Context<TW, F, OS>
+where
+    TW: BasicTbitWord + trinary::TritWord,
+{
+    fn skip(&mut self, nbytes: &'a NBytes<N>) -> Fallible<&mut Self> {
+        Ok(wrap_skip_bytes(self.as_mut(), &nbytes.0)?.as_mut())
+    }
+}
+
 impl<'a, TW, F, T: 'a + SkipFallback<TW, F>, OS: io::OStream<TW>> Skip<&'a Fallback<T>> for Context<TW, F, OS> {
     fn skip(&mut self, val: &'a Fallback<T>) -> Fallible<&mut Self> {
         (val.0).wrap_skip(self)?;
         Ok(self)
     }
 }
+
+impl<'a, TW, F, OS: io::OStream<TW>> Skip<&'a ntru::PrivateKey<TW, F>> for Context<TW, F, OS>
+where
+    TW: BasicTbitWord + trinary::TritWord,
+{
+    fn skip(&mut self, sk: &'a ntru::PrivateKey<TW, F>) -> Fallible<&mut Self> {
+        let padding = Tbits::<TW>::zero(sizeof_sk_padding());
+        wrap_skip_trits(self.as_mut(), sk.tbits().slice())?;
+        Ok(wrap_skip_trits(self.as_mut(), padding.slice())?.as_mut())
+    }
+}
@@ -2,6 +2,7 @@ use failure::Fallible;
 
 use crate::types::{
     size_trytes,
+    Int,
     Size,
     Trint3,
 };
@@ -30,3 +31,27 @@ pub(crate) fn wrap_size<'a, TW, Ctx: Wrap<TW>>(ctx: &'a mut Ctx, size: Size) ->
     }
     Ok(ctx)
 }
+
+/// Helper function for wrapping (encoding/absorbing) fixed-width integers.
+pub(crate) fn wrap_int<TW, Ctx: Wrap<TW>, T: Int>(ctx: &mut Ctx, int: T) -> Fallible<&mut Ctx> {
+    let mut n = int.to_i128();
+    for _ in 0..T::TRYTES {
+        let r = (n + 13).rem_euclid(27) - 13;
+        ctx.wrap3(Trint3(r as i8))?;
+        n = (n - r) / 27;
+    }
+    Ok(ctx)
+}
+
+/// Helper function for wrapping (encoding/absorbing) bytes.
+///
+/// Each byte is encoded on its own as `u8`, ie. 2 trytes in balanced base 27. This deviates from
+/// the byte-to-trit conversion in `tbits::binary` which converts a whole binary string as one
+/// big integer: per-byte encoding keeps field sizes linear in the number of bytes and lets
+/// bytes be decoded one at a time from the stream, at the cost of 6 trits per byte instead of ~5.05.
+pub(crate) fn wrap_bytes<'a, TW, Ctx: Wrap<TW>>(ctx: &'a mut Ctx, bytes: &[u8]) -> Fallible<&'a mut Ctx> {
+    for byte in bytes {
+        wrap_int(ctx, *byte)?;
+    }
+    Ok(ctx)
+}
@@ -0,0 +1,177 @@
+//! Streams over `std::io` byte readers and writers, trits are packed 5 per byte (`B1T5` words).
+//!
+//! The byte representation is the same as of `trits_to_b1t5`: the last byte is padded with zero
+//! trits. A message can be written directly to a file or a socket and read back without being
+//! materialised in one buffer.
+
+use failure::{
+    bail,
+    ensure,
+    Fallible,
+};
+use std::{
+    io::{
+        self,
+        Read,
+        Write,
+    },
+    mem,
+};
+
+use iota_streams_core::tbits::{
+    trinary::{
+        trits_from_b1t5,
+        trits_to_b1t5,
+        TritWord,
+    },
+    TbitSlice,
+    TbitSliceMut,
+    Tbits,
+};
+
+use super::{
+    IStream,
+    OStream,
+};
+
+/// Output stream writing trits packed 5 per byte into `W`.
+///
+/// A slice is written on the next `try_advance` or `commit`, trits not filling a whole byte
+/// are kept until `finish`. Writer errors in `commit` are reported by the next `try_advance` or `finish`.
+pub struct WriteOStream<W, TW> {
+    writer: W,
+    /// Slice given by the last `try_advance`.
+    current: Option<Tbits<TW>>,
+    /// Trits not filling a whole byte.
+    rest: Tbits<TW>,
+    size: usize,
+    error: Option<io::Error>,
+}
+
+impl<W, TW> WriteOStream<W, TW>
+where
+    W: Write,
+    TW: TritWord,
+{
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            current: None,
+            rest: Tbits::zero(0),
+            size: 0,
+            error: None,
+        }
+    }
+
+    /// Total number of written trits.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    fn write_current(&mut self) -> io::Result<()> {
+        if let Some(current) = self.current.take() {
+            let mut trits = mem::replace(&mut self.rest, Tbits::zero(0));
+            trits += &current;
+            let n = trits.size() / 5 * 5;
+            self.writer.write_all(&trits_to_b1t5(trits.slice().take(n)))?;
+            self.rest = Tbits::from_slice(trits.slice().drop(n));
+        }
+        Ok(())
+    }
+
+    fn check_error(&mut self) -> Fallible<()> {
+        match self.error.take() {
+            Some(e) => Err(e.into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Write the remaining trits padding the last byte with zero trits, flush and return the writer.
+    pub fn finish(mut self) -> Fallible<W> {
+        self.check_error()?;
+        self.write_current()?;
+        if !self.rest.is_empty() {
+            self.writer.write_all(&trits_to_b1t5(self.rest.slice()))?;
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<W, TW> OStream<TW> for WriteOStream<W, TW>
+where
+    W: Write,
+    TW: TritWord,
+{
+    fn try_advance<'a>(&'a mut self, n: usize) -> Fallible<TbitSliceMut<'a, TW>> {
+        self.check_error()?;
+        self.write_current()?;
+        self.size += n;
+        Ok(self.current.get_or_insert(Tbits::zero(n)).slice_mut())
+    }
+
+    fn commit(&mut self) {
+        if self.error.is_none() {
+            if let Err(e) = self.write_current() {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    fn dump(&self) -> String {
+        format!("size={}", self.size)
+    }
+}
+
+/// Input stream reading trits packed 5 per byte from `R`.
+pub struct ReadIStream<R, TW> {
+    reader: R,
+    /// Trits of the last slice followed by trits of the last read byte.
+    buf: Tbits<TW>,
+    /// Size of the last slice in `buf`.
+    pos: usize,
+}
+
+impl<R, TW> ReadIStream<R, TW>
+where
+    R: Read,
+    TW: TritWord,
+{
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Tbits::zero(0),
+            pos: 0,
+        }
+    }
+
+    /// Check that padding trits of the last read byte are zero and return the reader.
+    pub fn finish(self) -> Fallible<R> {
+        let rest = self.buf.slice().drop(self.pos);
+        ensure!(rest == Tbits::zero(rest.size()).slice(), "Padding trits are not zero.");
+        Ok(self.reader)
+    }
+}
+
+impl<R, TW> IStream<TW> for ReadIStream<R, TW>
+where
+    R: Read,
+    TW: TritWord,
+{
+    fn try_advance<'a>(&'a mut self, n: usize) -> Fallible<TbitSlice<'a, TW>> {
+        let mut trits = Tbits::from_slice(self.buf.slice().drop(self.pos));
+        if trits.size() < n {
+            let mut bytes = vec![0; (n - trits.size() + 4) / 5];
+            self.reader.read_exact(&mut bytes)?;
+            match trits_from_b1t5::<TW>(5 * bytes.len(), &bytes) {
+                Some(t) => trits += &t,
+                None => bail!("Invalid B1T5 byte."),
+            }
+        }
+        self.buf = trits;
+        self.pos = n;
+        Ok(self.buf.slice().take(n))
+    }
+
+    fn commit(&mut self) {}
+}
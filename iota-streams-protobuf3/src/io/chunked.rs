@@ -0,0 +1,226 @@
+//! Streams over a sequence of fragments, eg. messages of Tangle transactions.
+//!
+//! Slices within a single fragment are accessed in place. Only slices spanning several fragments
+//! are copied into a temporary buffer, so a message is never materialised in one buffer.
+
+use failure::{
+    ensure,
+    Fallible,
+};
+use iota_streams_core::tbits::{
+    word::BasicTbitWord,
+    TbitSlice,
+    TbitSliceMut,
+    Tbits,
+};
+
+use super::{
+    IStream,
+    OStream,
+};
+
+/// Copy `x` into fragments `chunks` starting at position `pos` of the first fragment.
+/// Return the position in the last fragment after the copied trits.
+fn copy_to_chunks<TW>(mut x: TbitSlice<TW>, chunks: &mut [Tbits<TW>], mut pos: usize) -> usize
+where
+    TW: BasicTbitWord,
+{
+    for chunk in chunks {
+        let n = std::cmp::min(x.size(), chunk.size() - pos);
+        x.advance(n).copy(&chunk.slice_mut().drop(pos).take(n));
+        pos += n;
+        if x.is_empty() {
+            break;
+        }
+        pos = 0;
+    }
+    pos
+}
+
+/// Output stream writing into fragments of a fixed size.
+///
+/// A slice spanning fragments is buffered and copied into the fragments on the next `try_advance`
+/// or `commit`, `commit` must be called after the last slice has been written.
+pub struct ChunkedOStream<TW> {
+    chunk_size: usize,
+    chunks: Vec<Tbits<TW>>,
+    /// Position in the last fragment.
+    pos: usize,
+    /// Slice spanning fragments which is not yet copied.
+    pending: Option<Tbits<TW>>,
+}
+
+impl<TW> ChunkedOStream<TW>
+where
+    TW: BasicTbitWord,
+{
+    /// Create stream writing into fragments of `chunk_size` trits.
+    pub fn new(chunk_size: usize) -> Self {
+        assert!(0 < chunk_size, "Fragment size must be positive.");
+        Self {
+            chunk_size,
+            chunks: Vec::new(),
+            pos: 0,
+            pending: None,
+        }
+    }
+
+    /// Total number of written trits.
+    pub fn size(&self) -> usize {
+        match self.chunks.len() {
+            0 => 0,
+            n => (n - 1) * self.chunk_size + self.pos,
+        }
+    }
+
+    /// Committed fragments, the last fragment is zero-padded.
+    pub fn chunks(&self) -> &[Tbits<TW>] {
+        &self.chunks
+    }
+
+    /// Commit and return the fragments, the last fragment contains only the written trits.
+    pub fn into_chunks(mut self) -> Vec<Tbits<TW>> {
+        self.commit();
+        if self.pos < self.chunk_size {
+            if let Some(last) = self.chunks.pop() {
+                self.chunks.push(Tbits::from_slice(last.slice().take(self.pos)));
+            }
+        }
+        self.chunks
+    }
+
+    /// Number of trits available in the last fragment.
+    fn available(&self) -> usize {
+        match self.chunks.len() {
+            0 => 0,
+            _ => self.chunk_size - self.pos,
+        }
+    }
+}
+
+impl<TW> OStream<TW> for ChunkedOStream<TW>
+where
+    TW: BasicTbitWord,
+{
+    fn try_advance<'a>(&'a mut self, n: usize) -> Fallible<TbitSliceMut<'a, TW>> {
+        self.commit();
+
+        if !self.chunks.is_empty() && n <= self.available() {
+            let pos = self.pos;
+            self.pos += n;
+            let last = self.chunks.last_mut().unwrap();
+            return Ok(last.slice_mut().drop(pos).take(n));
+        }
+
+        if self.available() == 0 && 0 < n && n <= self.chunk_size {
+            self.chunks.push(Tbits::zero(self.chunk_size));
+            self.pos = n;
+            let last = self.chunks.last_mut().unwrap();
+            return Ok(last.slice_mut().take(n));
+        }
+
+        Ok(self.pending.get_or_insert(Tbits::zero(n)).slice_mut())
+    }
+
+    fn commit(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            if pending.is_empty() {
+                return;
+            }
+            if self.available() == 0 {
+                self.chunks.push(Tbits::zero(self.chunk_size));
+                self.pos = 0;
+            }
+            let first = self.chunks.len() - 1;
+            let size = self.pos + pending.size();
+            while (self.chunks.len() - first) * self.chunk_size < size {
+                self.chunks.push(Tbits::zero(self.chunk_size));
+            }
+            self.pos = copy_to_chunks(pending.slice(), &mut self.chunks[first..], self.pos);
+        }
+    }
+
+    fn dump(&self) -> String {
+        format!("chunks={} size={}", self.chunks.len(), self.size())
+    }
+}
+
+/// Input stream reading from fragments of arbitrary sizes.
+pub struct ChunkedIStream<'b, TW> {
+    chunks: &'b [Tbits<TW>],
+    /// Position in the first fragment.
+    pos: usize,
+    /// Buffer for the last slice spanning fragments.
+    buf: Tbits<TW>,
+}
+
+impl<'b, TW> ChunkedIStream<'b, TW>
+where
+    TW: BasicTbitWord,
+{
+    pub fn new(chunks: &'b [Tbits<TW>]) -> Self {
+        Self {
+            chunks,
+            pos: 0,
+            buf: Tbits::zero(0),
+        }
+    }
+
+    /// Number of remaining trits.
+    pub fn size(&self) -> usize {
+        self.chunks.iter().map(Tbits::size).sum::<usize>() - self.pos
+    }
+
+    /// Check whether all trits have been read.
+    pub fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+
+    /// Skip exhausted fragments.
+    fn next_chunk(&mut self) {
+        while let Some((first, rest)) = self.chunks.split_first() {
+            if self.pos < first.size() {
+                break;
+            }
+            self.chunks = rest;
+            self.pos = 0;
+        }
+    }
+}
+
+impl<'b, TW> IStream<TW> for ChunkedIStream<'b, TW>
+where
+    TW: BasicTbitWord,
+{
+    fn try_advance<'a>(&'a mut self, n: usize) -> Fallible<TbitSlice<'a, TW>> {
+        self.next_chunk();
+        if let Some(first) = self.chunks.first() {
+            if n <= first.size() - self.pos {
+                let slice = first.slice().drop(self.pos).take(n);
+                self.pos += n;
+                return Ok(slice);
+            }
+        }
+
+        ensure!(n <= self.size(), "Input chunks too short.");
+        let mut buf = Tbits::zero(n);
+        {
+            let mut slice = buf.slice_mut();
+            while !slice.is_empty() {
+                self.next_chunk();
+                let first = &self.chunks[0];
+                let k = std::cmp::min(slice.size(), first.size() - self.pos);
+                first.slice().drop(self.pos).take(k).copy(&slice.advance(k));
+                self.pos += k;
+            }
+        }
+        self.buf = buf;
+        Ok(self.buf.slice())
+    }
+
+    fn commit(&mut self) {}
+
+    fn dump(&self) -> String {
+        format!("chunks={} pos={}", self.chunks.len(), self.pos)
+    }
+}
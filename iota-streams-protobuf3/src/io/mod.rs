@@ -107,6 +107,12 @@ impl<TW> IStream<TW> for NoIStream {
     fn commit(&mut self) {}
 }
 
+mod chunked;
+pub use chunked::*;
+
+mod b1t5;
+pub use b1t5::*;
+
 #[cfg(test)]
 mod test {
     use super::*;
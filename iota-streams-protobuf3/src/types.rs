@@ -1,11 +1,13 @@
 use failure::{
     bail,
+    ensure,
     Fallible,
 };
 use std::{
     convert::{
         AsMut,
         AsRef,
+        TryFrom,
     },
     fmt,
     hash,
@@ -17,6 +19,7 @@ use iota_streams_core::{
         spongos::Spongos,
     },
     tbits::{
+        trinary,
         word::{
             BasicTbitWord,
             SpongosTbitWord,
@@ -222,17 +225,128 @@ pub fn sizeof_sizet(n: usize) -> usize {
     3 * (size_trytes(n) + 1)
 }
 
+/// Number of zero trits padding NTRU private key up to a multiple of 3 when it is `skip`ped.
+pub fn sizeof_sk_padding() -> usize {
+    (3 - iota_streams_core_ntru::key_encapsulation::ntru::PRIVATE_KEY_SIZE % 3) % 3
+}
+
 impl fmt::Display for Size {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "Size({})", self.0)
     }
 }
 
+/// Fixed-width integer types `u8`, `u16`, `u32`, `u64` and `i64`. A value is encoded with
+/// a fixed number of trytes in balanced base 27, the least significant tryte first.
+pub trait Int: Copy {
+    /// Number of trytes encoding a value.
+    const TRYTES: usize;
+
+    fn to_i128(self) -> i128;
+
+    /// Convert decoded value, `None` if it's out of range of the type.
+    fn from_i128(n: i128) -> Option<Self>;
+}
+
+macro_rules! impl_int {
+    ($($t:ty => $trytes:expr),*) => {
+        $(
+            impl Int for $t {
+                const TRYTES: usize = $trytes;
+
+                fn to_i128(self) -> i128 {
+                    self as i128
+                }
+
+                fn from_i128(n: i128) -> Option<Self> {
+                    <$t>::try_from(n).ok()
+                }
+            }
+        )*
+    };
+}
+
+// `(27^d - 1) / 2` must not be less than the max absolute value of the type.
+impl_int!(u8 => 2, u16 => 4, u32 => 7, u64 => 14, i64 => 14);
+
+/// PB3 `bytes` type, variable-size array of bytes. The number of bytes is encoded as `size_t`
+/// followed by the bytes, each byte is encoded as `u8`.
+///
+/// Note, the encoding is not compatible with `tbits::binary` conversion of byte strings into
+/// trits, a byte string converted with it must not be unwrapped as `Bytes` and vice versa.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Hash)]
+pub struct Bytes(pub Vec<u8>);
+
+/// PB3 `byte [N]` type, fixed-size array of bytes. The size is known at compile time and
+/// is not encoded, each byte is encoded as `u8`.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+pub struct NBytes<const N: usize>(pub [u8; N]);
+
+impl<const N: usize> Default for NBytes<N> {
+    fn default() -> Self {
+        Self([0; N])
+    }
+}
+
+/// Number of trits encoding `n` bytes.
+pub fn sizeof_bytes(n: usize) -> usize {
+    3 * u8::TRYTES * n
+}
+
 /// PB3 `external` modifier, it changes behaviour of commands in the following way.
 /// The external field is not encoded in trinary representation and the value is stored in the environment implicitly.
 #[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
 pub struct External<T>(pub T);
 
+/// Command processing the discriminant of PB3 `oneof`: `absorb oneof`, `mask oneof` or `skip oneof`.
+/// Fields of the chosen branch are processed with their own commands.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum OneofMode {
+    Absorb,
+    Mask,
+    Skip,
+}
+
+/// Max number of `oneof` branches, the discriminant is encoded as non-negative `tryte`.
+pub const ONEOF_MAX_BRANCHES: usize = 14;
+
+/// Discriminant of PB3 `oneof`: index of the chosen branch out of `n` branches.
+/// In Unwrap operation `index` is replaced with the decoded discriminant.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct OneofTag {
+    pub index: usize,
+    pub n: usize,
+}
+
+impl OneofTag {
+    pub fn new(index: usize, n: usize) -> Self {
+        Self { index, n }
+    }
+
+    /// Trinary representation of the discriminant.
+    pub fn to_trint3(&self) -> Fallible<Trint3> {
+        ensure!(
+            self.index < self.n && self.n <= ONEOF_MAX_BRANCHES,
+            "Bad oneof branch {} out of {}.",
+            self.index,
+            self.n
+        );
+        Ok(Trint3(self.index as i8))
+    }
+
+    /// Check decoded discriminant and set `index`.
+    pub fn set_trint3(&mut self, t: Trint3) -> Fallible<()> {
+        ensure!(
+            0 <= t.0 && (t.0 as usize) < self.n,
+            "Bad oneof discriminant {} for {} branches.",
+            t,
+            self.n
+        );
+        self.index = t.0 as usize;
+        Ok(())
+    }
+}
+
 /// The `link` type is generic and transport-specific. Links can be address+tag pair
 /// when messages are published in the Tangle. Or links can be a URL when HTTP is used.
 /// Or links can be a message sequence number in a stream/socket.
@@ -380,6 +494,8 @@ use crate::command::{
     sizeof,
     unwrap,
     wrap,
+    Repeated,
+    Skip,
 };
 
 pub struct Fallback<T>(pub T);
@@ -450,3 +566,59 @@ pub trait SkipFallback<TW, F> {
     fn wrap_skip<OS: io::OStream<TW>>(&self, ctx: &mut wrap::Context<TW, F, OS>) -> Fallible<()>;
     fn unwrap_skip<IS: io::IStream<TW>>(&mut self, ctx: &mut unwrap::Context<TW, F, IS>) -> Fallible<()>;
 }
+
+/// Link store is encoded as a list of links together with inner spongos states and
+/// associated info. This allows for the link store to be persisted.
+///
+/// ```pb3
+/// message DefaultLinkStore {
+///     skip size n;
+///     skip repeated(n) {
+///         skip link link;
+///         skip tryte inner[81];
+///         skip info info;
+///     }
+/// }
+/// ```
+impl<TW, F, Link, Info> SkipFallback<TW, F> for DefaultLinkStore<TW, F, Link, Info>
+where
+    TW: SpongosTbitWord + trinary::TritWord,
+    F: PRP<TW>,
+    F::Inner: AsRef<Tbits<TW>> + From<Tbits<TW>>,
+    Link: Eq + hash::Hash + Default + SkipFallback<TW, F>,
+    Info: Default + SkipFallback<TW, F>,
+{
+    fn sizeof_skip(&self, ctx: &mut sizeof::Context<TW, F>) -> Fallible<()> {
+        ctx.skip(Size(self.map.len()))?
+            .repeated(self.map.iter(), |ctx, (link, (inner, info))| {
+                ctx.skip(<&Fallback<Link>>::from(link))?
+                    .skip(&NTrytes(inner.as_ref().clone()))?
+                    .skip(<&Fallback<Info>>::from(info))
+            })?;
+        Ok(())
+    }
+    fn wrap_skip<OS: io::OStream<TW>>(&self, ctx: &mut wrap::Context<TW, F, OS>) -> Fallible<()> {
+        ctx.skip(Size(self.map.len()))?
+            .repeated(self.map.iter(), |ctx, (link, (inner, info))| {
+                ctx.skip(<&Fallback<Link>>::from(link))?
+                    .skip(&NTrytes(inner.as_ref().clone()))?
+                    .skip(<&Fallback<Info>>::from(info))
+            })?;
+        Ok(())
+    }
+    fn unwrap_skip<IS: io::IStream<TW>>(&mut self, ctx: &mut unwrap::Context<TW, F, IS>) -> Fallible<()> {
+        let mut repeated_links = Size(0);
+        let map = &mut self.map;
+        ctx.skip(&mut repeated_links)?.repeated(repeated_links, |ctx| {
+            let mut link = Link::default();
+            let mut inner = NTrytes::zero(F::CAPACITY);
+            let mut info = Info::default();
+            ctx.skip(<&mut Fallback<Link>>::from(&mut link))?
+                .skip(&mut inner)?
+                .skip(<&mut Fallback<Info>>::from(&mut info))?;
+            map.insert(link, (F::Inner::from(inner.0), info));
+            Ok(ctx)
+        })?;
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
 /// Streams Application layer definitions.
 pub use iota_streams_app as app;
+/// Macros generating Streams Message content from Protobuf3 definitions.
+pub use iota_streams_app_macros as app_macros;
 /// Streams Channel Application implementation.
 pub use iota_streams_app_channels as app_channels;
 /// Core binary/trinary and cryptographic tools.